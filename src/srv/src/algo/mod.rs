@@ -18,24 +18,19 @@
 //! [^deps]: [`Task`] `a` is &lt;a dependent of/dependant on&gt; [`Task`] `b` if `a`'s [`deps`](Task::deps)-field contains `b`.
 //! [^pref-mag]: A [`Preference`] is of higher magnitude when it is further from zero; i.e. [`f32::abs`]
 //!
+//! Equally preferred candidates are chosen in the order shuffled by [`SchedulerConfig::seed`], if set,
+//! and then by lowest [`UserId`], so the same data always generates the same schedule.
+//! See [`SchedulerConfig::tie_break`].
+//!
 //! TODO: consider [PERT](https://en.wikipedia.org/wiki/Program_evaluation_and_review_technique)
 
-<<<<<<< HEAD
-use crate::data::{Preference, Slot, Task, TaskId, TaskMap, User, UserId};
-use daggy::{Dag, Walker, WouldCycle};
-use miette::Result;
-use petgraph::visit::Topo;
-use rustc_hash::{FxHashMap, FxHashSet};
-use serde::{Deserialize, Serialize};
-=======
 use crate::data::*;
-use daggy::{Dag, Walker, WouldCycle};
+use chrono::{DateTime, TimeDelta, Utc};
+use daggy::{Dag, Walker};
 use miette::Result;
 use petgraph::visit::Topo;
-use rustc_hash::{FxBuildHasher, FxHashMap};
+use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
->>>>>>> 04a1808e76feb61ebfb644cf6eff190bd1c24f5a
 use thiserror::Error;
 
 /// Error generated while attempting to create a schedule.
@@ -48,10 +43,10 @@ pub enum SchedulingError {
     NonExistentTask(TaskId),
 
     /// Failed to construct a DAG due to existence of a cycle.
-    #[error("task dependencies cannot be cyclic")]
-    WouldCycle(#[from] WouldCycle<Vec<()>>),
-<<<<<<< HEAD
-=======
+    ///
+    /// Holds the tasks forming the cycle (see [`find_cycle`]).
+    #[error("task dependencies cannot be cyclic: {}", display_cycle(_0))]
+    WouldCycle(Vec<TaskId>),
 
     /// Schedule would break a [`Preference::INFINITY`]/[`Preference::NEG_INFINITY`] requirement.
     #[error("no schedule can be generated that does not break at least one +/-inf preference")]
@@ -60,7 +55,29 @@ pub enum SchedulingError {
     /// Not enough [`User`]s for the provided [`Slot`]s.
     #[error("insufficient users to cover shifts")]
     Understaffed,
->>>>>>> 04a1808e76feb61ebfb644cf6eff190bd1c24f5a
+
+    /// A task would be completed after its [hard deadline](Task::hard_deadline).
+    #[error("task {_0} would miss its hard deadline")]
+    MissedDeadline(TaskId),
+
+    /// A [pinned](Pins) user is forbidden from their slot by a [`Preference::NEG_INFINITY`] rule.
+    #[error("user {_1} is pinned to slot {_0} but cannot work it")]
+    InfeasiblePin(SlotId, UserId),
+
+    /// More users are [pinned](Pins) to a slot, or insisted on by the tasks worked during it,
+    /// than its [`Slot::max_staff`].
+    #[error("slot {_0} would be staffed past its max_staff")]
+    Overstaffed(SlotId),
+}
+
+/// Format a cycle from [`find_cycle`] as `a -> b -> a`.
+fn display_cycle(cycle: &[TaskId]) -> String {
+    cycle
+        .iter()
+        .chain(cycle.first())
+        .map(TaskId::to_string)
+        .collect::<Vec<_>>()
+        .join(" -> ")
 }
 
 type DepGraph<'a> = Dag<&'a Task, ()>;
@@ -72,9 +89,9 @@ type DepGraph<'a> = Dag<&'a Task, ()>;
 /// a task is dependent on a task that does not exist.
 ///
 /// # Errors
-/// This function may return an error if the dependencies contain
-/// cycles.
-pub fn dep_graph(dict: &TaskMap) -> Result<DepGraph<'_>, WouldCycle<Vec<()>>> {
+/// This function may return a [`SchedulingError::WouldCycle`] error if the
+/// dependencies contain cycles.
+pub fn dep_graph(dict: &TaskMap) -> Result<DepGraph<'_>, SchedulingError> {
     use std::iter::repeat_n;
 
     // tasks must create a DAG (no cycles)
@@ -91,268 +108,2105 @@ pub fn dep_graph(dict: &TaskMap) -> Result<DepGraph<'_>, WouldCycle<Vec<()>>> {
         dict.values()
             .flat_map(|Task { id, deps, .. }| repeat_n(id, deps.len()).zip(deps))
             .map(|(child, parent)| (key_indices[parent], key_indices[child], ())),
-    )?;
+    )
+    .map_err(|_| SchedulingError::WouldCycle(find_cycle(dict).unwrap_or_default()))?;
 
     Ok(g)
 }
 
+/// Cheaply check that the task dependencies contain no cycles, without building a [`DepGraph`].
+///
+/// Dependencies on tasks that do not exist are ignored.
+pub fn is_dag(dict: &TaskMap) -> bool {
+    find_cycle(dict).is_none()
+}
+
+/// Find a cycle in the task dependencies, if there are any.
+///
+/// Each task in the returned cycle depends on the next, and the last depends on the first.
+/// Dependencies on tasks that do not exist are ignored.
+pub fn find_cycle(dict: &TaskMap) -> Option<Vec<TaskId>> {
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum Mark {
+        /// On the current path
+        Grey,
+        /// Fully explored
+        Black,
+    }
+
+    let mut marks = TaskMap::<Mark>::default();
+    for &root in dict.keys() {
+        if marks.contains_key(&root) {
+            continue;
+        }
+        marks.insert(root, Mark::Grey);
+        let mut stack = vec![(root, dict[&root].deps.iter())];
+        while let Some((id, deps)) = stack.last_mut() {
+            match deps.next() {
+                Some(dep) if dict.contains_key(dep) => match marks.get(dep) {
+                    Some(Mark::Grey) => {
+                        // back edge; the path from `dep` to here is the cycle
+                        let path = stack.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+                        let start = path.iter().position(|id| id == dep)?;
+                        return Some(path[start..].to_vec());
+                    }
+                    Some(Mark::Black) => {}
+                    None => {
+                        marks.insert(*dep, Mark::Grey);
+                        stack.push((*dep, dict[dep].deps.iter()));
+                    }
+                },
+                Some(_) => {}
+                None => {
+                    marks.insert(*id, Mark::Black);
+                    stack.pop();
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Every `(dependent, dependency)` edge of a [`DepGraph`].
+pub fn dep_edges<'a>(graph: &DepGraph<'a>) -> impl Iterator<Item = (TaskId, TaskId)> {
+    graph
+        .raw_edges()
+        .iter()
+        .map(|edge| (graph[edge.target()].id, graph[edge.source()].id))
+}
+
 /// Creates a topological sorting iterator over a [`DepGraph`].
 pub fn dep_order<'a>(graph: &DepGraph<'a>) -> impl Iterator<Item = &'a Task> + Clone {
     Topo::new(graph).iter(graph).map(|i| graph[i])
 }
 
-/// A collection of time slots along with the tasks and users assigned to them.
-#[derive(Debug, Serialize, Deserialize)]
-<<<<<<< HEAD
-pub struct Schedule {
-    /// Timeslots and their assignments.
-    pub slots: Vec<(Slot, FxHashSet<TaskId>, FxHashSet<UserId>)>,
+/// A task's [soft](Task::soft_deadline) and [hard](Task::hard_deadline) deadlines,
+/// once any [relative deadline](Task::deadline_offset) is resolved. See [`resolve_deadlines`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Deadlines {
+    /// See [`Task::soft_deadline`]
+    pub soft: Option<DateTime<Utc>>,
+    /// See [`Task::hard_deadline`]
+    pub hard: Option<DateTime<Utc>>,
 }
-=======
-pub struct Schedule(pub SlotMap</* (TaskSet, */ UserSet /* ) */>);
->>>>>>> 04a1808e76feb61ebfb644cf6eff190bd1c24f5a
 
-impl Schedule {
-    /// Generate a schedule based on the provided requirements.
-    ///
-<<<<<<< HEAD
-    /// See [module-level documentation](crate::algo) for more details.
-    pub fn generate(
-        slots: &[Slot],
-        tasks: &TaskMap,
-        users: &FxHashMap<UserId, User>,
-    ) -> Result<Self, SchedulingError> {
-        let _deps = dep_graph(tasks)?;
-        // let ord = dep_order(&deps);
-        for slot in slots {
-            let mut candidates = users
+impl From<&Task> for Deadlines {
+    /// The task's absolute deadlines, ignoring any offset.
+    fn from(task: &Task) -> Self {
+        Self {
+            soft: task.soft_deadline,
+            hard: task.hard_deadline,
+        }
+    }
+}
+
+impl Deadlines {
+    /// The latest the task is expected to be completed by, like [`Task::deadline`].
+    #[inline]
+    pub fn latest(&self) -> Option<DateTime<Utc>> {
+        self.hard.or(self.soft)
+    }
+}
+
+/// The effective deadlines of every task in `graph`, resolving [relative deadlines](Task::deadline_offset).
+///
+/// Tasks are visited in dependency order. A task with an offset and dependencies is due `offset` after
+/// the latest of its dependencies finishes: the time it was placed to finish at in `finished`, if any,
+/// or else its own [latest](Deadlines::latest) effective deadline. That time takes the place of both of its deadlines.
+/// If any dependency has neither, the offset cannot be resolved and the task keeps its absolute deadlines,
+/// as do tasks without an offset.
+pub fn resolve_deadlines(
+    graph: &DepGraph<'_>,
+    finished: &TaskMap<DateTime<Utc>>,
+) -> TaskMap<Deadlines> {
+    let mut deadlines = TaskMap::<Deadlines>::default();
+    for task in dep_order(graph) {
+        let relative = task
+            .deadline_offset
+            .filter(|_| !task.deps.is_empty())
+            .and_then(|offset| {
+                task.deps
+                    .iter()
+                    .map(|dep| finished.get(dep).copied().or(deadlines[dep].latest()))
+                    .collect::<Option<Vec<_>>>()?
+                    .into_iter()
+                    .max()?
+                    .checked_add_signed(offset)
+            });
+        let resolved = relative.map_or_else(
+            || Deadlines::from(task),
+            |due| Deadlines {
+                soft: Some(due),
+                hard: Some(due),
+            },
+        );
+        deadlines.insert(task.id, resolved);
+    }
+    deadlines
+}
+
+/// [`resolve_deadlines`] for `tasks`, before any of them are placed.
+///
+/// If the dependencies cannot form a [`DepGraph`] (they contain a cycle, or a task that does not exist),
+/// no offsets are resolved and every task keeps its absolute deadlines.
+pub fn effective_deadlines(tasks: &TaskMap) -> TaskMap<Deadlines> {
+    deadlines_given(tasks, &TaskMap::default())
+}
+
+/// [`effective_deadlines`] once the [pinned tasks](Pins::tasks) are placed, as [`Schedule::generate_with`] holds them.
+/// See [`pinned_finishes`].
+pub fn pinned_deadlines(slots: &SlotMap, tasks: &TaskMap, pins: &Pins) -> TaskMap<Deadlines> {
+    deadlines_given(tasks, &pinned_finishes(slots, pins))
+}
+
+fn deadlines_given(tasks: &TaskMap, finished: &TaskMap<DateTime<Utc>>) -> TaskMap<Deadlines> {
+    let well_formed = tasks
+        .values()
+        .all(|task| task.deps.iter().all(|dep| tasks.contains_key(dep)));
+    match well_formed.then(|| dep_graph(tasks)) {
+        Some(Ok(graph)) => resolve_deadlines(&graph, finished),
+        _ => tasks
+            .values()
+            .map(|task| (task.id, Deadlines::from(task)))
+            .collect(),
+    }
+}
+
+/// When each [pinned](Pins::tasks) task finishes: at the end of the latest of `slots` it is pinned to.
+///
+/// Pins to slots that do not exist are ignored.
+pub fn pinned_finishes(slots: &SlotMap, pins: &Pins) -> TaskMap<DateTime<Utc>> {
+    let mut finished = TaskMap::<DateTime<Utc>>::default();
+    for (slot, tasks) in &pins.tasks {
+        let Some(slot) = slots.get(slot) else {
+            continue;
+        };
+        for &task in tasks {
+            let end = finished.entry(task).or_insert(slot.end);
+            *end = (*end).max(slot.end);
+        }
+    }
+    finished
+}
+
+/// The heaviest chain of dependencies in `graph`, weighing each task by `weight`:
+/// the chain that decides the least time in which every task can be finished.
+///
+/// Ordered from the first task to be done to the last. Among equally heavy chains,
+/// those through lower task IDs are preferred. Empty if `graph` is.
+pub fn critical_path<W>(graph: &DepGraph<'_>, weight: impl Fn(&Task) -> W) -> Vec<TaskId>
+where
+    W: Copy + Ord + std::ops::Add<Output = W>,
+{
+    let heavier = |(a, a_id): &(W, TaskId), (b, b_id): &(W, TaskId)| {
+        a.cmp(b).then_with(|| b_id.0.cmp(&a_id.0))
+    };
+
+    // the heaviest chain ending with each task, as its total weight and the task before it
+    let mut best = TaskMap::<(W, Option<TaskId>)>::default();
+    for task in dep_order(graph) {
+        let prev = task
+            .deps
+            .iter()
+            .map(|dep| (best[dep].0, *dep))
+            .max_by(heavier);
+        let total = prev.map_or(weight(task), |(total, _)| total + weight(task));
+        best.insert(task.id, (total, prev.map(|(_, dep)| dep)));
+    }
+
+    let mut next = best
+        .iter()
+        .map(|(id, (total, _))| (*total, *id))
+        .max_by(heavier)
+        .map(|(_, id)| id);
+    let mut path = Vec::new();
+    while let Some(id) = next {
+        path.push(id);
+        next = best[&id].1;
+    }
+    path.reverse();
+    path
+}
+
+/// Returns the dependencies of task `id`, direct or indirect, that are not yet satisfied as of `as_of`.
+///
+/// A dependency is considered satisfied once its [effective deadline](effective_deadlines) has passed.
+/// Dependencies without a deadline are never satisfied.
+/// The dependencies of a satisfied task are assumed to be satisfied as well.
+///
+/// # Errors
+/// This function may return an error if `id`, or any task it depends on, does not exist.
+pub fn blocking_tasks(
+    tasks: &TaskMap,
+    id: TaskId,
+    as_of: DateTime<Utc>,
+) -> Result<TaskSet, SchedulingError> {
+    let get = |id| tasks.get(&id).ok_or(SchedulingError::NonExistentTask(id));
+    let deadlines = effective_deadlines(tasks);
+    let mut blocking = TaskSet::default();
+    let mut stack = Vec::from_iter(&get(id)?.deps);
+    while let Some(&dep) = stack.pop() {
+        let task = get(dep)?;
+        let deadline = deadlines[&dep].latest();
+        if deadline.is_none_or(|deadline| deadline > as_of) && blocking.insert(dep) {
+            stack.extend(&task.deps);
+        }
+    }
+    Ok(blocking)
+}
+
+/// The shortest chain of dependencies leading from task `from` to task `to`, both included,
+/// such that each task in it [depends](Task::deps) on the next.
+///
+/// `[from]` if `from` and `to` are the same task. [`None`] if `from` does not depend on `to`,
+/// directly or indirectly. Dependencies that do not exist are skipped.
+pub fn dependency_path(tasks: &TaskMap, from: TaskId, to: TaskId) -> Option<Vec<TaskId>> {
+    // breadth-first, remembering how each task was first reached
+    let mut reached_from = TaskMap::<TaskId>::default();
+    let mut queue = std::collections::VecDeque::from([from]);
+    while let Some(id) = queue.pop_front() {
+        if id == to {
+            let mut path = vec![to];
+            let mut at = to;
+            while let Some(&prev) = reached_from.get(&at) {
+                path.push(prev);
+                at = prev;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &dep in tasks.get(&id).into_iter().flat_map(|task| &task.deps) {
+            if dep != from && tasks.contains_key(&dep) && !reached_from.contains_key(&dep) {
+                reached_from.insert(dep, id);
+                queue.push_back(dep);
+            }
+        }
+    }
+    None
+}
+
+/// The rule that decides `user`'s preference towards `interval`, out of all their rules covering the whole of it.
+///
+/// Rules layer: the rule with the [narrowest cover](Rule::narrowest_cover) is the most specific and wins over broader ones,
+/// so a narrow rule can override part of a broad one. Among equally specific rules, the preference of greatest magnitude wins.
+///
+/// A [forbidding](Preference::forbids) rule that [overlaps](Rule::overlap) any part of `interval` vetoes it,
+/// and so governs it even without covering the whole of it.
+///
+/// [`None`] if no rule covers `interval`.
+pub fn governing_rule<'a>(user: &'a User, interval: &TimeInterval) -> Option<&'a Rule> {
+    let veto = user
+        .availability
+        .values()
+        .filter(|r| r.pref.forbids() && r.overlap(interval) > TimeDelta::zero())
+        .min_by_key(|r| r.id.0);
+    if veto.is_some() {
+        return veto;
+    }
+    user.availability
+        .values()
+        .filter_map(|r| r.narrowest_cover(interval).map(|cover| (cover, r)))
+        .min_by(|(a_cover, a), (b_cover, b)| {
+            a_cover
+                .cmp(b_cover)
+                .then_with(|| b.pref.0.abs().total_cmp(&a.pref.0.abs()))
+                .then_with(|| a.id.0.cmp(&b.id.0))
+        })
+        .map(|(_, r)| r)
+}
+
+/// `user`'s preference towards `interval`: that of their [governing rule](governing_rule),
+/// or their [default](User::default_availability) if no rule covers it.
+///
+/// [`None`] if neither exists, meaning the user is unavailable.
+pub fn availability_pref(user: &User, interval: &TimeInterval) -> Option<Preference> {
+    governing_rule(user, interval)
+        .map(|r| r.pref)
+        .or(user.default_availability)
+}
+
+/// Whether `user`'s [preference](availability_pref) towards `interval` exists and does not forbid it.
+///
+/// This is what makes a user a candidate for a slot.
+pub fn is_available(user: &User, interval: &TimeInterval) -> bool {
+    availability_pref(user, interval).is_some_and(|pref| !pref.forbids())
+}
+
+/// How late completing task `task` at `finish` would be, past its [soft deadline](Deadlines::soft).
+///
+/// Zero if the task is on time or has no soft deadline. The lateness is the penalty to minimize.
+///
+/// # Errors
+/// This function returns an error if `finish` is after the task's [hard deadline](Deadlines::hard),
+/// as the task cannot be scheduled then.
+pub fn deadline_penalty(
+    task: TaskId,
+    deadlines: Deadlines,
+    finish: DateTime<Utc>,
+) -> Result<TimeDelta, SchedulingError> {
+    if deadlines.hard.is_some_and(|hard| finish > hard) {
+        return Err(SchedulingError::MissedDeadline(task));
+    }
+    Ok(deadlines.soft.map_or(TimeDelta::zero(), |soft| {
+        (finish - soft).max(TimeDelta::zero())
+    }))
+}
+
+/// Pick which of the `eligible` slots to do task `task` in.
+///
+/// The scheduler does not place tasks yet; this only decides which slot [`suggest_slots`] counts as serving a task.
+///
+/// Slots finishing after the task's [hard deadline](Deadlines::hard) are never chosen.
+/// Of the rest, slots listing the task in their [`Slot::preferred_tasks`] come first, so a preference only wins
+/// when it is feasible. Ties go to the slot with the least [lateness](deadline_penalty), then the earliest slot.
+/// Dependencies are the caller's responsibility.
+///
+/// [`None`] if no eligible slot can meet the deadline.
+pub fn choose_slot<'a>(
+    task: TaskId,
+    deadlines: Deadlines,
+    eligible: impl IntoIterator<Item = &'a Slot>,
+) -> Option<&'a Slot> {
+    eligible
+        .into_iter()
+        .filter_map(|slot| {
+            deadline_penalty(task, deadlines, slot.interval.end)
+                .ok()
+                .map(|penalty| (slot, penalty))
+        })
+        .min_by_key(|&(slot, penalty)| {
+            (
+                !slot.preferred_tasks.contains(&task),
+                penalty,
+                slot.interval.start,
+                slot.id.0,
+            )
+        })
+        .map(|(slot, _)| slot)
+}
+
+/// Whether `team` can meet the [minimum](ProficiencyReq::hard_min) of every skill `task` requires throughout `interval`,
+/// each member contributing at most [`Proficiency::ONE`] of each skill.
+///
+/// A task that requires no skills only needs someone to do it.
+pub fn is_qualified(task: &Task, team: &[&User], interval: &TimeInterval) -> bool {
+    if task.skills.is_empty() {
+        return !team.is_empty();
+    }
+    task.skills.iter().all(|(&skill, req)| {
+        let total = team
+            .iter()
+            .map(|user| user.proficiency(skill, interval).min(*Proficiency::ONE))
+            .sum::<f32>();
+        total >= *req.hard_min
+    })
+}
+
+/// The tasks worked during `slot`: its [preferred tasks](Slot::preferred_tasks) and [pinned tasks](Pins::tasks),
+/// ordered by title, then by ID.
+///
+/// Tasks that do not exist, or that the slot ends after the [hard deadline](Deadlines::hard) of in `deadlines`,
+/// are not worked during it and are skipped.
+pub fn worked_tasks<'a>(
+    slot: &Slot,
+    tasks: &'a TaskMap,
+    deadlines: &TaskMap<Deadlines>,
+    pins: &Pins,
+) -> Vec<&'a Task> {
+    let pinned = pins.tasks.get(&slot.id).into_iter().flatten();
+    let mut worked = slot
+        .preferred_tasks
+        .iter()
+        .chain(pinned.filter(|id| !slot.preferred_tasks.contains(id)))
+        .filter_map(|id| tasks.get(id))
+        .filter(|task| {
+            let deadlines = deadlines.get(&task.id).copied().unwrap_or_default();
+            deadline_penalty(task.id, deadlines, slot.end).is_ok()
+        })
+        .collect::<Vec<_>>();
+    worked.sort_by(|a, b| a.title.cmp(&b.title).then(a.id.0.cmp(&b.id.0)));
+    worked
+}
+
+/// How strongly the tasks worked during `slot` call for each user to be staffed on it,
+/// from the [preferred users](Task::preferred_users) of the slot's [preferred tasks](Slot::preferred_tasks)
+/// and [pinned tasks](Pins::tasks).
+///
+/// A user's preferences across those tasks are summed.
+/// [`Preference::NEG_INFINITY`] wins over anything else, so one task can always keep a user off the slot.
+/// See [`worked_tasks`].
+pub fn task_affinity(
+    slot: &Slot,
+    tasks: &TaskMap,
+    deadlines: &TaskMap<Deadlines>,
+    pins: &Pins,
+) -> UserMap<Preference> {
+    let mut affinity = UserMap::<Preference>::default();
+    for task in worked_tasks(slot, tasks, deadlines, pins) {
+        for (&user, &pref) in &task.preferred_users {
+            let sum = affinity.entry(user).or_default();
+            *sum = if sum.forbids() || pref.forbids() {
+                Preference::NEG_INFINITY
+            } else {
+                Preference(sum.0 + pref.0)
+            };
+        }
+    }
+    affinity
+}
+
+/// How far past `now` [`suggest_slots`] looks for tasks without a deadline.
+pub const SUGGESTION_HORIZON: TimeDelta = TimeDelta::weeks(4);
+
+/// Intervals to add slots in for the tasks that none of `slots` can serve, keyed by task.
+///
+/// A slot can serve a task if [`choose_slot`] would pick it out of the slots starting no earlier than `now`
+/// whose [available](is_available) users are [qualified](is_qualified) for the task.
+/// Tasks whose [effective deadline](effective_deadlines) has already passed are skipped.
+///
+/// Each suggestion is the earliest stretch of a user's availability rules, or of the overlap of two users' rules,
+/// between `now` and the task's effective deadline (or [`SUGGESTION_HORIZON`] later, without one),
+/// throughout which the available users are qualified. Tasks with no such stretch are left out.
+pub fn suggest_slots(
+    slots: &SlotMap,
+    tasks: &TaskMap,
+    users: &UserMap,
+    now: DateTime<Utc>,
+) -> TaskMap<TimeInterval> {
+    let serves = |task: &Task, interval: &TimeInterval| {
+        let team = users
+            .values()
+            .filter(|user| is_available(user, interval))
+            .collect::<Vec<_>>();
+        is_qualified(task, &team, interval)
+    };
+    let deadlines = effective_deadlines(tasks);
+
+    tasks
+        .values()
+        .filter_map(|task| {
+            let deadline = deadlines[&task.id].latest();
+            if deadline.is_some_and(|deadline| deadline <= now) {
+                return None;
+            }
+            let eligible = slots
                 .values()
-                .filter_map(|u| {
-                    u.availability
-                        .iter()
-                        .filter(|(t, _)| slot.interval.end < t.start || t.end < slot.interval.start)
-                        .reduce(|a, b| if a.1 <= b.1 { a } else { b })
-                        .map(|a| (u, a))
-                })
+                .filter(|slot| slot.start >= now && serves(task, &slot.interval));
+            if choose_slot(task.id, deadlines[&task.id], eligible).is_some() {
+                return None;
+            }
+
+            let horizon = TimeInterval {
+                start: now,
+                end: deadline.unwrap_or(now + SUGGESTION_HORIZON),
+            };
+            let stretches = users
+                .values()
+                .flat_map(|user| user.availability.values())
+                .filter(|rule| !rule.pref.forbids())
+                .flat_map(|rule| rule.covered(&horizon))
                 .collect::<Vec<_>>();
+            let overlaps = stretches
+                .iter()
+                .enumerate()
+                .flat_map(|(i, a)| stretches[i + 1..].iter().filter_map(|b| a.intersection(b)));
+            stretches
+                .iter()
+                .copied()
+                .chain(overlaps)
+                .filter(|stretch| serves(task, stretch))
+                .min_by_key(|stretch| (stretch.start, std::cmp::Reverse(stretch.end)))
+                .map(|stretch| (task.id, stretch))
+        })
+        .collect()
+}
 
-            candidates.sort_by(|(_, a), (_, b)| {
-                a.1.partial_cmp(&b.1)
-                    .expect("preference may be inf, but should never be NaN")
-            });
+/// The tasks that could still be worked during `interval`: those whose [effective deadline](effective_deadlines)
+/// is after it starts, and whose dependencies could all be complete by then.
+///
+/// A task could be complete by the end of the earliest of `slots` it could be worked during,
+/// by the same rule. Dependencies that do not exist, or that are part of a cycle, never complete.
+pub fn schedulable_tasks(slots: &SlotMap, tasks: &TaskMap, interval: &TimeInterval) -> TaskSet {
+    let deadlines = effective_deadlines(tasks);
+    let mut done_by = TaskMap::<DateTime<Utc>>::default();
+    let workable = |task: &Task, start: DateTime<Utc>, done_by: &TaskMap<DateTime<Utc>>| {
+        deadlines[&task.id]
+            .latest()
+            .is_none_or(|deadline| deadline > start)
+            && task
+                .deps
+                .iter()
+                .all(|dep| done_by.get(dep).is_some_and(|&done| done <= start))
+    };
+    // completion times only ever move earlier, so this settles within one pass per task
+    loop {
+        let mut changed = false;
+        for task in tasks.values() {
+            let earliest = slots
+                .values()
+                .filter(|slot| workable(task, slot.start, &done_by))
+                .map(|slot| slot.end)
+                .min();
+            if let Some(end) = earliest
+                && done_by.get(&task.id).is_none_or(|&done| end < done)
+            {
+                done_by.insert(task.id, end);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    tasks
+        .values()
+        .filter(|task| workable(task, interval.start, &done_by))
+        .map(|task| task.id)
+        .collect()
+}
+
+/// The least fraction of a slot a user must be available for to be a [partial candidate](SchedulerConfig::partial_pref).
+pub const MIN_PARTIAL_COVERAGE: f32 = 0.5;
+
+/// Weakening of stale preferences, so that old strong preferences do not dominate forever.
+///
+/// A finite [`Preference`] with a known [`Rule::set_at`] has its magnitude halved
+/// every `half_life` between when it was set and the start of the slot being staffed.
+/// Infinities never decay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Decay {
+    /// How long it takes a preference to lose half of its magnitude.
+    #[serde(with = "crate::data::seconds")]
+    pub half_life: TimeDelta,
+}
+
+impl Decay {
+    /// The decayed value of `pref`, set at `set_at`, as of `at`.
+    pub fn apply(&self, pref: Preference, set_at: DateTime<Utc>, at: DateTime<Utc>) -> Preference {
+        if pref.is_infinite() || self.half_life <= TimeDelta::zero() {
+            return pref;
+        }
+        // preferences stated after the fact are as fresh as can be
+        let age = (at - set_at).max(TimeDelta::zero());
+        let half_lives = age.as_seconds_f64() / self.half_life.as_seconds_f64();
+        Preference(pref.0 * 0.5f32.powf(half_lives as f32))
+    }
+}
+
+/// Assignments a manager has confirmed, which generation must keep.
+///
+/// Pins referring to slots, users, or tasks that no longer exist are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pins {
+    /// Users that must be staffed on each slot, in addition to any others needed to meet [`Slot::min_staff`].
+    pub users: SlotMap<UserSet>,
+
+    /// Tasks that must be worked on during each slot.
+    ///
+    /// The scheduler does not assign tasks to slots yet, so these only [bias staffing](task_affinity),
+    /// and fail generation if the slot ends after a task's [hard deadline](Deadlines::hard).
+    pub tasks: SlotMap<TaskSet>,
+}
+
+/// Optional adjustments to how a schedule is generated.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchedulerConfig {
+    /// See [`Decay`]. [`None`] to never decay.
+    pub decay: Option<Decay>,
+
+    /// Whether to [normalize](SchedulerConfig::normalized) each user's preferences
+    /// before comparing them with other users'.
+    #[serde(default)]
+    pub normalize: bool,
+
+    /// The least time a user must have off between the end of one slot they work and the start of the next.
+    /// [`None`] to allow back-to-back slots.
+    ///
+    /// [Pinned](Pins::users) users are staffed regardless, but still count against the rest of their slots.
+    #[serde(default, with = "crate::data::seconds::option")]
+    pub min_rest: Option<TimeDelta>,
+
+    /// Shuffles the order in which equally preferred candidates are chosen.
+    /// The same seed on the same data always generates the same schedule.
+    /// [`None`] to prefer lower [`UserId`]s.
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    /// See [`Pins`].
+    #[serde(default)]
+    pub pins: Pins,
+}
+
+impl SchedulerConfig {
+    /// The preference `rule` carries as of `at`.
+    pub fn effective_pref(&self, rule: &Rule, at: DateTime<Utc>) -> Preference {
+        match (self.decay, rule.set_at) {
+            (Some(decay), Some(set_at)) => decay.apply(rule.pref, set_at, at),
+            _ => rule.pref,
+        }
+    }
+
+    /// How strongly `user` is suited to `interval` when only some of their rules
+    /// [overlap](Rule::overlap) it, with none covering the whole of it.
+    ///
+    /// Each overlapping rule is scored as `(overlap / interval duration) * preference`, and the best score is used.
+    /// Rules overlapping less than [`MIN_PARTIAL_COVERAGE`] of the interval are ignored.
+    ///
+    /// [`None`] if no rule overlaps enough, or if any [`Preference::NEG_INFINITY`] rule overlaps at all.
+    pub fn partial_pref(&self, user: &User, interval: &TimeInterval) -> Option<Preference> {
+        let duration = (interval.end - interval.start).as_seconds_f64();
+        if duration <= 0.0 {
+            return None;
+        }
+        let mut best = None::<Preference>;
+        for rule in user.availability.values() {
+            let fraction = (rule.overlap(interval).as_seconds_f64() / duration) as f32;
+            if rule.pref.forbids() {
+                if fraction > 0.0 {
+                    return None;
+                }
+            } else if fraction >= MIN_PARTIAL_COVERAGE {
+                let score = Preference(fraction * self.effective_pref(rule, interval.start).0);
+                best = best.max(Some(score));
+            }
+        }
+        best
+    }
 
-            // TODO
+    /// Where `user` falls among equally preferred candidates, lowest first:
+    /// shuffled under [`SchedulerConfig::seed`], if any, then by ID,
+    /// so that ties never depend on the order users are stored in.
+    pub fn tie_break(&self, user: UserId) -> (Option<u64>, u64) {
+        use std::hash::{Hash, Hasher};
+
+        let shuffled = self.seed.map(|seed| {
+            let mut hasher = rustc_hash::FxHasher::default();
+            (seed, user).hash(&mut hasher);
+            hasher.finish()
+        });
+        (shuffled, user.0)
+    }
+
+    /// Whether a user already staffed on `assigned` has enough [rest](SchedulerConfig::min_rest)
+    /// on either side of them to also work `interval`.
+    pub fn is_rested(&self, assigned: &[TimeInterval], interval: &TimeInterval) -> bool {
+        let Some(min_rest) = self.min_rest else {
+            return true;
+        };
+        assigned.iter().all(|other| {
+            // gap between the two, whichever comes first; negative if they overlap
+            let gap = (interval.start - other.end).max(other.start - interval.end);
+            gap >= min_rest
+        })
+    }
+
+    /// `pref` rescaled relative to the rest of `user`'s preferences, if [`SchedulerConfig::normalize`] is set.
+    ///
+    /// Finite preferences are divided by the greatest magnitude among `user`'s finite rule preferences
+    /// and [default](User::default_availability), so that a user who only uses `0.1..=0.5` competes
+    /// on equal terms with one who uses `0.2..=1.0`. Infinities are returned unchanged.
+    pub fn normalized(&self, user: &User, pref: Preference) -> Preference {
+        if !self.normalize || !pref.0.is_finite() {
+            return pref;
+        }
+        let scale = user
+            .availability
+            .values()
+            .map(|rule| rule.pref)
+            .chain(user.default_availability)
+            .filter(|pref| pref.0.is_finite())
+            .map(|pref| pref.0.abs())
+            .fold(0.0, f32::max);
+        if scale > 0.0 {
+            Preference(pref.0 / scale)
+        } else {
+            pref
         }
+    }
+}
+
+/// Why a [`User`] was or was not staffed on a [`Slot`]. See [`Schedule::explain`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CandidateExplanation {
+    /// The user's [preference](availability_pref) for the slot.
+    /// [`None`] if they are unavailable, or if their preference forbids it.
+    pub preference: Option<Preference>,
+
+    /// Whether the user's governing rule for the slot exists and does not forbid it.
+    /// Only available users are candidates for the slot.
+    pub available: bool,
+
+    /// The user's [`Preference::NEG_INFINITY`] rules overlapping any part of the slot,
+    /// each of which forbids it.
+    pub forbidden_by: Vec<RuleId>,
+
+    /// Whether the user was staffed on the slot.
+    pub selected: bool,
+}
+
+/// The reasoning behind the staffing of a [`Slot`]. See [`Schedule::explain`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlotExplanation {
+    /// The slot being explained.
+    pub slot: SlotId,
 
-        todo!()
-=======
+    /// The reasoning for every user, whether or not they were a candidate.
+    pub candidates: UserMap<CandidateExplanation>,
+}
+
+/// A collection of time slots along with the tasks and users assigned to them.
+///
+/// Schedules are equal when every slot has the same staff, regardless of the order they were assigned in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Schedule(pub SlotMap</* (TaskSet, */ UserSet /* ) */>);
+
+impl Schedule {
+    /// Generate a schedule based on the provided requirements.
+    ///
     /// See [module-level documentation](self) for more details.
     pub fn generate(
         slots: &SlotMap,
         tasks: &TaskMap,
         users: &UserMap,
     ) -> Result<Self, SchedulingError> {
-        let _deps = dep_graph(tasks)?;
-        // let ord = dep_order(&deps);
+        Self::generate_with(slots, tasks, users, &SchedulerConfig::default())
+    }
 
-        let mut _slot_candidates = slots
-            .iter()
-            .map(|(slot_id, slot)| {
-                let interval = &slot.interval;
-                let candidates = users
-                    .values()
-                    .filter_map(|u| {
-                        let mut it = u
-                            .availability
-                            .values()
-                            .filter(|r| r.pref > Preference::NEG_INFINITY && r.contains(interval))
-                            .peekable();
+    /// [`Self::generate`], with adjustments.
+    ///
+    /// Tasks are held to their [effective deadlines](resolve_deadlines), with each [pinned](Pins::tasks) task
+    /// finishing at the end of its slot (see [`pinned_deadlines`]). A task pinned to a slot
+    /// that ends after its hard deadline makes generation fail, while a [preferred task](Slot::preferred_tasks)
+    /// past its hard deadline is simply not worked during the slot. Finishing after a soft deadline is allowed.
+    pub fn generate_with(
+        slots: &SlotMap,
+        tasks: &TaskMap,
+        users: &UserMap,
+        config: &SchedulerConfig,
+    ) -> Result<Self, SchedulingError> {
+        let deadlines = resolve_deadlines(&dep_graph(tasks)?, &pinned_finishes(slots, &config.pins));
 
-                        it.peek()
-                            .is_some()
-                            .then(|| (u.id, it.map(|r| (r.pref, r)).collect()))
+        // chronological, so rest is enforced in the order slots are worked
+        let mut ordered = slots.iter().collect::<Vec<_>>();
+        ordered.sort_by_key(|(slot_id, slot)| (slot.interval, slot_id.0));
+        let mut assigned = UserMap::<Vec<TimeInterval>>::default();
+
+        ordered
+            .into_iter()
+            .map(|(slot_id, slot)| {
+                let pinned = config
+                    .pins
+                    .users
+                    .get(slot_id)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|id| users.get(id))
+                    .map(|u| {
+                        let forbidden =
+                            availability_pref(u, &slot.interval).is_some_and(Preference::forbids);
+                        if forbidden {
+                            Err(SchedulingError::InfeasiblePin(*slot_id, u.id))
+                        } else {
+                            Ok(u.id)
+                        }
                     })
-                    .collect();
+                    .collect::<Result<UserSet, _>>()?;
 
-                (*slot_id, candidates)
-            })
-            .collect::<SlotMap<UserMap<BTreeMap<Preference, &Rule>>>>();
+                let is_rested = |u: &&User| {
+                    config.is_rested(
+                        assigned.get(&u.id).map_or(&[], Vec::as_slice),
+                        &slot.interval,
+                    )
+                };
+
+                for id in config.pins.tasks.get(slot_id).into_iter().flatten() {
+                    if let Some(&deadlines) = deadlines.get(id) {
+                        deadline_penalty(*id, deadlines, slot.end)?;
+                    }
+                }
+
+                let affinity = task_affinity(slot, tasks, &deadlines, &config.pins);
+                let is_allowed =
+                    |u: &&User| !affinity.get(&u.id).is_some_and(|pref| pref.forbids());
+                let biased = |u: &User, pref: Preference| match affinity.get(&u.id) {
+                    Some(bias) if bias.0.is_finite() => Preference(pref.0 + bias.0),
+                    _ => pref,
+                };
+                let is_forced = |u: &User| affinity.get(&u.id) == Some(&Preference::INFINITY);
+
+                // a slot without a minimum is only an opportunity to work on tasks, not a shift to cover,
+                // so nobody is staffed on it besides pinned users and those a worked task insists on
+                let Some(min_staff) = slot.min_staff else {
+                    let mut staff = pinned;
+                    staff.extend(
+                        users
+                            .values()
+                            .filter(|u| is_forced(u))
+                            .filter(is_rested)
+                            .filter(|u| is_available(u, &slot.interval))
+                            .map(|u| u.id),
+                    );
+                    if slot.max_staff.is_some_and(|max| staff.len() > max.get()) {
+                        return Err(SchedulingError::Overstaffed(*slot_id));
+                    }
+                    for user in &staff {
+                        assigned.entry(*user).or_default().push(slot.interval);
+                    }
+                    return Ok((*slot_id, staff));
+                };
 
-        slots
-            .iter()
-            .map(|(slot_id, slot)| {
                 let mut candidates = users
                     .values()
+                    .filter(|u| !pinned.contains(&u.id))
+                    .filter(is_rested)
+                    .filter(is_allowed)
                     .filter_map(|u| {
-                        let mut it = u
-                            .availability
-                            .values()
-                            .filter(|r| {
-                                r.pref > Preference::NEG_INFINITY && r.contains(&slot.interval)
-                            })
-                            .map(|r| (r.pref, r))
-                            .peekable();
-
-                        it.peek().is_some().then(|| (u, it.collect()))
+                        governing_rule(u, &slot.interval)
+                            .map(|r| config.effective_pref(r, slot.start))
+                            .or(u.default_availability)
+                            .filter(|pref| !pref.forbids())
+                            .map(|pref| (u, biased(u, config.normalized(u, pref))))
                     })
-                    .collect::<Vec<(&User, BTreeMap<Preference, &Rule>)>>();
+                    .collect::<Vec<(&User, Preference)>>();
+
+                // fall back on partially available users only to make up a shortfall
+                let shortfall = min_staff
+                    .get()
+                    .saturating_sub(pinned.len())
+                    .saturating_sub(candidates.len());
+                if shortfall > 0 {
+                    let mut partial = users
+                        .values()
+                        .filter(|u| !pinned.contains(&u.id))
+                        .filter(is_rested)
+                        .filter(is_allowed)
+                        .filter(|u| availability_pref(u, &slot.interval).is_none())
+                        .filter_map(|u| {
+                            config
+                                .partial_pref(u, &slot.interval)
+                                .map(|pref| (u, biased(u, config.normalized(u, pref))))
+                        })
+                        .collect::<Vec<(&User, Preference)>>();
+                    partial.sort_by_key(|&(u, pref)| {
+                        (std::cmp::Reverse(pref), config.tie_break(u.id))
+                    });
+                    partial.truncate(shortfall);
+                    candidates.extend(partial);
+                }
+
+                // users a worked task insists on are staffed whenever they are candidates
+                let (forced, mut candidates) = candidates
+                    .into_iter()
+                    .partition::<Vec<_>, _>(|(u, _)| is_forced(u));
 
                 let staff = 'staff: {
-                    let mut staff = if let Some(min_staff) = slot.min_staff {
+                    // pinned and forced users count towards the minimum regardless of preference
+                    let mut staff = pinned;
+                    staff.extend(forced.into_iter().map(|(user, _)| user.id));
+                    let n = {
                         use std::cmp::Ordering;
-                        let n = min_staff.get();
+                        let n = min_staff.get().saturating_sub(staff.len());
                         match candidates.len().cmp(&n) {
-                            Ordering::Greater => {
-                                UserSet::with_capacity_and_hasher(n, FxBuildHasher)
-                            }
+                            Ordering::Greater => n,
 
                             Ordering::Equal => {
                                 // don't need to sort if we're taking all of them
-                                break 'staff candidates
-                                    .into_iter()
-                                    .map(|(user, _)| user.id)
-                                    .collect();
+                                staff.extend(candidates.into_iter().map(|(user, _)| user.id));
+                                break 'staff staff;
                             }
 
                             Ordering::Less => return Err(SchedulingError::Understaffed),
                         }
-                    } else {
-                        Default::default()
                     };
 
-                    candidates.sort_by_cached_key(|(_, prefs)| {
-                        std::cmp::Reverse(
-                            *prefs
-                                .first_key_value() // maximum preference
-                                .expect("candidates are filtered by overlap with this slot")
-                                .0,
-                        )
+                    candidates.sort_by_key(|&(u, pref)| {
+                        (std::cmp::Reverse(pref), config.tie_break(u.id))
                     });
 
-                    if let Some(min_staff) = slot.min_staff {
-                        staff.extend(
-                            candidates
-                                .split_off(min_staff.get())
-                                .into_iter()
-                                .map(|(user, _)| user.id),
-                        );
-                    }
-
+                    candidates.truncate(n);
+                    staff.extend(candidates.into_iter().map(|(user, _)| user.id));
                     staff
                 };
+                if slot.max_staff.is_some_and(|max| staff.len() > max.get()) {
+                    return Err(SchedulingError::Overstaffed(*slot_id));
+                }
 
+                for user in &staff {
+                    assigned.entry(*user).or_default().push(slot.interval);
+                }
                 Ok((*slot_id, staff))
             })
             .collect::<Result<_, _>>()
             .map(Schedule)
->>>>>>> 04a1808e76feb61ebfb644cf6eff190bd1c24f5a
     }
-}
 
-#[cfg(test)]
-mod scheduler_tests {
-    use super::*;
-<<<<<<< HEAD
-    use chrono::prelude::{NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
-=======
-    use rustc_hash::FxHashSet;
->>>>>>> 04a1808e76feb61ebfb644cf6eff190bd1c24f5a
-
-    fn dbg_ord(dep_graph: &DepGraph<'_>) {
-        println!("task order:");
-        for (n, task) in dep_order(dep_graph).enumerate() {
-            println!(
-                "{n:>4}. {} ({}){}\n        deps: {{{}}}",
-                &task.title,
-                task.id,
-                match &task.deadline {
-                    Some(x) => format!("\n        deadline: {}", x.format("%b %d, %Y - %H:%M")),
-                    None => String::new(),
-                },
-                task.deps
-                    .iter()
-                    .map(|id| id.to_string())
-                    .collect::<Vec<_>>()
-                    .join(", ")
+    /// Explain why each user was or was not staffed on `slot`.
+    pub fn explain(&self, slot: &Slot, users: &UserMap) -> SlotExplanation {
+        let staff = self.0.get(&slot.id);
+        let candidates = users
+            .values()
+            .map(|user| {
+                let mut forbidden_by = user
+                    .availability
+                    .values()
+                    .filter(|r| r.pref.forbids() && r.overlap(&slot.interval) > TimeDelta::zero())
+                    .map(|r| r.id)
+                    .collect::<Vec<_>>();
+                forbidden_by.sort_by_key(|id| id.0);
+                let preference =
+                    availability_pref(user, &slot.interval).filter(|pref| !pref.forbids());
+                let explanation = CandidateExplanation {
+                    preference,
+                    available: preference.is_some(),
+                    forbidden_by,
+                    selected: staff.is_some_and(|staff| staff.contains(&user.id)),
+                };
+                (user.id, explanation)
+            })
+            .collect();
+
+        SlotExplanation {
+            slot: slot.id,
+            candidates,
+        }
+    }
+
+    /// Render the schedule for reading in a terminal.
+    ///
+    /// Each slot is listed with its name (or ID, if unnamed) and interval, followed by the titles of the tasks
+    /// [worked](worked_tasks) during it under `pins`, if any, and then the names of its staff.
+    /// Slots are ordered by interval (see [`TimeInterval` ordering](TimeInterval#ordering)) and staff by name.
+    /// Slots and users that are missing from `slots` and `users` are skipped.
+    pub fn render(&self, slots: &SlotMap, tasks: &TaskMap, users: &UserMap, pins: &Pins) -> String {
+        use std::fmt::Write;
+
+        const TIME_FMT: &str = "%Y-%m-%d %H:%M";
+        let mut assignments = self
+            .0
+            .iter()
+            .filter_map(|(slot_id, staff)| slots.get(slot_id).map(|slot| (slot, staff)))
+            .collect::<Vec<_>>();
+        assignments.sort_by_key(|(slot, _)| (slot.interval, slot.id.0));
+        let deadlines = pinned_deadlines(slots, tasks, pins);
+
+        let mut out = String::new();
+        for (slot, staff) in assignments {
+            let name = if slot.name.is_empty() {
+                slot.id.to_string()
+            } else {
+                slot.name.clone()
+            };
+            _ = writeln!(
+                out,
+                "{name} ({} - {})",
+                slot.start.format(TIME_FMT),
+                slot.end.format(TIME_FMT),
             );
+            let worked = worked_tasks(slot, tasks, &deadlines, pins);
+            if !worked.is_empty() {
+                let titles = worked
+                    .iter()
+                    .map(|task| task.title.as_str())
+                    .collect::<Vec<_>>();
+                _ = writeln!(out, "    tasks: {}", titles.join("; "));
+            }
+            let mut staff = staff
+                .iter()
+                .filter_map(|id| users.get(id))
+                .collect::<Vec<_>>();
+            if staff.is_empty() {
+                out.push_str("    (no staff)\n");
+            }
+            staff.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.0.cmp(&b.id.0)));
+            for user in staff {
+                _ = writeln!(out, "    {}", user.name);
+            }
         }
+        out
     }
 
-<<<<<<< HEAD
-    macro_rules! test_project {
-        ($(
-            $id:literal: $title:literal
-            $([$mo:literal/$d:literal/$yr:literal$( @ $hr:literal:$m:literal)?])?
-            { $($dep:literal),* $(,)? }
-        ),* $(,)?) => {
-            [$(Task {
-                id: TaskId($id),
-                title: $title.to_string(),
-                desc: String::new(),
-                skills: FxHashMap::default(),
-                deadline: None$(.or(Some(
-                    Utc.from_utc_datetime(
-                        &NaiveDateTime::new(
-                            NaiveDate::from_ymd_opt($yr, $mo, $d)
-                                .unwrap_or_else(|| panic!(
-                                    "`{}/{}/{}` is not a valid date",
-                                    $mo,
-                                    $d,
-                                    $yr,
-                                )),
-                            None$(.or(Some(NaiveTime::from_hms_opt($hr, $m, 0)
-                                .unwrap_or_else(|| panic!(
-                                    "`{}:{}` is not a valid time",
-                                    $hr,
-                                    $m,
-                                )))))?
-                                .unwrap_or(NaiveTime::default()),
-                        ),
-                    ))
-                ))?,
-                deps: FxHashSet::from_iter([$(TaskId($dep)),*]),
-            }),*]
-                .into_iter()
-                .map(|task| (task.id, task))
-                .collect()
-=======
-    macro_rules! hash_set {
-        ($($item:expr),* $(,)?) => {
-            FxHashSet::from_iter([$($item),*])
->>>>>>> 04a1808e76feb61ebfb644cf6eff190bd1c24f5a
-        };
+    /// Iterate over the slots `user` is scheduled for.
+    ///
+    /// Slots are yielded by id; look them up in the [`SlotMap`] the schedule was generated from.
+    pub fn assignments_for_user(&self, user: UserId) -> impl Iterator<Item = &SlotId> {
+        self.0
+            .iter()
+            .filter_map(move |(slot, staff)| staff.contains(&user).then_some(slot))
+    }
+
+    /// Find every user staffed on two slots whose intervals [overlap](TimeInterval::is_overlapping).
+    ///
+    /// Each double-booking is reported once as `(user, a, b)` with `a` having the lower slot ID,
+    /// ordered by user and then by slot IDs. Slots that are missing from `slots` are skipped.
+    pub fn double_bookings(&self, slots: &SlotMap) -> Vec<(UserId, SlotId, SlotId)> {
+        let mut per_user = UserMap::<Vec<&Slot>>::default();
+        for (slot_id, staff) in self {
+            if let Some(slot) = slots.get(slot_id) {
+                for &user in staff {
+                    per_user.entry(user).or_default().push(slot);
+                }
+            }
+        }
+
+        let mut found = Vec::new();
+        for (user, mut assigned) in per_user {
+            assigned.sort_by_key(|slot| slot.id.0);
+            for (i, a) in assigned.iter().enumerate() {
+                for b in &assigned[i + 1..] {
+                    if a.interval.is_overlapping(&b.interval) {
+                        found.push((user, a.id, b.id));
+                    }
+                }
+            }
+        }
+        found.sort_by_key(|&(user, a, b)| (user.0, a.0, b.0));
+        found
+    }
+}
+
+impl<'a> IntoIterator for &'a Schedule {
+    type Item = (&'a SlotId, &'a UserSet);
+    type IntoIter = std::collections::hash_map::Iter<'a, SlotId, UserSet>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod scheduler_tests {
+    use super::*;
+    use rustc_hash::FxHashSet;
+
+    macro_rules! hash_set {
+        ($($item:expr),* $(,)?) => {
+            FxHashSet::from_iter([$($item),*])
+        };
     }
 
     #[test]
     fn test0() {
-<<<<<<< HEAD
-        let tasks = test_project! {
-=======
         let tasks = tasks! {
->>>>>>> 04a1808e76feb61ebfb644cf6eff190bd1c24f5a
             5436: "foo" [4/12/2025 @ 5:30] {},
             2537: "bar" [4/12/2025] { 3423 },
             3423: "baz" { 5436 },
         };
 
         let dag = dep_graph(&tasks).unwrap();
-        dbg_ord(&dag);
+        let order = dep_order(&dag).collect::<Vec<_>>();
         assert_eq!(
-<<<<<<< HEAD
-            dep_order(&dag)
-                .map(|task| task.title.as_str())
-                .collect::<Vec<_>>(),
-            vec!["foo", "baz", "bar"]
-=======
-            &dep_order(&dag)
+            order
+                .iter()
                 .map(|task| task.title.as_str())
                 .collect::<Vec<_>>(),
-            &["foo", "baz", "bar"]
+            ["foo", "baz", "bar"]
+        );
+        for (i, task) in order.iter().enumerate() {
+            assert!(
+                task.deps
+                    .iter()
+                    .all(|dep| order[..i].iter().any(|prev| prev.id == *dep)),
+                "{} should come after all of its dependencies",
+                task.title
+            );
+        }
+
+        let users = users! {
+            0: "bob" {
+                0: 4/11/2025 - 4/13/2025 | 1.0,
+            },
+        };
+        let slots = slots! {
+            0: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00 [1] | "a",
+        };
+        let schedule = Schedule::generate(&slots, &tasks, &users).unwrap();
+        assert_eq!(
+            schedule.0,
+            SlotMap::from_iter([(SlotId(0), hash_set! { UserId(0) })]),
+            "dependent tasks should not prevent staffing"
         );
     }
 
     #[test]
-    fn test1() {
+    fn test_task_affinity() {
+        let users = users! {
+            0: "bob" {
+                0: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00 | 1.0,
+            },
+            1: "maria" {
+                1: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00 | 0.1,
+            },
+        };
+        let mut tasks = tasks! {
+            0: "payroll" {},
+        };
+        let mut slots = slots! {
+            0: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00 [1] | "a",
+        };
+        slots.get_mut(&SlotId(0)).unwrap().preferred_tasks = TaskSet::from_iter([TaskId(0)]);
+        let staff = |slots: &SlotMap, tasks: &TaskMap| {
+            Schedule::generate(slots, tasks, &users).unwrap().0[&SlotId(0)].clone()
+        };
+        assert_eq!(staff(&slots, &tasks), hash_set! { UserId(0) });
+
+        let payroll = tasks.get_mut(&TaskId(0)).unwrap();
+        payroll.preferred_users = UserMap::from_iter([(UserId(1), Preference::INFINITY)]);
+        assert_eq!(
+            staff(&slots, &tasks),
+            hash_set! { UserId(1) },
+            "only maria does payroll, so she should be staffed when available"
+        );
+
+        let payroll = tasks.get_mut(&TaskId(0)).unwrap();
+        payroll.preferred_users = UserMap::from_iter([(UserId(0), Preference::NEG_INFINITY)]);
+        assert_eq!(
+            staff(&slots, &tasks),
+            hash_set! { UserId(1) },
+            "bob should never do payroll"
+        );
+
+        slots.get_mut(&SlotId(0)).unwrap().preferred_tasks.clear();
+        assert_eq!(
+            staff(&slots, &tasks),
+            hash_set! { UserId(0) },
+            "affinity should only apply to slots the task is worked during"
+        );
+    }
+
+    #[test]
+    fn test_task_only_slot() {
+        let users = users! {
+            0: "bob" {
+                0: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00 | 1.0,
+            },
+            1: "maria" {
+                1: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00 | 0.1,
+            },
+        };
+        let mut tasks = tasks! {
+            0: "payroll" {},
+        };
+        let mut slots = slots! {
+            0: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00 | "a",
+        };
+        slots.get_mut(&SlotId(0)).unwrap().preferred_tasks = TaskSet::from_iter([TaskId(0)]);
+        let staff = |tasks: &TaskMap| {
+            Schedule::generate(&slots, tasks, &users).unwrap().0[&SlotId(0)].clone()
+        };
+        assert_eq!(
+            staff(&tasks),
+            hash_set! {},
+            "nobody needs to cover a slot without a minimum"
+        );
+
+        let payroll = tasks.get_mut(&TaskId(0)).unwrap();
+        payroll.preferred_users = UserMap::from_iter([(UserId(1), Preference::INFINITY)]);
+        assert_eq!(
+            staff(&tasks),
+            hash_set! { UserId(1) },
+            "payroll needs maria, so she should be staffed"
+        );
+    }
+
+    #[test]
+    fn test_tie_break_by_id() {
+        let users = users! {
+            7: "bob" {
+                0: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00 | 0.5,
+            },
+            3: "maria" {
+                1: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00 | 0.5,
+            },
+            5: "lee" {
+                2: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00 | 0.5,
+            },
+        };
+        let slots = slots! {
+            0: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00 [1] | "a",
+        };
+        let schedule = Schedule::generate(&slots, &TaskMap::default(), &users).unwrap();
+        assert_eq!(
+            schedule.0[&SlotId(0)],
+            hash_set! { UserId(3) },
+            "equally preferred candidates should go to the lowest ID"
+        );
+
+        // the same two users under consecutive IDs, until they are stored higher ID first
+        let pair = users! {
+            0: "bob" {
+                0: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00 | 0.5,
+            },
+            1: "maria" {
+                1: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00 | 0.5,
+            },
+        };
+        let (users, low) = (0..64)
+            .map(|n| {
+                let ids = [UserId(n), UserId(n + 1)];
+                let users = pair
+                    .values()
+                    .zip(ids)
+                    .map(|(user, id)| (id, User { id, ..user.clone() }))
+                    .collect::<UserMap<_>>();
+                (users, ids[0])
+            })
+            .find(|(users, low)| users.keys().next() != Some(low))
+            .expect("some pair of IDs should be stored out of ID order");
+        let schedule = Schedule::generate(&slots, &TaskMap::default(), &users).unwrap();
+        assert_eq!(
+            schedule.0[&SlotId(0)],
+            hash_set! { low },
+            "the lowest ID should win even when it is not stored first"
+        );
+    }
+
+    #[test]
+    fn test_partly_forbidden_slot() {
+        let users = users! {
+            0: "bob" {
+                0: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00 | 1.0,
+                1: 4/11/2025 @ 12:00 - 4/11/2025 @ 13:00 | f32::NEG_INFINITY,
+            },
+            1: "maria" {
+                2: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00 | 0.1,
+            },
+        };
+        let slots = slots! {
+            0: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00 [1] | "a",
+        };
+        let bob = &users[&UserId(0)];
+        assert_eq!(
+            governing_rule(bob, &slots[&SlotId(0)].interval).map(|r| r.id),
+            Some(RuleId(1)),
+            "a forbidden window within the slot should veto it"
+        );
+        assert!(!is_available(bob, &slots[&SlotId(0)].interval));
+
+        let schedule = Schedule::generate(&slots, &TaskMap::default(), &users).unwrap();
+        assert_eq!(schedule.0[&SlotId(0)], hash_set! { UserId(1) });
+        assert_eq!(
+            schedule.explain(&slots[&SlotId(0)], &users).candidates[&UserId(0)].forbidden_by,
+            [RuleId(1)]
+        );
+    }
+
+    #[test]
+    fn test_is_dag() {
+        let mut tasks = tasks! {
+            5436: "foo" [4/12/2025 @ 5:30] {},
+            2537: "bar" [4/12/2025] { 3423 },
+            3423: "baz" { 5436 },
+        };
+        assert!(is_dag(&tasks));
+
+        tasks
+            .get_mut(&TaskId(5436))
+            .unwrap()
+            .deps
+            .insert(TaskId(2537));
+        assert!(!is_dag(&tasks), "back edge should form a cycle");
+        assert!(dep_graph(&tasks).is_err(), "should agree with dep_graph");
+    }
+
+    #[test]
+    fn test_cycle_error_lists_tasks() {
+        let tasks = tasks! {
+            1: "a" { 2 },
+            2: "b" { 3 },
+            3: "c" { 1 },
+            4: "d" { 1 },
+        };
+        let mut cycle = find_cycle(&tasks).unwrap();
+        let first = cycle.iter().position(|id| *id == TaskId(1)).unwrap();
+        cycle.rotate_left(first);
+        assert_eq!(cycle, vec![TaskId(1), TaskId(2), TaskId(3)]);
+
+        let e = dep_graph(&tasks).unwrap_err();
+        let SchedulingError::WouldCycle(cycle) = &e else {
+            panic!("expected a cycle, got {e}");
+        };
+        assert_eq!(cycle.len(), 3, "task d is not part of the cycle");
+        let message = e.to_string();
+        for id in ["t.1", "t.2", "t.3"] {
+            assert!(message.contains(id), "{message:?} should mention {id}");
+        }
+        assert!(!message.contains("t.4"), "{message:?}");
+    }
+
+    #[test]
+    fn test_blocking_tasks() {
+        let tasks = tasks! {
+            0: "done" [4/10/2025] {},
+            1: "also done" [4/11/2025] {},
+            2: "pending" [4/20/2025] {},
+            3: "goal" { 0, 1, 2 },
+        };
+
+        assert_eq!(
+            blocking_tasks(&tasks, TaskId(3), datetime!(4/15/2025)).unwrap(),
+            hash_set! { TaskId(2) },
+        );
+        assert_eq!(
+            blocking_tasks(&tasks, TaskId(3), datetime!(4/21/2025)).unwrap(),
+            hash_set! {},
+            "nothing should block once every deadline has passed"
+        );
+        assert!(matches!(
+            blocking_tasks(&tasks, TaskId(9), datetime!(4/15/2025)),
+            Err(SchedulingError::NonExistentTask(TaskId(9)))
+        ));
+    }
+
+    #[test]
+    fn test_dependency_path() {
+        let tasks = tasks! {
+            0: "goal" { 1, 2 },
+            1: "long way" { 3 },
+            2: "short way" { 4 },
+            3: "step" { 4 },
+            4: "blocker" {},
+            5: "unrelated" {},
+        };
+
+        assert_eq!(
+            dependency_path(&tasks, TaskId(0), TaskId(4)),
+            Some(vec![TaskId(0), TaskId(2), TaskId(4)]),
+        );
+        assert_eq!(
+            dependency_path(&tasks, TaskId(0), TaskId(0)),
+            Some(vec![TaskId(0)])
+        );
+        assert_eq!(dependency_path(&tasks, TaskId(0), TaskId(5)), None);
+        assert_eq!(
+            dependency_path(&tasks, TaskId(4), TaskId(0)),
+            None,
+            "paths only follow dependencies, not dependents"
+        );
+    }
+
+    #[test]
+    fn test_critical_path() {
+        let tasks = crate::tasks! {
+            0: "plan" {},
+            1: "build" {0},
+            2: "paint" {0},
+            3: "ship" {1, 2},
+            4: "unrelated" {},
+        };
+        let hours = TaskMap::from_iter([
+            (TaskId(0), 1),
+            (TaskId(1), 5),
+            (TaskId(2), 2),
+            (TaskId(3), 1),
+            (TaskId(4), 6),
+        ]);
+        let graph = dep_graph(&tasks).unwrap();
+
+        assert_eq!(
+            critical_path(&graph, |task| TimeDelta::hours(hours[&task.id])),
+            [TaskId(0), TaskId(1), TaskId(3)],
+        );
+        assert_eq!(
+            critical_path(&graph, |task| TimeDelta::hours(hours[&task.id] % 5)),
+            [TaskId(0), TaskId(2), TaskId(3)],
+            "painting is slower than building now"
+        );
+        assert!(critical_path(&dep_graph(&TaskMap::default()).unwrap(), |_| 1).is_empty());
+    }
+
+    #[test]
+    fn test_schedulable_tasks() {
+        let tasks = crate::tasks! {
+            0: "past due" [4/12/2025 @ 8:00] {},
+            1: "due later" [4/20/2025] {},
+            2: "no deadline" {},
+            3: "after due later" [4/20/2025] {1},
+            4: "after missed" [4/20/2025] {0},
+            5: "after missing" {9},
+        };
+        let slots = slots! {
+            0: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00,
+            1: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00,
+        };
+        assert_eq!(
+            schedulable_tasks(&slots, &tasks, &slots[&SlotId(1)].interval),
+            TaskSet::from_iter([TaskId(1), TaskId(2), TaskId(3), TaskId(4)]),
+            "task 4's dependency could be done during slot 0, before it was due"
+        );
+        assert_eq!(
+            schedulable_tasks(&slots, &tasks, &slots[&SlotId(0)].interval),
+            TaskSet::from_iter([TaskId(0), TaskId(1), TaskId(2)]),
+            "nothing can be done before the first slot"
+        );
+    }
+
+    #[test]
+    fn test_suggest_slots() {
+        let mut users = users! {
+            0: "bob" {
+                0: 4/14/2025 @ 9:00 - 4/14/2025 @ 17:00 | 0.5,
+            },
+            1: "lisa" {
+                1: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 | 0.5,
+            },
+        };
+        users
+            .get_mut(&UserId(0))
+            .unwrap()
+            .skills
+            .insert(SkillId(0), Proficiency::ONE.into());
+        let mut tasks = crate::tasks! {
+            0: "repair" [4/20/2025] {},
+            1: "sweep" [4/20/2025] {},
+            2: "overdue" [4/1/2025] {},
+        };
+        let req =
+            ProficiencyReq::new(Proficiency::ONE, Proficiency::ONE.., Proficiency::ONE..).unwrap();
+        for id in [TaskId(0), TaskId(2)] {
+            tasks
+                .get_mut(&id)
+                .unwrap()
+                .skills
+                .insert(SkillId(0), req.clone());
+        }
+        let slots = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 [1] | "a",
+        };
+
+        let suggestions = suggest_slots(&slots, &tasks, &users, datetime!(4/10/2025 @ 9:00));
+        assert_eq!(
+            suggestions,
+            TaskMap::from_iter([(
+                TaskId(0),
+                crate::time_interval!(4/14/2025 @ 9:00 - 4/14/2025 @ 17:00)
+            )]),
+            "only bob can repair, and he is not free during the slot; \
+             lisa can sweep during the slot; the overdue task is skipped"
+        );
+    }
+
+    #[test]
+    fn test_resolve_deadlines() {
+        let mut tasks = crate::tasks! {
+            0: "a" [4/10/2025] {},
+            1: "b" [4/30/2025] {0},
+            2: "c" {1},
+            3: "d" {},
+        };
+        tasks.get_mut(&TaskId(1)).unwrap().deadline_offset = Some(TimeDelta::days(2));
+        tasks.get_mut(&TaskId(2)).unwrap().deadline_offset = Some(TimeDelta::days(1));
+        tasks.get_mut(&TaskId(3)).unwrap().deadline_offset = Some(TimeDelta::days(1));
+        let graph = dep_graph(&tasks).unwrap();
+
+        let deadlines = resolve_deadlines(&graph, &TaskMap::default());
+        assert_eq!(deadlines[&TaskId(0)].latest(), Some(datetime!(4/10/2025)));
+        assert_eq!(
+            deadlines[&TaskId(1)],
+            Deadlines {
+                soft: Some(datetime!(4/12/2025)),
+                hard: Some(datetime!(4/12/2025)),
+            },
+            "offset should replace both absolute deadlines"
+        );
+        assert_eq!(deadlines[&TaskId(2)].latest(), Some(datetime!(4/13/2025)));
+        assert_eq!(
+            deadlines[&TaskId(3)].latest(),
+            None,
+            "offset without dependencies should be ignored"
+        );
+        assert_eq!(effective_deadlines(&tasks), deadlines);
+
+        let finished = TaskMap::from_iter([(TaskId(0), datetime!(4/8/2025 @ 17:00))]);
+        let deadlines = resolve_deadlines(&graph, &finished);
+        assert_eq!(
+            deadlines[&TaskId(1)].latest(),
+            Some(datetime!(4/10/2025 @ 17:00)),
+            "a placed dependency should count from when it finishes"
+        );
+        assert_eq!(
+            deadlines[&TaskId(2)].latest(),
+            Some(datetime!(4/11/2025 @ 17:00))
+        );
+    }
+
+    #[test]
+    fn test_relative_deadline_blocks() {
+        let mut tasks = tasks! {
+            0: "prepare" [4/10/2025] {},
+            1: "follow up" [4/30/2025] {0},
+            2: "goal" {1},
+        };
+        tasks.get_mut(&TaskId(1)).unwrap().deadline_offset = Some(TimeDelta::days(2));
+        assert_eq!(
+            blocking_tasks(&tasks, TaskId(2), datetime!(4/15/2025)).unwrap(),
+            hash_set! {},
+            "follow up is due two days after prepare, not on its absolute deadline"
+        );
+    }
+
+    #[test]
+    fn test_soft_and_hard_deadlines() {
+        let deadlines = Deadlines {
+            soft: Some(datetime!(4/11/2025)),
+            hard: Some(datetime!(4/14/2025)),
+        };
+        assert_eq!(
+            deadline_penalty(TaskId(0), deadlines, datetime!(4/10/2025)).unwrap(),
+            TimeDelta::zero(),
+            "on time"
+        );
+        assert_eq!(
+            deadline_penalty(TaskId(0), deadlines, datetime!(4/12/2025)).unwrap(),
+            TimeDelta::days(1),
+            "past the soft deadline is allowed but penalized"
+        );
+        assert!(matches!(
+            deadline_penalty(TaskId(0), deadlines, datetime!(4/15/2025)),
+            Err(SchedulingError::MissedDeadline(TaskId(0)))
+        ));
+
+        let late = crate::slot_lit! { 0: 4/10/2025 @ 9:00 - 4/12/2025 @ 17:00 };
+        let on_time = crate::slot_lit! { 1: 4/10/2025 @ 12:00 - 4/10/2025 @ 17:00 };
+        assert_eq!(
+            choose_slot(TaskId(0), deadlines, [&late, &on_time]).map(|slot| slot.id),
+            Some(on_time.id),
+            "a slot finishing before the soft deadline should beat an earlier one finishing after it"
+        );
+
+        let users = users! {
+            0: "bob" {
+                0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 | 1.0,
+                1: 4/15/2025 @ 9:00 - 4/15/2025 @ 17:00 | 1.0,
+            },
+        };
+        let mut tasks = tasks! {
+            0: "report" {},
+        };
+        let report = tasks.get_mut(&TaskId(0)).unwrap();
+        report.soft_deadline = deadlines.soft;
+        report.hard_deadline = deadlines.hard;
+        let slots = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 [1] | "after soft",
+            1: 4/15/2025 @ 9:00 - 4/15/2025 @ 17:00 [1] | "after hard",
+        };
+        let pinned_to = |slot: SlotId| SchedulerConfig {
+            pins: Pins {
+                tasks: SlotMap::from_iter([(slot, TaskSet::from_iter([TaskId(0)]))]),
+                ..Pins::default()
+            },
+            ..SchedulerConfig::default()
+        };
+        assert!(
+            Schedule::generate_with(&slots, &tasks, &users, &pinned_to(SlotId(0))).is_ok(),
+            "working a task past its soft deadline is allowed"
+        );
+        assert!(matches!(
+            Schedule::generate_with(&slots, &tasks, &users, &pinned_to(SlotId(1))),
+            Err(SchedulingError::MissedDeadline(TaskId(0)))
+        ));
+    }
+
+    #[test]
+    fn test_pinned_dependency_moves_deadline() {
+        let mut tasks = tasks! {
+            0: "prepare" [4/20/2025] {},
+            1: "follow up" [4/30/2025] {0},
+        };
+        tasks.get_mut(&TaskId(1)).unwrap().deadline_offset = Some(TimeDelta::days(1));
+        let slots = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 | "early",
+            1: 4/14/2025 @ 9:00 - 4/14/2025 @ 17:00 | "late",
+            2: 4/15/2025 @ 9:00 - 4/15/2025 @ 17:00 | "follow up",
+        };
+        let prepared_in = |slot: Option<SlotId>| SchedulerConfig {
+            pins: Pins {
+                tasks: SlotMap::from_iter(
+                    slot.map(|slot| (slot, TaskSet::from_iter([TaskId(0)])))
+                        .into_iter()
+                        .chain([(SlotId(2), TaskSet::from_iter([TaskId(1)]))]),
+                ),
+                ..Pins::default()
+            },
+            ..SchedulerConfig::default()
+        };
+        let generate = |slot| {
+            Schedule::generate_with(&slots, &tasks, &UserMap::default(), &prepared_in(slot))
+        };
+
+        assert!(
+            generate(None).is_ok(),
+            "unplaced, prepare counts from its own deadline"
+        );
+        assert!(
+            matches!(
+                generate(Some(SlotId(0))),
+                Err(SchedulingError::MissedDeadline(TaskId(1)))
+            ),
+            "prepared early, follow up is due a day after"
+        );
+        assert!(
+            generate(Some(SlotId(1))).is_ok(),
+            "prepared later, follow up is due later"
+        );
+        assert_eq!(
+            pinned_deadlines(&slots, &tasks, &prepared_in(Some(SlotId(1))).pins)[&TaskId(1)].hard,
+            Some(datetime!(4/15/2025 @ 17:00))
+        );
+    }
+
+    #[test]
+    fn test_task_affinity_respects_hard_deadline() {
+        let users = users! {
+            0: "bob" {
+                0: 4/15/2025 @ 9:00 - 4/15/2025 @ 17:00 | 1.0,
+            },
+            1: "maria" {
+                1: 4/15/2025 @ 9:00 - 4/15/2025 @ 17:00 | 0.1,
+            },
+        };
+        let mut tasks = tasks! {
+            0: "payroll" [4/14/2025] {},
+        };
+        tasks.get_mut(&TaskId(0)).unwrap().preferred_users =
+            UserMap::from_iter([(UserId(1), Preference::INFINITY)]);
+        let mut slots = slots! {
+            0: 4/15/2025 @ 9:00 - 4/15/2025 @ 17:00 [1] | "a",
+        };
+        slots.get_mut(&SlotId(0)).unwrap().preferred_tasks = TaskSet::from_iter([TaskId(0)]);
+        assert_eq!(
+            Schedule::generate(&slots, &tasks, &users).unwrap().0[&SlotId(0)],
+            hash_set! { UserId(0) },
+            "payroll is past due by the slot, so it should not pull maria in"
+        );
+    }
+
+    #[test]
+    fn test_choose_slot_prefers_marked_slot() {
+        let mut task = crate::task_lit! { 0: "inventory" {} };
+        task.hard_deadline = Some(datetime!(4/14/2025));
+        let a = crate::slot_lit! { 0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 };
+        let b = crate::slot_lit! { 1: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00 };
+        let deadlines = Deadlines::from(&task);
+        assert_eq!(
+            choose_slot(task.id, deadlines, [&a, &b]).map(|slot| slot.id),
+            Some(b.id),
+            "without a preference, the earliest slot is chosen"
+        );
+
+        let mut a = a;
+        a.preferred_tasks.insert(task.id);
+        assert_eq!(
+            choose_slot(task.id, deadlines, [&a, &b]).map(|slot| slot.id),
+            Some(a.id)
+        );
+
+        a.interval = crate::time_interval! { 4/15/2025 @ 9:00 - 4/15/2025 @ 17:00 };
+        assert_eq!(
+            choose_slot(task.id, deadlines, [&a, &b]).map(|slot| slot.id),
+            Some(b.id),
+            "a preferred slot past the hard deadline is not feasible"
+        );
+    }
+
+    #[test]
+    fn test_marked_slot_staffs_preferred_user() {
+        let users = users! {
+            0: "bob" {
+                0: 4/11/2025 @ 9:00 - 4/12/2025 @ 17:00 | 1.0,
+            },
+            1: "maria" {
+                1: 4/11/2025 @ 9:00 - 4/12/2025 @ 17:00 | 0.1,
+            },
+        };
+        let mut tasks = tasks! {
+            0: "inventory" {},
+        };
+        tasks.get_mut(&TaskId(0)).unwrap().preferred_users =
+            UserMap::from_iter([(UserId(1), Preference::INFINITY)]);
+        let mut slots = slots! {
+            0: 4/11/2025 @ 9:00 - 4/11/2025 @ 17:00 [1] | "a",
+            1: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 [1] | "b",
+        };
+        slots.get_mut(&SlotId(1)).unwrap().preferred_tasks = TaskSet::from_iter([TaskId(0)]);
+
+        let schedule =
+            Schedule::generate_with(&slots, &tasks, &users, &SchedulerConfig::default()).unwrap();
+        assert_eq!(
+            schedule.0[&SlotId(1)],
+            hash_set! { UserId(1) },
+            "maria should be staffed on the slot marked for inventory"
+        );
+        assert_eq!(
+            schedule.0[&SlotId(0)],
+            hash_set! { UserId(0) },
+            "the unmarked slot should not be affected by the task"
+        );
+    }
+
+    #[test]
+    fn test_partial_availability() {
+        let users = users! {
+            0: "bob" {
+                0: 4/12/2025 @ 9:00 - 4/12/2025 @ 9:45 | 0.8,
+            },
+            1: "lisa" {
+                1: 4/12/2025 @ 9:00 - 4/12/2025 @ 9:15 | 1.0,
+            },
+        };
+        let slots = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 10:00 [1],
+        };
+        let config = SchedulerConfig::default();
+        let interval = &slots[&SlotId(0)].interval;
+
+        assert_eq!(
+            config.partial_pref(&users[&UserId(0)], interval),
+            Some(Preference(0.6)),
+            "75% of the slot at 0.8 should score 0.6"
+        );
+        assert_eq!(
+            config.partial_pref(&users[&UserId(1)], interval),
+            None,
+            "25% of the slot is below the threshold"
+        );
+
+        let schedule = Schedule::generate(&slots, &Default::default(), &users).unwrap();
+        assert_eq!(schedule.0[&SlotId(0)], hash_set! { UserId(0) });
+
+        let mut users = users;
+        users.get_mut(&UserId(0)).unwrap().availability.insert(
+            RuleId(2),
+            crate::rule_lit! { 2: 4/12/2025 @ 9:50 - 4/12/2025 @ 9:55 | f32::NEG_INFINITY },
+        );
+        assert!(
+            matches!(
+                Schedule::generate(&slots, &Default::default(), &users),
+                Err(SchedulingError::Understaffed)
+            ),
+            "a forbidden part of the slot should rule the user out"
+        );
+    }
+
+    #[test]
+    fn test_decay_favors_fresher_preference() {
+        let mut users = users! {
+            0: "bob" {
+                0: 4/1/2025 - 5/1/2025 | 1.0,
+            },
+            1: "lisa" {
+                1: 4/1/2025 - 5/1/2025 | 1.0,
+            },
+        };
+        users
+            .get_mut(&UserId(0))
+            .unwrap()
+            .availability
+            .get_mut(&RuleId(0))
+            .unwrap()
+            .set_at = Some(datetime!(1/1/2025));
+        users
+            .get_mut(&UserId(1))
+            .unwrap()
+            .availability
+            .get_mut(&RuleId(1))
+            .unwrap()
+            .set_at = Some(datetime!(4/1/2025));
+
+        let slots = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 [1] | "a",
+        };
+        let config = SchedulerConfig {
+            decay: Some(Decay {
+                half_life: TimeDelta::days(30),
+            }),
+            ..Default::default()
+        };
+
+        let schedule =
+            Schedule::generate_with(&slots, &Default::default(), &users, &config).unwrap();
+        assert_eq!(
+            schedule.0[&SlotId(0)],
+            hash_set! { UserId(1) },
+            "the fresher preference should be favored"
+        );
+
+        let decay = config.decay.unwrap();
+        assert_eq!(
+            decay.apply(
+                Preference::INFINITY,
+                datetime!(1/1/2025),
+                datetime!(4/1/2025)
+            ),
+            Preference::INFINITY,
+            "infinities should never decay"
+        );
+        assert_eq!(
+            decay.apply(
+                Preference(1.0),
+                datetime!(1/1/2025),
+                datetime!(1/31/2025)
+            ),
+            Preference(0.5),
+        );
+    }
+
+    #[test]
+    fn test_narrow_rule_governs() {
+        let users = users! {
+            0: "bob" {
+                0: 4/1/2025 - 5/1/2025 | 0.2,
+                1: 4/12/2025 @ 6:00 - 4/12/2025 @ 12:00 | 0.9,
+            },
+            1: "lisa" {
+                2: 4/1/2025 - 5/1/2025 | 0.5,
+            },
+        };
+        let bob = &users[&UserId(0)];
+        let morning = crate::time_interval! { 4/12/2025 @ 8:00 - 4/12/2025 @ 11:00 };
+        let afternoon = crate::time_interval! { 4/12/2025 @ 13:00 - 4/12/2025 @ 16:00 };
+        assert_eq!(governing_rule(bob, &morning).unwrap().id, RuleId(1));
+        assert_eq!(governing_rule(bob, &afternoon).unwrap().id, RuleId(0));
+
+        let slots = slots! {
+            0: 4/12/2025 @ 8:00 - 4/12/2025 @ 11:00 [1] | "morning",
+            1: 4/12/2025 @ 13:00 - 4/12/2025 @ 16:00 [1] | "afternoon",
+        };
+        let schedule = Schedule::generate(&slots, &Default::default(), &users).unwrap();
+        assert_eq!(
+            schedule.0[&SlotId(0)],
+            hash_set! { UserId(0) },
+            "bob's narrow 0.9 should govern the morning"
+        );
+        assert_eq!(
+            schedule.0[&SlotId(1)],
+            hash_set! { UserId(1) },
+            "bob's broad 0.2 should govern the afternoon"
+        );
+    }
+
+    #[test]
+    fn test_default_availability() {
+        let mut users = users! {
+            0: "bob" {},
+            1: "lisa" {
+                0: 4/12/2025 - 4/13/2025 | f32::NEG_INFINITY,
+            },
+        };
+        let slots = slots! {
+            0: 4/12/2025 @ 8:00 - 4/12/2025 @ 11:00 [1] | "a",
+        };
+        assert!(
+            Schedule::generate(&slots, &Default::default(), &users).is_err(),
+            "users without rules are unavailable by default"
+        );
+
+        for user in users.values_mut() {
+            user.default_availability = Some(Preference(0.3));
+        }
+        let schedule = Schedule::generate(&slots, &Default::default(), &users).unwrap();
+        assert_eq!(
+            schedule.0[&SlotId(0)],
+            hash_set! { UserId(0) },
+            "lisa's rule should still override her default"
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        // lisa rates the same slots as bob, just on half the scale
+        let users = users! {
+            0: "bob" {
+                0: 4/12/2025 - 4/13/2025 | 1.0,
+                1: 4/14/2025 - 4/15/2025 | 0.6,
+            },
+            1: "lisa" {
+                2: 4/12/2025 - 4/13/2025 | 0.5,
+                3: 4/14/2025 - 4/15/2025 | 0.3,
+            },
+        };
+        let interval = &crate::time_interval!(4/14/2025 @ 9:00 - 4/14/2025 @ 17:00);
+        let config = SchedulerConfig {
+            normalize: true,
+            ..Default::default()
+        };
+        let [bob, lisa] = [UserId(0), UserId(1)].map(|id| {
+            let user = &users[&id];
+            config.normalized(user, availability_pref(user, interval).unwrap())
+        });
+        assert!((bob.0 - lisa.0).abs() < 1e-6, "{bob:?} vs {lisa:?}");
+        assert_eq!(
+            config.normalized(&users[&UserId(1)], Preference::INFINITY),
+            Preference::INFINITY,
+            "infinities are exempt"
+        );
+
+        // lisa prefers this slot more, relative to her own scale
+        let users = users! {
+            0: "bob" {
+                0: 4/12/2025 - 4/13/2025 | 1.0,
+                1: 4/14/2025 - 4/15/2025 | 0.6,
+            },
+            1: "lisa" {
+                2: 4/12/2025 - 4/13/2025 | 0.5,
+                3: 4/14/2025 - 4/15/2025 | 0.4,
+            },
+        };
+        let slots = slots! {
+            0: 4/14/2025 @ 9:00 - 4/14/2025 @ 17:00 [1] | "a",
+        };
+        let schedule = Schedule::generate(&slots, &Default::default(), &users).unwrap();
+        assert_eq!(schedule.0[&SlotId(0)], hash_set! { UserId(0) });
+        let schedule =
+            Schedule::generate_with(&slots, &Default::default(), &users, &config).unwrap();
+        assert_eq!(schedule.0[&SlotId(0)], hash_set! { UserId(1) });
+    }
+
+    #[test]
+    fn test_min_rest() {
+        let users = users! {
+            0: "bob" {
+                0: 4/12/2025 - 4/13/2025 | 1.0,
+            },
+            1: "lisa" {
+                1: 4/12/2025 - 4/13/2025 | 0.5,
+            },
+        };
+        let slots = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 11:00 [1] | "a",
+            1: 4/12/2025 @ 12:00 - 4/12/2025 @ 14:00 [1] | "b",
+        };
+        let schedule = Schedule::generate(&slots, &Default::default(), &users).unwrap();
+        assert_eq!(schedule.0[&SlotId(0)], hash_set! { UserId(0) });
+        assert_eq!(schedule.0[&SlotId(1)], hash_set! { UserId(0) });
+
+        let config = SchedulerConfig {
+            min_rest: Some(TimeDelta::hours(2)),
+            ..Default::default()
+        };
+        let schedule =
+            Schedule::generate_with(&slots, &Default::default(), &users, &config).unwrap();
+        assert_eq!(schedule.0[&SlotId(0)], hash_set! { UserId(0) });
+        assert_eq!(
+            schedule.0[&SlotId(1)],
+            hash_set! { UserId(1) },
+            "bob has only had an hour's rest"
+        );
+    }
+
+    #[test]
+    fn test_schedule_eq_ignores_order() {
+        let a = Schedule(SlotMap::from_iter([
+            (
+                SlotId(0),
+                UserSet::from_iter([UserId(0), UserId(1), UserId(2)]),
+            ),
+            (SlotId(1), UserSet::from_iter([UserId(3)])),
+        ]));
+        let b = Schedule(SlotMap::from_iter([
+            (SlotId(1), UserSet::from_iter([UserId(3)])),
+            (
+                SlotId(0),
+                UserSet::from_iter([UserId(2), UserId(0), UserId(1)]),
+            ),
+        ]));
+        assert_eq!(a, b);
+
+        let c = Schedule(SlotMap::from_iter([
+            (SlotId(0), UserSet::from_iter([UserId(0), UserId(1)])),
+            (SlotId(1), UserSet::from_iter([UserId(3)])),
+        ]));
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_seed() {
+        let users = users! {
+            0: "bob" { 0: 4/12/2025 - 4/13/2025 | 0.5 },
+            1: "lisa" { 1: 4/12/2025 - 4/13/2025 | 0.5 },
+            2: "jones" { 2: 4/12/2025 - 4/13/2025 | 0.5 },
+            3: "kim" { 3: 4/12/2025 - 4/13/2025 | 0.5 },
+        };
+        let slots = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 [1] | "a",
+        };
+        let generate = |seed| {
+            let config = SchedulerConfig {
+                seed: Some(seed),
+                ..Default::default()
+            };
+            Schedule::generate_with(&slots, &Default::default(), &users, &config)
+                .unwrap()
+                .0
+        };
+
+        assert_eq!(
+            generate(7),
+            generate(7),
+            "same seed should give the same schedule"
+        );
+        let picks = (0..16)
+            .map(|seed| generate(seed)[&SlotId(0)].clone())
+            .collect::<Vec<_>>();
+        assert!(
+            picks.iter().any(|pick| *pick != picks[0]),
+            "different seeds should break ties differently: {picks:?}"
+        );
+    }
+
+    #[test]
+    fn test_infeasible_pin() {
+        let users = users! {
+            0: "bob" {
+                0: 4/12/2025 - 4/13/2025 | f32::NEG_INFINITY,
+            },
+        };
+        let slots = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 | "a",
+        };
+        let config = SchedulerConfig {
+            pins: Pins {
+                users: SlotMap::from_iter([(SlotId(0), hash_set! { UserId(0) })]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            Schedule::generate_with(&slots, &Default::default(), &users, &config),
+            Err(SchedulingError::InfeasiblePin(SlotId(0), UserId(0)))
+        ));
+    }
+
+    #[test]
+    fn test_max_staff() {
+        let users = users! {
+            0: "bob" {
+                0: 4/12/2025 - 4/13/2025 | 1.0,
+            },
+            1: "lisa" {
+                1: 4/12/2025 - 4/13/2025 | 1.0,
+            },
+        };
+        let mut slots = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 [1] | "a",
+        };
+        slots.get_mut(&SlotId(0)).unwrap().max_staff = std::num::NonZeroUsize::new(1);
+        let config = SchedulerConfig {
+            pins: Pins {
+                users: SlotMap::from_iter([(SlotId(0), hash_set! { UserId(0), UserId(1) })]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            Schedule::generate_with(&slots, &Default::default(), &users, &config),
+            Err(SchedulingError::Overstaffed(SlotId(0)))
+        ));
+    }
+
+    /// Two slots that each need two users: bob is available for "b", lisa for "a", and jones for both.
+    fn bob_lisa_jones() -> (UserMap<User>, SlotMap<Slot>) {
         let users = users! {
             4578: "bob" {
                 0: 4/12/2025 @ 6:30 - 6/12/2025 @ 7:30 | 1.0,
@@ -369,6 +2223,12 @@ mod scheduler_tests {
             0: 4/12/2025 @ 5:30 - 6/12/2025 @ 6:30 [2] | "a",
             1: 4/12/2025 @ 6:30 - 6/12/2025 @ 7:30 [2] | "b",
         };
+        (users, slots)
+    }
+
+    #[test]
+    fn test1() {
+        let (users, slots) = bob_lisa_jones();
 
         let schedule = Schedule::generate(&slots, &Default::default(), &users).unwrap();
         assert_eq!(
@@ -387,7 +2247,84 @@ mod scheduler_tests {
                 ("a", hash_set! { "lisa", "jones" }),
                 ("b", hash_set! { "bob", "jones" }),
             ]),
->>>>>>> 04a1808e76feb61ebfb644cf6eff190bd1c24f5a
         );
     }
+
+    #[test]
+    fn test_render() {
+        let (users, mut slots) = bob_lisa_jones();
+        let tasks = tasks! {
+            0: "stock shelves" {},
+            1: "count register" {},
+        };
+        slots.get_mut(&SlotId(0)).unwrap().preferred_tasks = TaskSet::from_iter([TaskId(0)]);
+        let config = SchedulerConfig {
+            pins: Pins {
+                tasks: SlotMap::from_iter([(SlotId(0), TaskSet::from_iter([TaskId(1)]))]),
+                ..Pins::default()
+            },
+            ..SchedulerConfig::default()
+        };
+
+        let schedule = Schedule::generate_with(&slots, &tasks, &users, &config).unwrap();
+        assert_eq!(
+            schedule.render(&slots, &tasks, &users, &config.pins),
+            "a (2025-04-12 05:30 - 2025-06-12 06:30)\n    tasks: count register; stock shelves\n    jones\n    lisa\n\
+             b (2025-04-12 06:30 - 2025-06-12 07:30)\n    bob\n    jones\n",
+        );
+    }
+
+    #[test]
+    fn test_assignments_for_user() {
+        let (users, slots) = bob_lisa_jones();
+
+        let schedule = Schedule::generate(&slots, &Default::default(), &users).unwrap();
+        let slots_of = |id| {
+            schedule
+                .assignments_for_user(UserId(id))
+                .map(|slot| slots[slot].name.as_str())
+                .collect::<FxHashSet<_>>()
+        };
+        assert_eq!(slots_of(4578), hash_set! { "b" });
+        assert_eq!(slots_of(4753), hash_set! { "a" });
+        assert_eq!(slots_of(2773), hash_set! { "a", "b" });
+        assert_eq!(slots_of(1), hash_set! {}, "unknown users are not scheduled");
+        assert_eq!(
+            (&schedule).into_iter().count(),
+            slots.len(),
+            "every slot should be visited once"
+        );
+    }
+
+    #[test]
+    fn test_double_bookings() {
+        let slots = slots! {
+            0: 4/12/2025 @ 5:30 - 4/12/2025 @ 7:30,
+            1: 4/12/2025 @ 6:30 - 4/12/2025 @ 8:30,
+            2: 4/12/2025 @ 8:30 - 4/12/2025 @ 9:30,
+        };
+        let schedule = Schedule(SlotMap::from_iter([
+            (SlotId(0), hash_set! { UserId(1), UserId(2) }),
+            (SlotId(1), hash_set! { UserId(1) }),
+            (SlotId(2), hash_set! { UserId(1), UserId(2) }),
+        ]));
+        assert_eq!(
+            schedule.double_bookings(&slots),
+            vec![(UserId(1), SlotId(0), SlotId(1))],
+            "back-to-back slots should not count as overlapping"
+        );
+    }
+
+    #[test]
+    fn test_no_double_bookings() {
+        let slots = slots! {
+            0: 4/12/2025 @ 5:30 - 4/12/2025 @ 6:30,
+            1: 4/12/2025 @ 6:30 - 4/12/2025 @ 7:30,
+        };
+        let schedule = Schedule(SlotMap::from_iter([
+            (SlotId(0), hash_set! { UserId(1) }),
+            (SlotId(1), hash_set! { UserId(1) }),
+        ]));
+        assert_eq!(schedule.double_bookings(&slots), vec![]);
+    }
 }
@@ -2,6 +2,7 @@
 
 pub mod pref;
 pub mod rule;
+pub mod seconds;
 pub mod skill;
 pub mod slot;
 pub mod task;
@@ -14,6 +15,18 @@ pub use slot::*;
 pub use task::*;
 pub use user::*;
 
+/// The reason a string could not be parsed as an ID.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseIdError {
+    /// The string did not begin with the expected `prefix.`.
+    #[error("expected an id of the form `{_0}.<hex>`")]
+    Prefix(char),
+
+    /// The part after the prefix was not a valid hexadecimal number.
+    #[error(transparent)]
+    Int(#[from] std::num::ParseIntError),
+}
+
 macro_rules! id_type {
     ($(#[$m:meta])* impl Id<$repr:ty> for $Type:ident as $prefix:literal) => {
         ::paste::paste! {
@@ -31,6 +44,10 @@ macro_rules! id_type {
                     [<NEXT_ $Type:snake:upper _ID>].store(value, ::std::sync::atomic::Ordering::Relaxed);
                 }
 
+                pub(crate) fn load() -> $repr {
+                    [<NEXT_ $Type:snake:upper _ID>].load(::std::sync::atomic::Ordering::Relaxed)
+                }
+
                 pub(crate) fn next() -> Option<Self> {
                     Self::take(1).next()
                 }
@@ -47,6 +64,17 @@ macro_rules! id_type {
                 }
             }
 
+            impl std::str::FromStr for [<$Type Id>] {
+                type Err = $crate::data::ParseIdError;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    let hex = s
+                        .strip_prefix(concat!($prefix, "."))
+                        .ok_or($crate::data::ParseIdError::Prefix($prefix))?;
+                    Ok(Self($repr::from_str_radix(hex, 16)?))
+                }
+            }
+
             #[doc = " A dictionary associating [`" [<$Type Id>] "`]s with `T`."]
             pub type [<$Type Map>]<T = $Type> = ::rustc_hash::FxHashMap<[<$Type Id>], T>;
 
@@ -58,6 +86,41 @@ macro_rules! id_type {
 
 pub(crate) use id_type;
 
+/// An item that is stored in a dictionary keyed by its own ID.
+pub trait Keyed {
+    /// The ID type the item is keyed by.
+    type Id: Eq + std::hash::Hash;
+
+    /// The ID of this item.
+    fn id(&self) -> Self::Id;
+}
+
+macro_rules! keyed {
+    ($($Type:ident),* $(,)?) => {
+        ::paste::paste! {$(
+            impl Keyed for $Type {
+                type Id = [<$Type Id>];
+
+                #[inline]
+                fn id(&self) -> Self::Id {
+                    self.id
+                }
+            }
+        )*}
+    };
+}
+
+keyed!(Slot, Task, User, Rule);
+
+/// Collect `items` into a dictionary keyed by each item's [ID](Keyed::id).
+///
+/// If multiple items share an ID, the last one is kept.
+pub fn index_by_id<T: Keyed>(
+    items: impl IntoIterator<Item = T>,
+) -> rustc_hash::FxHashMap<T::Id, T> {
+    items.into_iter().map(|item| (item.id(), item)).collect()
+}
+
 #[cfg(test)]
 #[allow(unused_imports)]
 pub(crate) use test_macros::*;
@@ -128,6 +191,8 @@ pub(crate) mod test_macros {
                 id: $crate::data::slot::SlotId($id),
                 interval: $crate::time_interval!($mo0/$d0/$yr0$( @ $hr0:$m0)? - $mo1/$d1/$yr1$( @ $hr1:$m1)?),
                 min_staff: None$(.or(std::num::NonZeroUsize::new($min_staff)))?,
+                max_staff: None,
+                preferred_tasks: Default::default(),
                 name: None$(.or(Some($name.to_string())))?.unwrap_or(String::new())
             }
         };
@@ -144,10 +209,7 @@ pub(crate) mod test_macros {
             $([$min_staff:literal])?
             $(| $name:literal)?
         ),+ $(,)?) => {
-            [$($crate::slot_lit!($id: $mo0/$d0/$yr0$( @ $hr0:$m0)? - $mo1/$d1/$yr1$( @ $hr1:$m1)? $([$min_staff])? $(| $name)?)),*]
-                .into_iter()
-                .map(|slot| (slot.id, slot))
-                .collect::<$crate::data::slot::SlotMap>()
+            $crate::data::index_by_id::<$crate::data::slot::Slot>([$($crate::slot_lit!($id: $mo0/$d0/$yr0$( @ $hr0:$m0)? - $mo1/$d1/$yr1$( @ $hr1:$m1)? $([$min_staff])? $(| $name)?)),*])
         };
 
         () => {
@@ -167,10 +229,15 @@ pub(crate) mod test_macros {
                 title: $title.to_string(),
                 desc: String::new(),
                 skills: Default::default(/* TODO */),
-                deadline: None$(.or(Some(
+                soft_deadline: None$(.or(Some(
+                    datetime!($mo/$d/$yr$( @ $hr:$m)?)
+                )))?,
+                hard_deadline: None$(.or(Some(
                     datetime!($mo/$d/$yr$( @ $hr:$m)?)
                 )))?,
+                deadline_offset: None,
                 deps: $crate::data::task::TaskSet::from_iter([$($crate::data::task::TaskId($dep)),*]),
+                preferred_users: Default::default(),
             }
         };
     }
@@ -182,14 +249,11 @@ pub(crate) mod test_macros {
             $([$mo:literal/$d:literal/$yr:literal$( @ $hr:literal:$m:literal)?])?
             { $($dep:literal),* $(,)? }
         ),+ $(,)?) => {
-            [$($crate::task_lit!(
+            $crate::data::index_by_id::<$crate::data::task::Task>([$($crate::task_lit!(
                 $id: $title
                 $([$mo/$d/$yr$( @ $hr:$m)?])?
                 { $($dep),* }
-            )),*]
-                .into_iter()
-                .map(|task| (task.id, task))
-                .collect::<$crate::data::task::TaskMap>()
+            )),*])
         };
 
         () => {
@@ -208,8 +272,10 @@ pub(crate) mod test_macros {
             $crate::data::rule::Rule {
                 id: $crate::data::rule::RuleId($id),
                 include: smallvec::smallvec![$crate::time_interval!($mo0/$d0/$yr0$( @ $hr0:$m0)? - $mo1/$d1/$yr1$( @ $hr1:$m1)?)],
+                exclude: smallvec::SmallVec::new(),
                 rep: None,
                 pref: $crate::data::pref::Preference($pref),
+                set_at: None,
             }
         };
     }
@@ -222,10 +288,7 @@ pub(crate) mod test_macros {
             $mo1:literal/$d1:literal/$yr1:literal$( @ $hr1:literal:$m1:literal)?
             | $pref:expr
         ),* $(,)?) => {
-            [$($crate::rule_lit!($id: $mo0/$d0/$yr0$( @ $hr0:$m0)? - $mo1/$d1/$yr1$( @ $hr1:$m1)? | $pref)),*]
-                .into_iter()
-                .map(|rule| (rule.id, rule))
-                .collect::<$crate::data::rule::RuleMap>()
+            $crate::data::index_by_id::<$crate::data::rule::Rule>([$($crate::rule_lit!($id: $mo0/$d0/$yr0$( @ $hr0:$m0)? - $mo1/$d1/$yr1$( @ $hr1:$m1)? | $pref)),*])
         };
 
         () => {
@@ -253,6 +316,8 @@ pub(crate) mod test_macros {
                     $mo1/$d1/$yr1$( @ $hr1:$m1)?
                     | $pref
                 ),*),
+                rule_order: vec![$($crate::data::rule::RuleId($rule_id)),*],
+                default_availability: None,
                 user_prefs: Default::default(/* TODO */),
                 skills: Default::default(/* TODO */),
             }
@@ -270,10 +335,7 @@ pub(crate) mod test_macros {
                 | $pref:expr
             ),* $(,)?}
         ),+ $(,)?) => {
-            [$($crate::user_lit!($id: $name {$($rule_id: $mo0/$d0/$yr0$( @ $hr0:$m0)? - $mo1/$d1/$yr1$( @ $hr1:$m1)? | $pref),*})),*]
-                .into_iter()
-                .map(|user| (user.id, user))
-                .collect::<$crate::data::user::UserMap>()
+            $crate::data::index_by_id::<$crate::data::user::User>([$($crate::user_lit!($id: $name {$($rule_id: $mo0/$d0/$yr0$( @ $hr0:$m0)? - $mo1/$d1/$yr1$( @ $hr1:$m1)? | $pref),*})),*])
         };
 
         () => {
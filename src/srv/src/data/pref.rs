@@ -47,9 +47,73 @@ use serde::{Deserialize, Serialize};
 /// If unable to be scheduled *separately*, **do not schedule *that* user.**
 ///
 /// **ex:** restraining order, history of harassment
+///
+/// ## NaN
+/// Treated as `0.0`, and persisted as such.
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(try_from = "f32", into = "f32")]
 pub struct Preference(pub f32);
 
+/// The direction of a [`Preference`], independent of its strength.
+///
+/// Together with a non-negative magnitude, this is an alternative to the sign of a [`Preference`]:
+///
+/// | [`Preference`] | kind                              | magnitude |
+/// |----------------|-----------------------------------|-----------|
+/// | `+inf`         | [`Available`](Self::Available)     | `inf`     |
+/// | `0.0..=1.0`    | [`Available`](Self::Available)     | same      |
+/// | `-1.0..0.0`    | [`Unavailable`](Self::Unavailable) | negated   |
+/// | `-inf`         | [`Unavailable`](Self::Unavailable) | `inf`     |
+///
+/// See [`Preference::parts`] and [`Preference::from_parts`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AvailabilityKind {
+    /// For scheduling (or indifferent, at magnitude `0.0`).
+    Available,
+    /// Against scheduling. At magnitude `inf`, forbidden.
+    Unavailable,
+}
+
+/// A float outside the range of a [`Preference`].
+#[derive(Debug, Clone, Copy, PartialEq, thiserror::Error)]
+#[error("preference must be between -1 and +1, or exactly +/-inf, got {_0}")]
+pub struct InvalidPreference(pub f32);
+
+impl TryFrom<f32> for Preference {
+    type Error = InvalidPreference;
+
+    /// Normalizes NaN to `0.0`.
+    #[inline]
+    fn try_from(value: f32) -> Result<Self, Self::Error> {
+        let pref = Self(value).normalized();
+        if pref.0.is_infinite() || (Self::MIN.0..=Self::MAX.0).contains(&pref.0) {
+            Ok(pref)
+        } else {
+            Err(InvalidPreference(value))
+        }
+    }
+}
+
+impl Preference {
+    /// Like [`Preference::try_from`], but [saturates](Self::saturate) out-of-range values instead of failing.
+    ///
+    /// Also returns the error `try_from` would have produced, so the caller can warn about it.
+    pub fn clamped(value: f32) -> (Self, Option<InvalidPreference>) {
+        match Self::try_from(value) {
+            Ok(pref) => (pref, None),
+            Err(e) => (Self(value).saturate(), Some(e)),
+        }
+    }
+}
+
+impl From<Preference> for f32 {
+    #[inline]
+    fn from(value: Preference) -> Self {
+        value.normalized().0
+    }
+}
+
 impl PartialEq for Preference {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
@@ -58,6 +122,13 @@ impl PartialEq for Preference {
 }
 impl Eq for Preference {}
 
+impl std::hash::Hash for Preference {
+    #[inline]
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.bits().hash(state);
+    }
+}
+
 impl PartialOrd for Preference {
     #[inline]
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
@@ -67,9 +138,10 @@ impl PartialOrd for Preference {
 
 impl Ord for Preference {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.0
-            .partial_cmp(&other.0)
-            .expect("preference may be inf, but should never be NaN")
+        self.normalized()
+            .0
+            .partial_cmp(&other.normalized().0)
+            .expect("preference may be inf, but normalized preference is never NaN")
     }
 }
 
@@ -111,6 +183,56 @@ impl Preference {
     /// Maximum (100%) preference
     pub const MAX: Self = Self(1.0);
 
+    /// Replace NaN with `0.0` (no preference).
+    #[inline]
+    pub const fn normalized(self) -> Self {
+        if self.0.is_nan() { Self(0.0) } else { self }
+    }
+
+    /// Bit pattern of the [normalized](Self::normalized) value, with `-0.0` treated as `0.0`,
+    /// so that equal preferences have equal bits.
+    #[inline]
+    const fn bits(self) -> u32 {
+        let value = self.normalized().0;
+        if value == 0.0 { 0 } else { value.to_bits() }
+    }
+
+    /// The direction and non-negative magnitude of the [normalized](Self::normalized) preference.
+    /// See [`AvailabilityKind`] for the mapping.
+    #[inline]
+    pub const fn parts(self) -> (AvailabilityKind, f32) {
+        let value = self.normalized().0;
+        if value < 0.0 {
+            (AvailabilityKind::Unavailable, -value)
+        } else {
+            // -0.0 is no preference, not opposition
+            (AvailabilityKind::Available, value.abs())
+        }
+    }
+
+    /// Inverse of [`Preference::parts`]. The sign of `magnitude` is ignored.
+    #[inline]
+    pub const fn from_parts(kind: AvailabilityKind, magnitude: f32) -> Self {
+        let magnitude = magnitude.abs();
+        match kind {
+            AvailabilityKind::Available => Self(magnitude),
+            AvailabilityKind::Unavailable => Self(-magnitude),
+        }
+    }
+
+    /// The direction of the preference. See [`Preference::parts`].
+    #[inline]
+    pub const fn kind(self) -> AvailabilityKind {
+        self.parts().0
+    }
+
+    /// Whether the preference forbids scheduling outright:
+    /// [`Unavailable`](AvailabilityKind::Unavailable) at infinite magnitude.
+    #[inline]
+    pub const fn forbids(self) -> bool {
+        matches!(self.parts(), (AvailabilityKind::Unavailable, m) if m.is_infinite())
+    }
+
     /// Clamp to `-inf, 0.0..=1.0, +inf`
     pub const fn saturate(self) -> Self {
         if self.0.is_infinite() {
@@ -120,3 +242,86 @@ impl Preference {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nan_is_persisted_as_zero() {
+        let json = serde_json::to_string(&Preference(f32::NAN)).unwrap();
+        assert_eq!(json, "0.0", "NaN should be normalized before serialization");
+        assert_eq!(
+            serde_json::from_str::<Preference>(&json).unwrap(),
+            Preference(0.0)
+        );
+    }
+
+    #[test]
+    fn test_try_from_checks_range() {
+        assert_eq!(Preference::try_from(0.5), Ok(Preference(0.5)));
+        assert_eq!(Preference::try_from(1.5), Err(InvalidPreference(1.5)));
+        assert_eq!(Preference::try_from(-1.5), Err(InvalidPreference(-1.5)));
+        assert_eq!(
+            Preference::try_from(f32::NEG_INFINITY),
+            Ok(Preference::NEG_INFINITY)
+        );
+        assert!(serde_json::from_str::<Preference>("1.5").is_err());
+    }
+
+    #[test]
+    fn test_parts_round_trip() {
+        for value in [
+            f32::NEG_INFINITY,
+            -1.0,
+            -0.25,
+            0.0,
+            -0.0,
+            0.5,
+            1.0,
+            f32::INFINITY,
+        ] {
+            let pref = Preference(value);
+            let (kind, magnitude) = pref.parts();
+            assert!(
+                magnitude >= 0.0,
+                "{pref} has negative magnitude {magnitude}"
+            );
+            assert_eq!(Preference::from_parts(kind, magnitude), pref);
+        }
+        assert_eq!(
+            Preference(-0.25).parts(),
+            (AvailabilityKind::Unavailable, 0.25)
+        );
+        assert_eq!(Preference(-0.0).kind(), AvailabilityKind::Available);
+        assert!(Preference::NEG_INFINITY.forbids());
+        assert!(!Preference::MIN.forbids() && !Preference::INFINITY.forbids());
+    }
+
+    #[test]
+    fn test_hash_collapses_equal_preferences() {
+        let set = rustc_hash::FxHashSet::from_iter([
+            Preference(0.5),
+            Preference(0.5),
+            Preference(0.0),
+            Preference(-0.0),
+            Preference(f32::NAN),
+        ]);
+        assert_eq!(set.len(), 2, "expected {{0.5, 0.0}}, got {set:?}");
+    }
+
+    #[test]
+    fn test_nan_is_loaded_as_zero() {
+        let pref = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader("NaN".as_bytes())
+            .deserialize::<Preference>()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            pref.0, 0.0,
+            "NaN should be normalized after deserialization"
+        );
+    }
+}
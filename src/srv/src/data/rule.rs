@@ -1,12 +1,27 @@
 //! How availability is determined
 
-use crate::data::{Preference, TimeInterval};
+use crate::data::{AvailabilityKind, Preference, TimeInterval};
 use chrono::{DateTime, Days, Months, TimeDelta, Utc};
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
 
 super::id_type!(impl Id<u128> for Rule as 'r');
 
+/// The default for [`MAX_OCCURRENCES`].
+pub const DEFAULT_MAX_OCCURRENCES: usize = 100_000;
+
+/// The most occurrences a [`Repetition`] is ever expanded to.
+///
+/// A repetition like "every second for a decade" would otherwise take hundreds of millions
+/// of steps to evaluate. Repetitions with an `until` that exceed this are rejected
+/// (see [`Repetition::exceeds_max_occurrences`]), while permanent ones are only
+/// evaluated up to this many occurrences. Those with a [fixed period](Frequency::fixed_period)
+/// are counted from the occurrence nearest the queried time, so they never run out;
+/// those repeating by months or years are counted from their start, and so are
+/// evaluated for at least 8333 years at the default.
+pub static MAX_OCCURRENCES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_OCCURRENCES);
+
 /// Once every `n` units. Fields are added together.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct Frequency {
@@ -27,12 +42,42 @@ pub struct Frequency {
 }
 
 impl Frequency {
+    /// The length of the period, if it is always the same.
+    ///
+    /// [`None`] if the period involves months or years, whose lengths vary, or is zero.
+    fn fixed_period(self) -> Option<TimeDelta> {
+        if self.months != 0 || self.years != 0 {
+            return None;
+        }
+        let seconds = (i64::from(self.weeks) * 7 + i64::from(self.days)) * 86_400
+            + i64::from(self.hours) * 3_600
+            + i64::from(self.minutes) * 60
+            + i64::from(self.seconds);
+        TimeDelta::try_seconds(seconds).filter(|period| *period > TimeDelta::zero())
+    }
+
+    /// Add the period to `date`.
+    ///
+    /// Every step of the aggregation is checked, so this returns [`None`] rather than panicking
+    /// if the period cannot be represented or the result is out of range for [`DateTime`].
+    ///
+    /// With every field maxed out, the longest representable period is
+    /// 65535 years + 255 months + 255 weeks + 255 days + 255 hours + 255 minutes + 255 seconds,
+    /// which is applied as 786675 months, 2040 days, and 933555 seconds.
     #[inline]
     fn checked_add_date(self, date: DateTime<Utc>) -> Option<DateTime<Utc>> {
-        let seconds = i64::from(self.seconds) + 60 * i64::from(self.minutes);
-        let days = u64::from(self.days) + 7 * u64::from(self.weeks);
-        let months = u32::from(self.months) + 12 * u32::from(self.years);
-        date.checked_add_signed(TimeDelta::seconds(seconds))?
+        let seconds = i64::from(self.hours)
+            .checked_mul(60)?
+            .checked_add(i64::from(self.minutes))?
+            .checked_mul(60)?
+            .checked_add(i64::from(self.seconds))?;
+        let days = u64::from(self.weeks)
+            .checked_mul(7)?
+            .checked_add(u64::from(self.days))?;
+        let months = u32::from(self.years)
+            .checked_mul(12)?
+            .checked_add(u32::from(self.months))?;
+        date.checked_add_signed(TimeDelta::try_seconds(seconds)?)?
             .checked_add_days(Days::new(days))?
             .checked_add_months(Months::new(months))
     }
@@ -54,6 +99,7 @@ pub struct Repetition {
 struct RepetitionIter<'a> {
     rep: &'a Repetition,
     curr: Option<DateTime<Utc>>,
+    remaining: usize,
 }
 
 impl Iterator for RepetitionIter<'_> {
@@ -61,6 +107,7 @@ impl Iterator for RepetitionIter<'_> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
+        self.remaining = self.remaining.checked_sub(1)?;
         self.curr
             .filter(|date| self.rep.until.as_ref().is_none_or(|end| date <= end))
             .inspect(|date| {
@@ -70,13 +117,44 @@ impl Iterator for RepetitionIter<'_> {
 }
 
 impl Repetition {
+    /// Iterate over up to [`MAX_OCCURRENCES`] occurrences, skipping ahead to the last one
+    /// at most `offset` after [`start`](Self::start) if the [period is fixed](Frequency::fixed_period).
+    ///
+    /// Every occurrence at least `offset` after `start` is reached before the cap, unless
+    /// it is more than [`MAX_OCCURRENCES`] periods later.
+    fn iter_from(&self, offset: TimeDelta) -> RepetitionIter<'_> {
+        let skipped = self
+            .every
+            .fixed_period()
+            .filter(|_| offset > TimeDelta::zero())
+            .and_then(|period| {
+                let steps = offset.num_seconds() / period.num_seconds();
+                let skip = TimeDelta::try_seconds(steps.checked_mul(period.num_seconds())?)?;
+                self.start.checked_add_signed(skip)
+            });
+        RepetitionIter {
+            rep: self,
+            curr: Some(skipped.unwrap_or(self.start)),
+            remaining: MAX_OCCURRENCES.load(Relaxed),
+        }
+    }
+
     #[inline]
-    fn iter(&self) -> RepetitionIter<'_> {
+    fn iter_up_to(&self, limit: usize) -> RepetitionIter<'_> {
         RepetitionIter {
             rep: self,
             curr: Some(self.start),
+            remaining: limit,
         }
     }
+
+    /// Whether the repetition ends, but only after more than [`MAX_OCCURRENCES`] occurrences.
+    ///
+    /// Takes at most [`MAX_OCCURRENCES`] steps to decide.
+    pub fn exceeds_max_occurrences(&self) -> bool {
+        let max = MAX_OCCURRENCES.load(Relaxed);
+        self.until.is_some() && self.iter_up_to(max.saturating_add(1)).nth(max).is_some()
+    }
 }
 
 /// A rule for determining availability.
@@ -84,6 +162,7 @@ impl Repetition {
 /// Ex:
 /// - "available every Monday 3pm-7pm"
 /// - "never available on Fridays"
+/// - "available all week except Wednesday afternoon"
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Rule {
     /// Duplicate of the rule's ID.
@@ -92,43 +171,342 @@ pub struct Rule {
     /// The specific intervals this rule involves, before repeating.
     pub include: SmallVec<[TimeInterval; 1]>,
 
-    /// How often `include` repeats. [`None`] if one-off.
+    /// Windows carved out of `include`, before repeating.
+    #[serde(default)]
+    pub exclude: SmallVec<[TimeInterval; 0]>,
+
+    /// How often `include` and `exclude` repeat. [`None`] if one-off.
     pub rep: Option<Repetition>,
 
     /// How strongly to enforce this rule.
     pub pref: Preference,
+
+    /// When `pref` was stated. [`None`] if unknown, in which case it never decays.
+    ///
+    /// See [`Decay`](crate::algo::Decay).
+    #[serde(default)]
+    pub set_at: Option<DateTime<Utc>>,
 }
 
 impl Rule {
+    /// The key rules are ordered by for display: earliest [`include`](Rule::include) start first,
+    /// then by [preference](Rule::pref), then by ID.
+    ///
+    /// IDs are unique within a [`RuleMap`], so rules in one map never tie.
+    pub fn sort_key(&self) -> (Option<DateTime<Utc>>, Preference, u128) {
+        let earliest = self.include.iter().map(|t| t.start).min();
+        (earliest, self.pref, self.id.0)
+    }
+
+    /// Whether the rule is for or against the times it covers. See [`Preference::parts`].
+    #[inline]
+    pub const fn kind(&self) -> AvailabilityKind {
+        self.pref.kind()
+    }
+
     /// Whether the rule fully covers the interval with at least one
-    /// `include` or the repetition of an `include`.
+    /// `include` or the repetition of an `include`, without any
+    /// `exclude` or repetition of an `exclude` cutting into it.
     pub fn contains(&self, interval: &TimeInterval) -> bool {
+        self.is_included(interval) && !self.is_excluded(interval)
+    }
+
+    /// The length of the shortest `include` that fully covers the interval (or does so
+    /// when repeated), or [`None`] if the rule does not [contain](Self::contains) it.
+    ///
+    /// A shorter cover means the rule is more specific to the interval.
+    pub fn narrowest_cover(&self, interval: &TimeInterval) -> Option<TimeDelta> {
+        if self.is_excluded(interval) {
+            return None;
+        }
+        self.include
+            .iter()
+            .filter(|t| self.include_covers(t, interval))
+            .map(|t| t.end - t.start)
+            .min()
+    }
+
+    /// How much of `interval` the rule covers: the time within it that is inside some `include`
+    /// (or repetition of one) without being inside any `exclude` (or repetition of one).
+    ///
+    /// Unlike [`contains`](Self::contains), this measures partial coverage.
+    pub fn overlap(&self, interval: &TimeInterval) -> TimeDelta {
+        self.covered(interval).iter().map(|t| t.end - t.start).sum()
+    }
+
+    /// Every part of time the rule covers, in order, or [`None`] if it repeats forever.
+    pub fn coverage(&self) -> Option<Vec<TimeInterval>> {
+        let start = self.include.iter().map(|t| t.start).min()?;
+        let end = self.include.iter().map(|t| t.end).max()?;
+        let extent = match self.rep {
+            None => TimeInterval { start, end },
+            Some(Repetition {
+                start: rep_start,
+                until: Some(until),
+                ..
+            }) => TimeInterval {
+                start: start.min(rep_start),
+                end: end.max(until),
+            },
+            Some(_) => return None,
+        };
+        Some(self.covered(&extent))
+    }
+
+    /// The parts of `interval` the rule covers, in order: the time within it that is inside some `include`
+    /// (or repetition of one) without being inside any `exclude` (or repetition of one).
+    pub fn covered(&self, interval: &TimeInterval) -> Vec<TimeInterval> {
+        let window = match self.rep {
+            Some(rep) => TimeInterval {
+                start: interval.start.max(rep.start),
+                end: rep
+                    .until
+                    .map_or(interval.end, |until| interval.end.min(until)),
+            },
+            None => *interval,
+        };
+        if window.start >= window.end {
+            return Vec::new();
+        }
+        let included = merged(self.occurrences(&self.include, &window));
+        let excluded = merged(self.occurrences(&self.exclude, &window));
+        let mut covered = Vec::with_capacity(included.len());
+        for t in included {
+            // both are sorted and disjoint, so each hole can be cut in turn
+            let mut start = t.start;
+            for x in excluded.iter().filter(|x| x.is_overlapping(&t)) {
+                if start < x.start {
+                    covered.push(TimeInterval {
+                        start,
+                        end: x.start,
+                    });
+                }
+                start = start.max(x.end);
+            }
+            if start < t.end {
+                covered.push(TimeInterval { start, end: t.end });
+            }
+        }
+        covered
+    }
+
+    /// The parts of `intervals`, and of their repetitions, that fall within `window`.
+    fn occurrences(&self, intervals: &[TimeInterval], window: &TimeInterval) -> Vec<TimeInterval> {
+        match self.rep {
+            Some(rep) => {
+                let (Some(earliest), Some(latest_end)) = (
+                    intervals.iter().map(|t| t.start).min(),
+                    intervals.iter().map(|t| t.end).max(),
+                ) else {
+                    return Vec::new();
+                };
+                rep.iter_from(window.start - latest_end)
+                    .map(|date| date.signed_duration_since(rep.start))
+                    .take_while(|&offset| {
+                        earliest
+                            .checked_add_signed(offset)
+                            .is_some_and(|start| start < window.end)
+                    })
+                    .flat_map(|offset| intervals.iter().filter_map(move |t| t.shifted(offset)))
+                    .filter_map(|t| TimeInterval::clamp(&t, window))
+                    .collect()
+            }
+            None => intervals
+                .iter()
+                .filter_map(|t| TimeInterval::clamp(t, window))
+                .collect(),
+        }
+    }
+
+    fn is_included(&self, interval: &TimeInterval) -> bool {
+        self.include
+            .iter()
+            .any(|t| self.include_covers(t, interval))
+    }
+
+    /// Whether `include`, or a repetition of it, fully covers the interval.
+    fn include_covers(&self, include: &TimeInterval, interval: &TimeInterval) -> bool {
         match self.rep {
             Some(rep) => {
                 // bounds test
                 (interval.start >= rep.start && rep.until.is_none_or(|end| interval.end <= end))
-                    && rep.iter().any(|date| {
-                        // TODO: consider something akin to modulo
-                        let offset = date.signed_duration_since(rep.start);
-                        self.include
+                    && rep
+                        .iter_from(interval.end - include.end)
+                        .map(|date| date.signed_duration_since(rep.start))
+                        // repetitions only move later, so nothing can cover
+                        // once the include starts after the interval
+                        .take_while(|&offset| {
+                            include
+                                .start
+                                .checked_add_signed(offset)
+                                .is_some_and(|start| start <= interval.start)
+                        })
+                        .any(|offset| {
+                            // TODO: consider something akin to modulo
+                            include
+                                .shifted(offset)
+                                .is_some_and(|t| t.contains(interval))
+                        })
+            }
+            None => include.contains(interval),
+        }
+    }
+
+    /// Whether any `exclude` or repetition of an `exclude` cuts into the interval.
+    /// Touching the interval at an endpoint does not count.
+    fn is_excluded(&self, interval: &TimeInterval) -> bool {
+        let cuts = |t: &TimeInterval| t.start < interval.end && interval.start < t.end;
+        match self.rep {
+            Some(rep) => {
+                let (Some(earliest), Some(latest_end)) = (
+                    self.exclude.iter().map(|t| t.start).min(),
+                    self.exclude.iter().map(|t| t.end).max(),
+                ) else {
+                    return false;
+                };
+                rep.iter_from(interval.start - latest_end)
+                    .map(|date| date.signed_duration_since(rep.start))
+                    // repetitions only move later, so nothing can cut in
+                    // once even the earliest exclude starts after the interval
+                    .take_while(|&offset| {
+                        earliest
+                            .checked_add_signed(offset)
+                            .is_some_and(|start| start < interval.end)
+                    })
+                    .any(|offset| {
+                        self.exclude
                             .iter()
-                            .filter_map(|t| {
-                                t.start
-                                    .checked_add_signed(offset)
-                                    .zip(t.end.checked_add_signed(offset))
-                                    .map(|(start, end)| TimeInterval { start, end })
-                            })
-                            .any(|t| t.contains(interval))
+                            .filter_map(|t| t.shifted(offset))
+                            .any(|t| cuts(&t))
                     })
             }
-            None => self.include.iter().any(|t| t.contains(interval)),
+            None => self.exclude.iter().any(cuts),
+        }
+    }
+}
+
+/// Sort `intervals` and join any that overlap or touch, so that none of the results overlap.
+fn merged(mut intervals: Vec<TimeInterval>) -> Vec<TimeInterval> {
+    intervals.sort();
+    let mut out = Vec::<TimeInterval>::with_capacity(intervals.len());
+    for t in intervals {
+        match out.last_mut() {
+            Some(last) if t.start <= last.end => last.end = last.end.max(t.end),
+            _ => out.push(t),
         }
     }
+    out
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{rule_lit, time_interval};
+    use super::*;
+    use crate::{datetime, rule_lit, time_interval};
+
+    #[test]
+    fn test_id_round_trip() {
+        let id = RuleId(0xbeef);
+        assert_eq!(id.to_string().parse::<RuleId>(), Ok(id));
+        assert_eq!(
+            "u.beef".parse::<RuleId>(),
+            Err(crate::data::ParseIdError::Prefix('r'))
+        );
+        assert!(matches!(
+            "r.xyz".parse::<RuleId>(),
+            Err(crate::data::ParseIdError::Int(_))
+        ));
+    }
+
+    #[test]
+    fn test_maxed_frequency() {
+        let every = Frequency {
+            seconds: u8::MAX,
+            minutes: u8::MAX,
+            hours: u8::MAX,
+            days: u8::MAX,
+            weeks: u8::MAX,
+            months: u8::MAX,
+            years: u16::MAX,
+        };
+        let start = datetime!(4/5/2025);
+        let expect = start
+            .checked_add_signed(TimeDelta::seconds(933_555))
+            .and_then(|date| date.checked_add_days(Days::new(2040)))
+            .and_then(|date| date.checked_add_months(Months::new(786_675)));
+        assert_eq!(every.checked_add_date(start), expect);
+        assert!(expect.is_some());
+        assert_eq!(every.checked_add_date(DateTime::<Utc>::MAX_UTC), None);
+    }
+
+    #[test]
+    fn test_occurrences_are_capped() {
+        let every_second = Frequency {
+            seconds: 1,
+            ..Frequency::default()
+        };
+        let decade = Repetition {
+            every: every_second,
+            start: datetime!(1/1/2025),
+            until: Some(datetime!(1/1/2035)),
+        };
+        assert!(decade.exceeds_max_occurrences());
+        assert!(
+            !Repetition {
+                until: Some(datetime!(1/2/2025)),
+                ..decade
+            }
+            .exceeds_max_occurrences()
+        );
+
+        // a permanent repetition is evaluated from near the query rather than from its start
+        let rule = Rule {
+            rep: Some(Repetition {
+                until: None,
+                ..decade
+            }),
+            ..rule_lit! { 0: 1/1/2025 @ 9:00 - 1/1/2025 @ 9:30 | 1.0 }
+        };
+        assert!(rule.contains(&time_interval! { 1/1/2030 @ 9:00 - 1/1/2030 @ 9:30 }));
+    }
+
+    #[test]
+    fn test_permanent_hourly_rule_far_ahead() {
+        let rule = Rule {
+            exclude: smallvec::smallvec![time_interval! { 1/1/2025 @ 9:45 - 1/1/2025 @ 9:50 }],
+            rep: Some(Repetition {
+                every: Frequency {
+                    hours: 1,
+                    ..Frequency::default()
+                },
+                start: datetime!(1/1/2025),
+                until: None,
+            }),
+            ..rule_lit! { 0: 1/1/2025 @ 9:00 - 1/1/2025 @ 9:30 | 1.0 }
+        };
+        // well past the 100000 hours (about 11 years) that counting from the start could reach
+        let far = time_interval! { 6/1/2045 @ 13:00 - 6/1/2045 @ 13:30 };
+        assert!(rule.contains(&far));
+        assert!(!rule.contains(&time_interval! { 6/1/2045 @ 13:30 - 6/1/2045 @ 13:40 }));
+        assert!(rule.is_excluded(&time_interval! { 6/1/2045 @ 13:40 - 6/1/2045 @ 13:50 }));
+        assert_eq!(rule.covered(&far), vec![far]);
+        assert_eq!(
+            rule.overlap(&time_interval! { 6/1/2045 @ 13:00 - 6/1/2045 @ 15:00 }),
+            TimeDelta::hours(1)
+        );
+    }
+
+    #[test]
+    fn test_frequency_counts_hours() {
+        let every = Frequency {
+            hours: 2,
+            minutes: 30,
+            ..Default::default()
+        };
+        assert_eq!(
+            every.checked_add_date(datetime!(4/5/2025 @ 9:00)),
+            Some(datetime!(4/5/2025 @ 11:30))
+        );
+    }
 
     #[test]
     fn test_one_include_no_rep() {
@@ -153,7 +531,7 @@ mod tests {
         );
 
         assert!(
-            rule.contains(&time_interval! { 4/6/2025 - 5/6/2025 }),
+            rule.contains(&time_interval! { 4/6/2025 - 5/5/2025 }),
             "later start should count as contained"
         );
         assert!(
@@ -166,6 +544,109 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_exclude_carves_hole() {
+        let rule = Rule {
+            exclude: smallvec::smallvec![time_interval! { 4/9/2025 @ 12:00 - 4/9/2025 @ 17:00 }],
+            ..rule_lit! { 0: 4/7/2025 - 4/12/2025 | 1.0 }
+        };
+
+        assert!(
+            !rule.contains(&time_interval! { 4/9/2025 @ 13:00 - 4/9/2025 @ 14:00 }),
+            "query inside the hole should not count as contained"
+        );
+        assert!(
+            !rule.contains(&time_interval! { 4/9/2025 @ 9:00 - 4/9/2025 @ 13:00 }),
+            "query partially inside the hole should not count as contained"
+        );
+        assert!(
+            rule.contains(&time_interval! { 4/9/2025 @ 9:00 - 4/9/2025 @ 12:00 }),
+            "query ending where the hole starts should count as contained"
+        );
+        assert!(
+            rule.contains(&time_interval! { 4/10/2025 @ 13:00 - 4/10/2025 @ 14:00 }),
+            "query outside the hole should count as contained"
+        );
+    }
+
+    #[test]
+    fn test_overlap() {
+        let rule = Rule {
+            exclude: smallvec::smallvec![time_interval! { 4/7/2025 @ 12:00 - 4/7/2025 @ 13:00 }],
+            rep: Some(Repetition {
+                every: Frequency {
+                    days: 1,
+                    ..Default::default()
+                },
+                start: datetime!(4/7/2025),
+                until: None,
+            }),
+            ..rule_lit! { 0: 4/7/2025 @ 9:00 - 4/7/2025 @ 17:00 | 1.0 }
+        };
+
+        assert_eq!(
+            rule.overlap(&time_interval! { 4/8/2025 @ 10:00 - 4/8/2025 @ 11:00 }),
+            TimeDelta::hours(1),
+            "fully covered query should overlap entirely"
+        );
+        assert_eq!(
+            rule.overlap(&time_interval! { 4/8/2025 @ 11:00 - 4/8/2025 @ 14:00 }),
+            TimeDelta::hours(2),
+            "repeated hole should be subtracted"
+        );
+        assert_eq!(
+            rule.overlap(&time_interval! { 4/8/2025 @ 16:00 - 4/9/2025 @ 10:00 }),
+            TimeDelta::hours(2),
+            "overlap should be summed across repetitions"
+        );
+        assert_eq!(
+            rule.overlap(&time_interval! { 4/6/2025 @ 9:00 - 4/6/2025 @ 17:00 }),
+            TimeDelta::zero(),
+            "query before the repetition starts should not overlap"
+        );
+    }
+
+    #[test]
+    fn test_covered() {
+        let rule = Rule {
+            exclude: smallvec::smallvec![time_interval! { 4/7/2025 @ 12:00 - 4/7/2025 @ 13:00 }],
+            ..rule_lit! { 0: 4/7/2025 @ 9:00 - 4/7/2025 @ 17:00 | 1.0 }
+        };
+        assert_eq!(
+            rule.covered(&time_interval! { 4/7/2025 @ 10:00 - 4/7/2025 @ 18:00 }),
+            [
+                time_interval! { 4/7/2025 @ 10:00 - 4/7/2025 @ 12:00 },
+                time_interval! { 4/7/2025 @ 13:00 - 4/7/2025 @ 17:00 },
+            ],
+            "the hole should split the cover, and the query should clip it"
+        );
+    }
+
+    #[test]
+    fn test_exclude_repeats_with_include() {
+        let rule = Rule {
+            exclude: smallvec::smallvec![time_interval! { 4/9/2025 @ 12:00 - 4/9/2025 @ 17:00 }],
+            rep: Some(Repetition {
+                every: Frequency {
+                    weeks: 1,
+                    ..Default::default()
+                },
+                start: datetime!(4/7/2025),
+                until: None,
+            }),
+            ..rule_lit! { 0: 4/7/2025 - 4/12/2025 | 1.0 }
+        };
+
+        assert!(
+            !rule.contains(&time_interval! { 4/16/2025 @ 13:00 - 4/16/2025 @ 14:00 }),
+            "query inside a repetition of the hole should not count as contained"
+        );
+        assert!(
+            rule.contains(&time_interval! { 4/17/2025 @ 13:00 - 4/17/2025 @ 14:00 }),
+            "query outside any repetition of the hole should count as contained"
+        );
+    }
+
     #[test]
     fn test_multiple_include_no_rep() {
         let rule = rule_lit! { 0: 4/5/2025 - 5/5/2025 | 0.0 };
@@ -0,0 +1,92 @@
+//! (De)serialize [`TimeDelta`]s as whole seconds, for use with `#[serde(with = "...")]`.
+//!
+//! Durations are never negative, so negative seconds are rejected when deserializing.
+//! Fractions of a second are truncated when serializing.
+
+use chrono::TimeDelta;
+use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+/// Serialize `delta` as its number of whole seconds.
+pub fn serialize<S: Serializer>(delta: &TimeDelta, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_i64(delta.num_seconds())
+}
+
+/// Deserialize a non-negative number of seconds.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<TimeDelta, D::Error> {
+    from_secs(i64::deserialize(deserializer)?)
+}
+
+fn from_secs<E: Error>(secs: i64) -> Result<TimeDelta, E> {
+    if secs < 0 {
+        return Err(E::custom(format_args!(
+            "duration must not be negative, got {secs} seconds"
+        )));
+    }
+    TimeDelta::try_seconds(secs)
+        .ok_or_else(|| E::custom(format_args!("duration of {secs} seconds is too long")))
+}
+
+/// Like [`seconds`](self), for optional durations. [`None`] is `null`.
+pub mod option {
+    use super::*;
+
+    /// Serialize `delta` as its number of whole seconds, or `null`.
+    pub fn serialize<S: Serializer>(
+        delta: &Option<TimeDelta>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match delta {
+            Some(delta) => super::serialize(delta, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    /// Deserialize a non-negative number of seconds, or `null`.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<TimeDelta>, D::Error> {
+        Option::<i64>::deserialize(deserializer)?
+            .map(from_secs)
+            .transpose()
+    }
+}
+
+/// Like [`option`], for [`Update`](crate::integration::Update)s of optional durations.
+///
+/// Use with `#[serde(default)]`, so that a missing field keeps the existing value
+/// while `null` clears it.
+pub mod update {
+    use super::*;
+
+    /// Deserialize a non-negative number of seconds, or `null`, as a replacement value.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Option<TimeDelta>>, D::Error> {
+        option::deserialize(deserializer).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Wrapper(#[serde(with = "super")] TimeDelta);
+
+    #[test]
+    fn test_round_trip() {
+        let json = serde_json::to_string(&Wrapper(TimeDelta::minutes(90))).unwrap();
+        assert_eq!(json, "5400");
+        assert_eq!(
+            serde_json::from_str::<Wrapper>(&json).unwrap(),
+            Wrapper(TimeDelta::minutes(90))
+        );
+    }
+
+    #[test]
+    fn test_rejects_negative() {
+        let err = serde_json::from_str::<Wrapper>("-1").unwrap_err();
+        assert!(err.to_string().contains("negative"), "{err}");
+    }
+}
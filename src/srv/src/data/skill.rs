@@ -1,10 +1,12 @@
 //! See [`Skill`]
 
+use crate::data::TimeInterval;
+use chrono::prelude::*;
 use serde::{Deserialize, Serialize};
 
 super::id_type!(
     /// Used to determine which users *can* be scheduled on a [`Task`].
-    impl Id<u32> for Skill as 's'
+    impl Id<u32> for Skill as 'k'
 );
 
 /// Metadata regarding a skill
@@ -21,9 +23,75 @@ pub struct Skill {
 /// 0.0 = no skill.
 /// 1.0 = skill of one user with baseline skill.
 /// Can be multiplied by number of users.
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default, Serialize, Deserialize)]
+/// NaN is treated as no skill, and persisted as such.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(try_from = "f32", into = "f32")]
 pub struct Proficiency(f32);
 
+/// A float outside the range of a [`Proficiency`].
+#[derive(Debug, Clone, Copy, PartialEq, thiserror::Error)]
+#[error("proficiency must be finite and not negative, got {_0}")]
+pub struct InvalidProficiency(pub f32);
+
+impl PartialEq for Proficiency {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.normalized().0 == other.normalized().0
+    }
+}
+
+impl Eq for Proficiency {}
+
+impl PartialOrd for Proficiency {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.normalized().0.partial_cmp(&other.normalized().0)
+    }
+}
+
+impl std::hash::Hash for Proficiency {
+    #[inline]
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.bits().hash(state);
+    }
+}
+
+impl TryFrom<f32> for Proficiency {
+    type Error = InvalidProficiency;
+
+    /// Normalizes NaN to [`Proficiency::ZERO`].
+    #[inline]
+    fn try_from(value: f32) -> Result<Self, Self::Error> {
+        let proficiency = Self(value).normalized();
+        if proficiency.0.is_finite() && proficiency.0 >= 0.0 {
+            Ok(proficiency)
+        } else {
+            Err(InvalidProficiency(value))
+        }
+    }
+}
+
+impl Proficiency {
+    /// Like [`Proficiency::try_from`], but [saturates](Self::saturate) finite values that are
+    /// out of range instead of failing. Infinities are still rejected.
+    ///
+    /// Also returns the error `try_from` would have produced, so the caller can warn about it.
+    pub fn clamped(value: f32) -> Result<(Self, Option<InvalidProficiency>), InvalidProficiency> {
+        match Self::try_from(value) {
+            Ok(proficiency) => Ok((proficiency, None)),
+            Err(e) if value.is_finite() => Ok((Self(value).saturate(), Some(e))),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl From<Proficiency> for f32 {
+    #[inline]
+    fn from(value: Proficiency) -> Self {
+        value.normalized().0
+    }
+}
+
 impl std::fmt::Display for Proficiency {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if self.0.is_infinite() {
@@ -59,11 +127,149 @@ impl Proficiency {
     pub const ONE: Self = Self(1.0);
     /// Alias for [`Self::ZERO`]
     pub const MIN: Self = Self::ZERO;
-    /// Alias for [`f32::MAX`]
-    pub const MAX: Self = Self(f32::MAX);
+    /// Greatest [reasonable](Self::is_reasonable) proficiency;
+    /// equivalent to 100 users with baseline skill.
+    pub const MAX: Self = Self(100.0);
+    /// No upper limit. Only meaningful as a bound, such as [`ProficiencyReq::hard_max`](crate::data::ProficiencyReq::hard_max).
+    pub const UNBOUNDED: Self = Self(f32::INFINITY);
+
+    /// Replace NaN with [`Self::ZERO`].
+    #[inline]
+    pub const fn normalized(self) -> Self {
+        if self.0.is_nan() { Self::ZERO } else { self }
+    }
+
+    /// Bit pattern of the [normalized](Self::normalized) value, with `-0.0` treated as `0.0`,
+    /// so that equal proficiencies have equal bits.
+    #[inline]
+    const fn bits(self) -> u32 {
+        let value = self.normalized().0;
+        if value == 0.0 { 0 } else { value.to_bits() }
+    }
 
     /// Clamp between [`Self::MIN`] and [`Self::MAX`]
     pub const fn saturate(self) -> Self {
         Self(self.0.clamp(Self::MIN.0, Self::MAX.0))
     }
+
+    /// Whether the proficiency is between [`Self::MIN`] and [`Self::MAX`].
+    ///
+    /// Anything else (including NaN and infinity) is almost certainly a mistake.
+    pub const fn is_reasonable(self) -> bool {
+        Self::MIN.0 <= self.0 && self.0 <= Self::MAX.0
+    }
+}
+
+/// (De)serialize an upper-bound [`Proficiency`], for use with `#[serde(with = "...")]`.
+///
+/// [`Proficiency::UNBOUNDED`] has no representation in most formats (JSON writes it as `null`),
+/// so it is persisted as a missing value instead. A literal `inf`, as older CSV files contain,
+/// is still read as unbounded even though [`Proficiency::try_from`] rejects infinity.
+pub mod bound {
+    use super::Proficiency;
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    /// Serialize `bound`, or `null` if it is [unbounded](Proficiency::UNBOUNDED).
+    pub fn serialize<S: Serializer>(bound: &Proficiency, serializer: S) -> Result<S::Ok, S::Error> {
+        if bound.is_infinite() {
+            serializer.serialize_none()
+        } else {
+            serializer.serialize_some(bound)
+        }
+    }
+
+    /// Deserialize a [`Proficiency`], or `null` or `inf` as [`Proficiency::UNBOUNDED`].
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Proficiency, D::Error> {
+        match Option::<f32>::deserialize(deserializer)? {
+            None | Some(f32::INFINITY) => Ok(Proficiency::UNBOUNDED),
+            Some(value) => Proficiency::try_from(value).map_err(D::Error::custom),
+        }
+    }
+}
+
+/// A [`User`](crate::data::User)'s level of a skill.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct UserSkill {
+    /// How skilled the user is.
+    pub proficiency: Proficiency,
+
+    /// When the skill stops counting, such as for a certification.
+    /// [`None`] if permanent.
+    pub expires: Option<DateTime<Utc>>,
+}
+
+impl From<Proficiency> for UserSkill {
+    #[inline]
+    fn from(proficiency: Proficiency) -> Self {
+        Self {
+            proficiency,
+            expires: None,
+        }
+    }
+}
+
+impl UserSkill {
+    /// The proficiency throughout `interval`.
+    ///
+    /// [`Proficiency::ZERO`] if the skill expires before `interval` ends.
+    pub fn proficiency_during(&self, interval: &TimeInterval) -> Proficiency {
+        if self.expires.is_some_and(|expires| expires < interval.end) {
+            Proficiency::ZERO
+        } else {
+            self.proficiency
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_round_trip() {
+        let id = SkillId(0x10);
+        assert_eq!(id.to_string().parse::<SkillId>(), Ok(id));
+        assert_eq!(
+            "t.10".parse::<SkillId>(),
+            Err(crate::data::ParseIdError::Prefix('k'))
+        );
+        assert_eq!(
+            "s.10".parse::<SkillId>(),
+            Err(crate::data::ParseIdError::Prefix('k')),
+            "slot IDs should not parse as skill IDs"
+        );
+        assert!(matches!(
+            "k.xyz".parse::<SkillId>(),
+            Err(crate::data::ParseIdError::Int(_))
+        ));
+    }
+
+    #[test]
+    fn test_hash_collapses_equal_proficiencies() {
+        let (mut nan, mut neg_zero) = (Proficiency::ONE, Proficiency::ONE);
+        *nan = f32::NAN;
+        *neg_zero = -0.0;
+        let set = rustc_hash::FxHashSet::from_iter([
+            Proficiency::ONE,
+            Proficiency::ONE,
+            Proficiency::ZERO,
+            neg_zero,
+            nan,
+        ]);
+        assert_eq!(set.len(), 2, "expected {{1.0, 0.0}}, got {set:?}");
+    }
+
+    #[test]
+    fn test_nan_is_persisted_as_zero() {
+        let mut prof = Proficiency::ONE;
+        *prof = f32::NAN;
+        let json = serde_json::to_string(&prof).unwrap();
+        assert_eq!(json, "0.0", "NaN should be normalized before serialization");
+        assert_eq!(
+            serde_json::from_str::<Proficiency>(&json).unwrap(),
+            Proficiency::ZERO
+        );
+    }
 }
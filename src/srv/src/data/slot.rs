@@ -1,11 +1,13 @@
 //! See [`Slot`]
 
-use chrono::prelude::*;
+use crate::data::TaskSet;
+use chrono::{DurationRound, RoundingError, TimeDelta, prelude::*};
 use miette::Result;
 use serde::{Deserialize, Serialize, de::Visitor};
 use std::num::NonZeroUsize;
 
 super::id_type!(impl Id<u128> for Slot as 's');
+super::id_type!(impl Id<u64> for SlotTemplate as 'p');
 
 /// A timerange, mainly intended for timeslots.
 ///
@@ -171,9 +173,11 @@ impl Ord for TimeInterval {
 impl TimeInterval {
     /// Returns whether `self` and `other` occupy some shared range of time.
     /// i.e. their intersection is non-null.
-    pub(crate) fn _is_overlapping(&self, other: &Self) -> bool {
+    ///
+    /// Intervals are half-open, so back-to-back intervals do not overlap.
+    pub(crate) fn is_overlapping(&self, other: &Self) -> bool {
         debug_assert!(self.start <= self.end && other.start <= other.end);
-        !(self.end < other.start || other.end < self.start)
+        self.start < other.end && other.start < self.end
     }
 
     /// Returns whether `self` completely encloses `other`.
@@ -181,13 +185,55 @@ impl TimeInterval {
         debug_assert!(self.start <= self.end && other.start <= other.end);
         self.start <= other.start && other.end <= self.end
     }
+
+    /// Returns whether `t` is within `self`.
+    ///
+    /// Intervals are half-open: `start` is included, `end` is not.
+    pub(crate) fn contains_instant(&self, t: DateTime<Utc>) -> bool {
+        debug_assert!(self.start <= self.end);
+        std::ops::RangeBounds::contains(self, &t)
+    }
+
+    /// The time shared by `self` and `other`, or [`None`] if they do not [overlap](Self::is_overlapping).
+    #[inline]
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+        (start < end).then_some(Self { start, end })
+    }
+
+    /// The portion of `self` within `bounds`, or [`None`] if they are disjoint.
+    ///
+    /// Useful for clipping intervals to a visible window.
+    ///
+    /// Call as `TimeInterval::clamp(&interval, &bounds)`: method syntax on an owned interval
+    /// resolves to [`Ord::clamp`] instead.
+    #[inline]
+    pub fn clamp(&self, bounds: &TimeInterval) -> Option<Self> {
+        self.intersection(bounds)
+    }
+
+    /// Move both ends of the interval by `by`, preserving its duration.
+    ///
+    /// Returns [`None`] if either end would overflow.
+    #[inline]
+    pub fn shifted(&self, by: TimeDelta) -> Option<Self> {
+        Some(Self {
+            start: self.start.checked_add_signed(by)?,
+            end: self.end.checked_add_signed(by)?,
+        })
+    }
 }
 
 /// A segment of time that can be allocated for work, such as a "shift".
 ///
 /// Slots are ordered by their [`interval`](`Slot::interval`)
 /// (See [`TimeInterval` ordering](TimeInterval#ordering)).
+///
+/// The interval's `start` and `end` are serialized alongside the other fields,
+/// matching the shape of [`PySlot`](crate::integration::PySlot).
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "SlotRepr", into = "SlotRepr")]
 pub struct Slot {
     /// Duplicate of the slot's ID.
     pub id: SlotId,
@@ -197,17 +243,100 @@ pub struct Slot {
 
     /// [`None`]: Slot exists as an opportunity to
     /// work on tasks, not as a shift that must be
-    /// covered.
+    /// covered. Nobody is staffed on it besides
+    /// pinned users and users a worked task
+    /// insists on (see [`task_affinity`](crate::algo::task_affinity)).
     ///
     /// [`Some`]: an error may be emitted if there
     /// is not enough staff to cover the shift,
     /// even if all tasks are completed.
     pub min_staff: Option<NonZeroUsize>,
 
+    /// The most staff the slot can hold.
+    /// Generation fails if more users are pinned to the slot, or insisted on by its tasks.
+    ///
+    /// [`None`]: No limit.
+    pub max_staff: Option<NonZeroUsize>,
+
+    /// Tasks the manager would like done during this slot, such as "this slot is for inventory".
+    ///
+    /// A hint rather than a requirement. Placing tasks in slots is out of scope for the scheduler,
+    /// so this only biases who is staffed on the slot (see [`task_affinity`](crate::algo::task_affinity))
+    /// and which slot [`suggest_slots`](crate::algo::suggest_slots) counts as serving a task
+    /// (see [`choose_slot`](crate::algo::choose_slot)).
+    pub preferred_tasks: TaskSet,
+
     /// Name for the slot. Empty if unnamed.
     pub name: String,
 }
 
+/// Serialized form of [`Slot`].
+///
+/// `#[serde(flatten)]` would be simpler, but csv does not support it.
+#[derive(Serialize, Deserialize)]
+struct SlotRepr {
+    id: SlotId,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    min_staff: Option<NonZeroUsize>,
+    #[serde(default)]
+    max_staff: Option<NonZeroUsize>,
+    /// [`None`] if empty, so that slots without any can still be written as csv.
+    #[serde(default)]
+    preferred_tasks: Option<TaskSet>,
+    name: String,
+}
+
+impl TryFrom<SlotRepr> for Slot {
+    type Error = &'static str;
+
+    fn try_from(value: SlotRepr) -> Result<Self, Self::Error> {
+        let SlotRepr {
+            id,
+            start,
+            end,
+            min_staff,
+            max_staff,
+            preferred_tasks,
+            name,
+        } = value;
+        if start <= end {
+            Ok(Self {
+                id,
+                interval: TimeInterval { start, end },
+                min_staff,
+                max_staff,
+                preferred_tasks: preferred_tasks.unwrap_or_default(),
+                name,
+            })
+        } else {
+            Err("slot cannot end before it starts")
+        }
+    }
+}
+
+impl From<Slot> for SlotRepr {
+    fn from(value: Slot) -> Self {
+        let Slot {
+            id,
+            interval: TimeInterval { start, end },
+            min_staff,
+            max_staff,
+            preferred_tasks,
+            name,
+        } = value;
+        Self {
+            id,
+            start,
+            end,
+            min_staff,
+            max_staff,
+            preferred_tasks: (!preferred_tasks.is_empty()).then_some(preferred_tasks),
+            name,
+        }
+    }
+}
+
 impl std::ops::Deref for Slot {
     type Target = TimeInterval;
 
@@ -224,9 +353,172 @@ impl std::ops::DerefMut for Slot {
     }
 }
 
+/// A reusable shift definition, such as "8-hour weekday shift, min 2 staff",
+/// that can be [instantiated](SlotTemplate::instantiate) as a [`Slot`] at any start time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotTemplate {
+    /// Duplicate of the template's ID.
+    pub id: SlotTemplateId,
+
+    /// How long each instantiated slot lasts.
+    #[serde(with = "super::seconds")]
+    pub duration: TimeDelta,
+
+    /// See [`Slot::min_staff`].
+    pub min_staff: Option<NonZeroUsize>,
+
+    /// See [`Slot::max_staff`].
+    pub max_staff: Option<NonZeroUsize>,
+
+    /// See [`Slot::preferred_tasks`].
+    pub preferred_tasks: TaskSet,
+
+    /// Name given to each instantiated slot. Empty if unnamed.
+    pub name: String,
+}
+
+impl SlotTemplate {
+    /// Create a slot with this template's staffing and name, starting at `start`.
+    ///
+    /// Returns [`None`] if the slot would end too far in the future to represent.
+    pub fn instantiate(&self, id: SlotId, start: DateTime<Utc>) -> Option<Slot> {
+        Some(Slot {
+            id,
+            interval: TimeInterval {
+                start,
+                end: start.checked_add_signed(self.duration)?,
+            },
+            min_staff: self.min_staff,
+            max_staff: self.max_staff,
+            preferred_tasks: self.preferred_tasks.clone(),
+            name: self.name.clone(),
+        })
+    }
+}
+
+/// What [`Granularity::align`] does with a time that is not a multiple of [`Granularity::step`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GranularityMode {
+    /// Round it to the nearest multiple, rounding halfway times up.
+    #[default]
+    Round,
+
+    /// Reject it.
+    Reject,
+}
+
+/// A fixed step incoming times must fall on, such as every 15 minutes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Granularity {
+    /// The step every time must be a multiple of, counted from the Unix epoch.
+    #[serde(with = "super::seconds")]
+    pub step: TimeDelta,
+
+    /// See [`GranularityMode`].
+    #[serde(default)]
+    pub mode: GranularityMode,
+}
+
+/// Failure to [align](Granularity::align) an interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum AlignError {
+    /// The interval does not fall on the step, and [`GranularityMode::Reject`] is set.
+    #[error("{0} is not a multiple of the slot granularity")]
+    Unaligned(DateTime<Utc>),
+
+    /// The step is not positive, or the time is too far in the future to round.
+    #[error(transparent)]
+    Rounding(#[from] RoundingError),
+}
+
+impl Granularity {
+    /// Round both ends of `interval` to [`Self::step`], or reject them if unaligned,
+    /// depending on [`Self::mode`].
+    pub fn align(&self, interval: TimeInterval) -> Result<TimeInterval, AlignError> {
+        let align = |t: DateTime<Utc>| match self.mode {
+            GranularityMode::Round => Ok(t.duration_round(self.step)?),
+            GranularityMode::Reject if t.duration_trunc(self.step)? == t => Ok(t),
+            GranularityMode::Reject => Err(AlignError::Unaligned(t)),
+        };
+        Ok(TimeInterval {
+            start: align(interval.start)?,
+            end: align(interval.end)?,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::time_interval;
+    use super::*;
+    use crate::{datetime, time_interval};
+
+    #[test]
+    fn test_id_round_trip() {
+        let id = SlotId(0x1f);
+        assert_eq!(id.to_string().parse::<SlotId>(), Ok(id));
+        assert_eq!(
+            "t.1f".parse::<SlotId>(),
+            Err(crate::data::ParseIdError::Prefix('s'))
+        );
+        assert!(matches!(
+            "s.xyz".parse::<SlotId>(),
+            Err(crate::data::ParseIdError::Int(_))
+        ));
+    }
+
+    #[test]
+    fn test_granularity_align() {
+        let t = time_interval! { 4/5/2025 @ 9:7 - 4/5/2025 @ 11:30 };
+        let round = Granularity {
+            step: TimeDelta::minutes(15),
+            mode: GranularityMode::Round,
+        };
+        assert_eq!(
+            round.align(t),
+            Ok(time_interval! { 4/5/2025 @ 9:00 - 4/5/2025 @ 11:30 })
+        );
+        let later = time_interval! { 4/5/2025 @ 9:8 - 4/5/2025 @ 11:30 };
+        assert_eq!(
+            round.align(later),
+            Ok(time_interval! { 4/5/2025 @ 9:15 - 4/5/2025 @ 11:30 })
+        );
+
+        let reject = Granularity {
+            mode: GranularityMode::Reject,
+            ..round
+        };
+        assert_eq!(
+            reject.align(t),
+            Err(AlignError::Unaligned(datetime!(4/5/2025 @ 9:7)))
+        );
+        let aligned = time_interval! { 4/5/2025 @ 9:15 - 4/5/2025 @ 11:30 };
+        assert_eq!(reject.align(aligned), Ok(aligned));
+    }
+
+    #[test]
+    fn test_interval_shifted() {
+        let t = time_interval! { 4/5/2025 @ 9:00 - 4/5/2025 @ 11:30 };
+        let later = t.shifted(TimeDelta::days(3)).unwrap();
+        assert_eq!(later, time_interval! { 4/8/2025 @ 9:00 - 4/8/2025 @ 11:30 });
+        let earlier = t.shifted(TimeDelta::hours(-10)).unwrap();
+        assert_eq!(
+            earlier,
+            time_interval! { 4/4/2025 @ 23:00 - 4/5/2025 @ 1:30 }
+        );
+        assert_eq!(later.end - later.start, t.end - t.start);
+        assert_eq!(earlier.end - earlier.start, t.end - t.start);
+    }
+
+    #[test]
+    fn test_interval_shifted_overflow() {
+        let t = TimeInterval {
+            start: DateTime::<Utc>::MAX_UTC - TimeDelta::hours(1),
+            end: DateTime::<Utc>::MAX_UTC,
+        };
+        assert_eq!(t.shifted(TimeDelta::minutes(1)), None);
+        assert_eq!(t.shifted(TimeDelta::MIN), None);
+    }
 
     #[test]
     fn test_interval_contains_self() {
@@ -301,4 +593,64 @@ mod tests {
             "an interval starting earlier should not count as contained, even if sharing a duration"
         );
     }
+
+    #[test]
+    fn test_interval_contains_instant_at_start() {
+        assert!(
+            time_interval! { 4/5/2025 - 4/8/2025 }.contains_instant(datetime!(4/5/2025)),
+            "an interval should contain its start"
+        );
+    }
+
+    #[test]
+    fn test_interval_not_contains_instant_at_end() {
+        assert!(
+            !time_interval! { 4/5/2025 - 4/8/2025 }.contains_instant(datetime!(4/8/2025)),
+            "an interval should not contain its end"
+        );
+    }
+
+    #[test]
+    fn test_interval_contains_instant_in_middle() {
+        assert!(
+            time_interval! { 4/5/2025 - 4/8/2025 }.contains_instant(datetime!(4/6/2025 @ 12:30)),
+            "an interval should contain an instant between its start and end"
+        );
+    }
+
+    #[test]
+    fn test_interval_clamp_partially_inside() {
+        assert_eq!(
+            TimeInterval::clamp(
+                &time_interval! { 4/5/2025 - 4/8/2025 },
+                &time_interval! { 4/6/2025 - 4/10/2025 }
+            ),
+            Some(time_interval! { 4/6/2025 - 4/8/2025 }),
+            "the part outside the window should be clipped"
+        );
+    }
+
+    #[test]
+    fn test_interval_clamp_fully_inside() {
+        assert_eq!(
+            TimeInterval::clamp(
+                &time_interval! { 4/6/2025 - 4/7/2025 },
+                &time_interval! { 4/5/2025 - 4/8/2025 }
+            ),
+            Some(time_interval! { 4/6/2025 - 4/7/2025 }),
+            "an interval inside the window should be unchanged"
+        );
+    }
+
+    #[test]
+    fn test_interval_clamp_fully_outside() {
+        assert_eq!(
+            TimeInterval::clamp(
+                &time_interval! { 4/9/2025 - 4/10/2025 },
+                &time_interval! { 4/5/2025 - 4/8/2025 }
+            ),
+            None,
+            "an interval outside the window should be dropped"
+        );
+    }
 }
@@ -1,7 +1,10 @@
 //! See [`Task`]
 
-use crate::data::skill::{Proficiency, SkillId};
-use chrono::prelude::*;
+use crate::data::{
+    Preference, UserMap,
+    skill::{Proficiency, SkillId},
+};
+use chrono::{TimeDelta, prelude::*};
 use rustc_hash::{FxHashMap, FxHashSet};
 use serde::{Deserialize, Serialize};
 
@@ -16,16 +19,23 @@ pub struct ProficiencyReq {
     /// The lower bound of the target - try to stay above this.
     pub soft_min: Proficiency,
     /// The upper bound of the target - try to stay below this.
+    #[serde(with = "super::skill::bound")]
     pub soft_max: Proficiency,
 
     /// The lower bound - reject any solution below this.
     pub hard_min: Proficiency,
     /// The upper bound - reject any solution above this.
+    ///
+    /// [`Proficiency::UNBOUNDED`] if there is no upper limit.
+    #[serde(with = "super::skill::bound")]
     pub hard_max: Proficiency,
 }
 
 impl ProficiencyReq {
     /// Construct a new [`ProficiencyReq`] from ideal and ranges.
+    ///
+    /// Unbounded lower bounds default to [`Proficiency::MIN`],
+    /// unbounded upper bounds default to [`Proficiency::UNBOUNDED`].
     pub fn new<R1, R2>(target: Proficiency, soft_range: R1, hard_range: R2) -> Option<Self>
     where
         R1: std::ops::RangeBounds<Proficiency>,
@@ -49,7 +59,7 @@ impl ProficiencyReq {
         ) -> (Proficiency, Proficiency) {
             (
                 range.start_bound().get().unwrap_or(Proficiency::MIN),
-                range.end_bound().get().unwrap_or(Proficiency::MAX),
+                range.end_bound().get().unwrap_or(Proficiency::UNBOUNDED),
             )
         }
 
@@ -66,7 +76,11 @@ impl ProficiencyReq {
 }
 
 /// A product or service to be completed.
+///
+/// A single `deadline` field is accepted when deserializing, setting both
+/// [`soft_deadline`](Task::soft_deadline) and [`hard_deadline`](Task::hard_deadline).
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "TaskRepr")]
 pub struct Task {
     /// Duplicate of the task's ID.
     pub id: TaskId,
@@ -83,9 +97,217 @@ pub struct Task {
     /// Prefer to overshoot (except in great excess, like 200+%) rather than undershoot, but prioritizing closer matches.
     pub skills: FxHashMap<SkillId, ProficiencyReq>,
 
+    /// When the task should be completed by. Completing it later is penalized.
+    ///
+    /// [`None`]: No preference for early completion.
+    pub soft_deadline: Option<DateTime<Utc>>,
+
+    /// When the task must be completed by. Completing it later is a scheduling failure.
+    ///
     /// [`None`]: Task has no "completion" state.
-    pub deadline: Option<DateTime<Utc>>,
+    pub hard_deadline: Option<DateTime<Utc>>,
+
+    /// Deadline relative to the latest of the task's [dependencies](Self::deps) finishing.
+    ///
+    /// When set and the task has dependencies, this takes the place of both deadlines;
+    /// see [`resolve_deadlines`](crate::algo::resolve_deadlines).
+    #[serde(with = "super::seconds::option")]
+    pub deadline_offset: Option<TimeDelta>,
 
     /// Dependencies - [`Task`]s that must be completed before this one can be scheduled (estimated by deadlines).
     pub deps: FxHashSet<TaskId>,
+
+    /// How strongly each user should (or should not) be staffed on slots this task is worked during.
+    ///
+    /// [`Preference::INFINITY`] staffs the user whenever they are available,
+    /// and [`Preference::NEG_INFINITY`] never staffs them. See [`task_affinity`](crate::algo::task_affinity).
+    pub preferred_users: UserMap<Preference>,
+}
+
+impl Task {
+    /// The latest the task is expected to be completed by:
+    /// the [hard deadline](Self::hard_deadline), or the [soft deadline](Self::soft_deadline) if there is none.
+    ///
+    /// Ignores any [relative deadline](Self::deadline_offset);
+    /// see [`effective_deadlines`](crate::algo::effective_deadlines) for that.
+    #[inline]
+    pub fn deadline(&self) -> Option<DateTime<Utc>> {
+        self.hard_deadline.or(self.soft_deadline)
+    }
+
+    /// Set both the soft and hard deadlines.
+    #[inline]
+    pub fn set_deadline(&mut self, deadline: Option<DateTime<Utc>>) {
+        self.soft_deadline = deadline;
+        self.hard_deadline = deadline;
+    }
+}
+
+/// Deserialized form of [`Task`], accepting the older single `deadline` field.
+#[derive(Deserialize)]
+struct TaskRepr {
+    id: TaskId,
+    title: String,
+    desc: String,
+    skills: FxHashMap<SkillId, ProficiencyReq>,
+    #[serde(default)]
+    deadline: Option<DateTime<Utc>>,
+    #[serde(default)]
+    soft_deadline: Option<DateTime<Utc>>,
+    #[serde(default)]
+    hard_deadline: Option<DateTime<Utc>>,
+    #[serde(default, with = "super::seconds::option")]
+    deadline_offset: Option<TimeDelta>,
+    deps: FxHashSet<TaskId>,
+    #[serde(default)]
+    preferred_users: UserMap<Preference>,
+}
+
+impl From<TaskRepr> for Task {
+    fn from(value: TaskRepr) -> Self {
+        let TaskRepr {
+            id,
+            title,
+            desc,
+            skills,
+            deadline,
+            soft_deadline,
+            hard_deadline,
+            deadline_offset,
+            deps,
+            preferred_users,
+        } = value;
+        Self {
+            id,
+            title,
+            desc,
+            skills,
+            soft_deadline: soft_deadline.or(deadline),
+            hard_deadline: hard_deadline.or(deadline),
+            deadline_offset,
+            deps,
+            preferred_users,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datetime;
+
+    #[test]
+    fn test_id_round_trip() {
+        let id = TaskId(0x7);
+        assert_eq!(id.to_string().parse::<TaskId>(), Ok(id));
+        assert_eq!(
+            "u.7".parse::<TaskId>(),
+            Err(crate::data::ParseIdError::Prefix('t'))
+        );
+        assert!(matches!(
+            "t.xyz".parse::<TaskId>(),
+            Err(crate::data::ParseIdError::Int(_))
+        ));
+    }
+
+    #[test]
+    fn test_index_by_id() {
+        let list = vec![
+            crate::task_lit!(3: "a" {}),
+            crate::task_lit!(1: "b" {}),
+            crate::task_lit!(4: "c" {3}),
+        ];
+        let map: TaskMap = crate::data::index_by_id(list.clone());
+        assert_eq!(map.len(), list.len());
+        for (id, task) in &map {
+            assert_eq!(*id, task.id);
+        }
+        assert_eq!(map[&TaskId(4)].title, "c");
+    }
+
+    #[test]
+    fn test_deadline_sets_both() {
+        let task: Task = serde_json::from_value(serde_json::json!({
+            "id": 0,
+            "title": "a",
+            "desc": "",
+            "skills": {},
+            "deadline": "2025-04-12T00:00:00Z",
+            "deps": [],
+        }))
+        .unwrap();
+        assert_eq!(task.soft_deadline, Some(datetime!(4/12/2025)));
+        assert_eq!(task.hard_deadline, Some(datetime!(4/12/2025)));
+
+        let task: Task = serde_json::from_value(serde_json::json!({
+            "id": 0,
+            "title": "a",
+            "desc": "",
+            "skills": {},
+            "deadline": "2025-04-12T00:00:00Z",
+            "hard_deadline": "2025-04-14T00:00:00Z",
+            "deps": [],
+        }))
+        .unwrap();
+        assert_eq!(task.soft_deadline, Some(datetime!(4/12/2025)));
+        assert_eq!(
+            task.hard_deadline,
+            Some(datetime!(4/14/2025)),
+            "specific deadline should override the alias"
+        );
+        assert_eq!(task.deadline(), Some(datetime!(4/14/2025)));
+    }
+
+    #[test]
+    fn test_default_hard_max_is_not_f32_max() {
+        let req = ProficiencyReq::new(Proficiency::ONE, .., ..).unwrap();
+        assert_ne!(
+            *req.hard_max,
+            f32::MAX,
+            "unbounded upper limit should not be an arbitrary finite number"
+        );
+        assert_eq!(req.hard_max, Proficiency::UNBOUNDED);
+    }
+
+    #[test]
+    fn test_unbounded_req_round_trip() {
+        let req = ProficiencyReq::new(Proficiency::ONE, .., ..).unwrap();
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["hard_max"], serde_json::Value::Null);
+        assert_eq!(serde_json::from_value::<ProficiencyReq>(json).unwrap(), req);
+
+        let req = ProficiencyReq::new(Proficiency::ONE, ..Proficiency::MAX, ..).unwrap();
+        let json = serde_json::to_string(&req).unwrap();
+        assert_eq!(serde_json::from_str::<ProficiencyReq>(&json).unwrap(), req);
+    }
+
+    #[test]
+    fn test_unbounded_req_csv_round_trip() {
+        let req = ProficiencyReq::new(Proficiency::ONE, .., ..).unwrap();
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.serialize(&req).unwrap();
+        let csv = writer.into_inner().unwrap();
+        let read = csv::Reader::from_reader(csv.as_slice())
+            .deserialize::<ProficiencyReq>()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(read, req);
+
+        let legacy = "target,soft_min,soft_max,hard_min,hard_max\n1.0,0.0,inf,0.0,inf\n";
+        let read = csv::Reader::from_reader(legacy.as_bytes())
+            .deserialize::<ProficiencyReq>()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(read, req, "`inf` bounds written before should still load");
+    }
+
+    #[test]
+    fn test_proficiency_is_reasonable() {
+        assert!(Proficiency::ZERO.is_reasonable());
+        assert!(Proficiency::ONE.is_reasonable());
+        assert!(Proficiency::MAX.is_reasonable());
+        assert!(!Proficiency::UNBOUNDED.is_reasonable());
+    }
 }
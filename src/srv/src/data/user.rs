@@ -1,16 +1,17 @@
 //! See [`User`]
 
 use crate::data::{
-    RuleMap,
+    Rule, RuleId, RuleMap, RuleSet, TimeInterval,
     pref::Preference,
-    skill::{Proficiency, SkillMap},
+    skill::{Proficiency, SkillId, SkillMap, UserSkill},
 };
+use chrono::TimeDelta;
 use serde::{Deserialize, Serialize};
 
 super::id_type!(impl Id<u64> for User as 'u');
 
 /// A person who can be scheduled to work on a task.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     /// Duplicate of the task's ID.
     pub id: UserId,
@@ -22,6 +23,20 @@ pub struct User {
     /// Preferences regarding times the user can or can't be scheduled.
     pub availability: RuleMap,
 
+    /// The order [`Self::availability`] should be presented in.
+    ///
+    /// Should contain each key of [`Self::availability`] exactly once;
+    /// see [`Self::sync_rule_order`].
+    #[serde(default)]
+    pub rule_order: Vec<RuleId>,
+
+    /// The preference towards times none of [`Self::availability`] covers.
+    ///
+    /// [`None`]: Unavailable unless stated otherwise.
+    /// [`Some`]: Available unless stated otherwise, with this preference.
+    #[serde(default)]
+    pub default_availability: Option<Preference>,
+
     /// Preference towards sharing slots with other users.
     ///
     /// Ex:
@@ -33,5 +48,243 @@ pub struct User {
     ///
     /// Skills the user has 0 proficiency with should be excluded to save memory,
     /// as a missing skill is implied to be 0% proficiency.
-    pub skills: SkillMap<Proficiency>,
+    pub skills: SkillMap<UserSkill>,
+}
+
+impl User {
+    /// Iterate over [`Self::availability`] in [`Self::rule_order`].
+    pub fn rules_in_order(&self) -> impl Iterator<Item = &Rule> {
+        self.rule_order
+            .iter()
+            .filter_map(|id| self.availability.get(id))
+    }
+
+    /// Restore the [`Self::rule_order`] invariant after [`Self::availability`] was modified.
+    ///
+    /// Removed rules are dropped from the order,
+    /// and rules missing from the order are appended earliest-first (see [`Rule`]'s [`Ord`]).
+    pub fn sync_rule_order(&mut self) {
+        let mut unordered = self.availability.keys().copied().collect::<RuleSet>();
+        self.rule_order.retain(|id| unordered.remove(id));
+        let mut unordered = Vec::from_iter(unordered.iter().map(|id| &self.availability[id]));
+        unordered.sort_by_key(|rule| rule.sort_key());
+        let unordered = Vec::from_iter(unordered.into_iter().map(|rule| rule.id));
+        self.rule_order.extend(unordered);
+    }
+
+    /// Drop availability rules that make no difference to the user's preferences:
+    /// exact duplicates of another rule, keeping the one with the lowest ID,
+    /// and rules whose whole [coverage](Rule::coverage) another rule of the same preference
+    /// [contains](Rule::contains).
+    ///
+    /// Preferences are only the same if they were also [set](Rule::set_at) at the same time,
+    /// as they may otherwise [decay](crate::algo::Decay) differently.
+    ///
+    /// A narrower rule [overrides](crate::algo::governing_rule) a broader one, so a covered rule
+    /// is kept if any rule of a different preference overlaps it.
+    ///
+    /// Returns the IDs of the dropped rules, lowest first.
+    pub fn normalize_rules(&mut self) -> Vec<RuleId> {
+        let mut ids = Vec::from_iter(self.availability.keys().copied());
+        // highest first, so that the lowest of several duplicates is the one left
+        ids.sort_by_key(|id| std::cmp::Reverse(id.0));
+        let mut dropped = RuleSet::default();
+        for id in ids {
+            let rule = &self.availability[&id];
+            let others = || {
+                self.availability
+                    .values()
+                    .filter(|other| other.id != id && !dropped.contains(&other.id))
+            };
+            let same_pref = |other: &Rule| other.pref == rule.pref && other.set_at == rule.set_at;
+            let is_duplicate = others().any(|other| {
+                same_pref(other)
+                    && other.include == rule.include
+                    && other.exclude == rule.exclude
+                    && other.rep == rule.rep
+            });
+            let is_covered = || {
+                rule.coverage().is_some_and(|parts| {
+                    others()
+                        .any(|other| same_pref(other) && parts.iter().all(|t| other.contains(t)))
+                        && !others().any(|other| {
+                            !same_pref(other)
+                                && parts.iter().any(|t| other.overlap(t) > TimeDelta::zero())
+                        })
+                })
+            };
+            if is_duplicate || is_covered() {
+                dropped.insert(id);
+            }
+        }
+        self.availability.retain(|id, _| !dropped.contains(id));
+        self.sync_rule_order();
+        let mut dropped = Vec::from_iter(dropped);
+        dropped.sort_by_key(|id| id.0);
+        dropped
+    }
+
+    /// Drop entries that are equivalent to not being listed:
+    /// `0.0` [`Self::user_prefs`] and [`Proficiency::ZERO`] [`Self::skills`].
+    ///
+    /// [`Self::availability`] is left alone, as a rule marks
+    /// the user as available even if it has no preference.
+    pub fn compact(&mut self) {
+        self.user_prefs.retain(|_, pref| pref.0 != 0.0);
+        self.skills
+            .retain(|_, skill| skill.proficiency != Proficiency::ZERO);
+    }
+
+    /// The user's proficiency with `skill` throughout `interval`.
+    ///
+    /// Missing and [expired](UserSkill::expires) skills are [`Proficiency::ZERO`].
+    pub fn proficiency(&self, skill: SkillId, interval: &TimeInterval) -> Proficiency {
+        self.skills
+            .get(&skill)
+            .map_or(Proficiency::ZERO, |s| s.proficiency_during(interval))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{data::*, datetime, slot_lit, user_lit};
+
+    #[test]
+    fn test_id_round_trip() {
+        let id = UserId(0x2a);
+        assert_eq!(id.to_string().parse::<UserId>(), Ok(id));
+        assert_eq!("s.2a".parse::<UserId>(), Err(ParseIdError::Prefix('u')));
+        assert!(matches!(
+            "u.xyz".parse::<UserId>(),
+            Err(ParseIdError::Int(_))
+        ));
+    }
+
+    #[test]
+    fn test_sync_rule_order_earliest_first() {
+        let mut user = user_lit! {
+            0: "bob" {
+                0: 4/14/2025 @ 9:00 - 4/14/2025 @ 17:00 | 1.0,
+                1: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 | 1.0,
+                2: 4/13/2025 @ 9:00 - 4/13/2025 @ 17:00 | 0.5,
+                3: 4/13/2025 @ 9:00 - 4/13/2025 @ 17:00 | -0.5,
+            }
+        };
+        user.rule_order.clear();
+        user.sync_rule_order();
+        assert_eq!(
+            user.rule_order,
+            [RuleId(1), RuleId(3), RuleId(2), RuleId(0)],
+            "unordered rules should be appended earliest-first, then by preference"
+        );
+
+        // an explicit order is kept
+        user.rule_order = vec![RuleId(0)];
+        user.sync_rule_order();
+        assert_eq!(
+            user.rule_order,
+            [RuleId(0), RuleId(1), RuleId(3), RuleId(2)]
+        );
+    }
+
+    #[test]
+    fn test_normalize_rules() {
+        let mut user = user_lit! {
+            0: "bob" {
+                0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 | 1.0,
+                1: 4/12/2025 @ 10:00 - 4/12/2025 @ 12:00 | 1.0,
+                2: 4/13/2025 @ 9:00 - 4/13/2025 @ 17:00 | 0.5,
+                3: 4/13/2025 @ 9:00 - 4/13/2025 @ 17:00 | 0.5,
+            }
+        };
+        assert_eq!(
+            user.normalize_rules(),
+            [RuleId(1), RuleId(3)],
+            "the narrow rule and the later duplicate should be dropped"
+        );
+        assert_eq!(user.rule_order, [RuleId(0), RuleId(2)]);
+        assert_eq!(user.normalize_rules(), [], "nothing left to drop");
+
+        let mut user = user_lit! {
+            0: "bob" {
+                0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 | 1.0,
+                1: 4/12/2025 @ 10:00 - 4/12/2025 @ 12:00 | 1.0,
+                2: 4/12/2025 @ 9:00 - 4/12/2025 @ 13:00 | -0.5,
+            }
+        };
+        assert_eq!(
+            user.normalize_rules(),
+            [],
+            "the narrow rule overrides rule 2, so dropping it would change bob's preference"
+        );
+    }
+
+    #[test]
+    fn test_normalize_rules_keeps_fresher_rules() {
+        use crate::algo::Decay;
+        use chrono::TimeDelta;
+
+        let mut user = user_lit! {
+            0: "bob" {
+                0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 | 1.0,
+                1: 4/12/2025 @ 10:00 - 4/12/2025 @ 12:00 | 1.0,
+                2: 4/13/2025 @ 9:00 - 4/13/2025 @ 17:00 | 0.5,
+                3: 4/13/2025 @ 9:00 - 4/13/2025 @ 17:00 | 0.5,
+            }
+        };
+        let set_at =
+            |user: &mut User, id, at| user.availability.get_mut(&RuleId(id)).unwrap().set_at = at;
+        set_at(&mut user, 0, Some(datetime!(1/1/2025)));
+        set_at(&mut user, 1, Some(datetime!(4/1/2025)));
+        set_at(&mut user, 2, Some(datetime!(1/1/2025)));
+        set_at(&mut user, 3, Some(datetime!(4/1/2025)));
+
+        let decay = Decay {
+            half_life: TimeDelta::days(30),
+        };
+        let at = datetime!(4/12/2025 @ 10:00);
+        let effective = |rule: &Rule| decay.apply(rule.pref, rule.set_at.unwrap(), at);
+        assert!(
+            effective(&user.availability[&RuleId(1)]) > effective(&user.availability[&RuleId(0)]),
+            "the newer rule should have decayed less"
+        );
+        assert_eq!(
+            user.normalize_rules(),
+            [],
+            "rules stated at different times may decay differently, so none are redundant"
+        );
+    }
+
+    #[test]
+    fn test_expired_skill_is_unskilled() {
+        let user = User {
+            id: UserId(0),
+            name: "bob".to_string(),
+            availability: RuleMap::default(),
+            rule_order: Vec::new(),
+            default_availability: None,
+            user_prefs: UserMap::default(),
+            skills: SkillMap::from_iter([(
+                SkillId(0),
+                UserSkill {
+                    proficiency: Proficiency::ONE,
+                    expires: Some(datetime!(4/10/2025)),
+                },
+            )]),
+        };
+
+        let before = slot_lit! { 0: 4/8/2025 @ 8:00 - 4/8/2025 @ 16:00 };
+        let after = slot_lit! { 1: 4/12/2025 @ 8:00 - 4/12/2025 @ 16:00 };
+
+        assert_eq!(
+            user.proficiency(SkillId(0), &before),
+            Proficiency::ONE,
+            "skill should count for a slot before it expires"
+        );
+        assert_eq!(
+            user.proficiency(SkillId(0), &after),
+            Proficiency::ZERO,
+            "skill should not count for a slot after it expires"
+        );
+    }
 }
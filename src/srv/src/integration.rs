@@ -2,27 +2,426 @@
 //!
 //! The main reason for the `Py...` types is so that structures without IDs can be passed.
 //! Additionally, many backend types have non-[`None`] "None-like" values (such as empty strings).
+//!
+//! # Locking
+//!
+//! Each collection ([`SLOTS`], [`TASKS`], [`USERS`], [`PINNED`]) has its own [`RwLock`],
+//! so requests touching different collections do not block each other.
+//! Every request holds its locks until it returns, so each one sees and leaves each collection consistent,
+//! but locks taken one after another may observe another request's change in between.
+//!
+//! Requests that need several collections to agree with each other (such as generating a schedule)
+//! must hold all of those locks at once, acquired in the order
+//! [`SLOTS`] → [`TASKS`] → [`USERS`] → [`PINNED`] → [`TEMPLATES`], skipping any that are not needed.
+//! A single fixed order means two requests can never each hold a lock the other is waiting on.
+//! [`ReadAll`] and [`WriteAll`] take every lock in that order.
+//!
+//! A lock must not be acquired while already holding it, even for reading,
+//! as a writer waiting in between would deadlock.
+//!
+//! [`IDEMPOTENCY_KEYS`] comes before all of them, as [`call_idempotent`] holds it for the whole
+//! of an `add_*` call. Requests that clear it along with the collections must lock it first.
+//!
+//! The `get_*` endpoints give up on a contended lock after [`READ_TIMEOUT`], if set,
+//! producing a [`fault::unavailable`] error the caller may retry.
+//!
+//! Settings such as [`SCHEDULER_CONFIG`], [`SLOT_GRANULARITY`], and [`READ_TIMEOUT`] are only written on startup, so they may be read while holding any lock.
 
-use crate::data::*;
-use chrono::{DateTime, Utc};
-use parking_lot::RwLock;
+use crate::{
+    Format,
+    algo::{self, Pins, Schedule, SchedulerConfig, SchedulingError, SlotExplanation},
+    data::*,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use parking_lot::{Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use regex::Regex;
 use rustc_hash::{FxHashMap, FxHashSet};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use smallvec::SmallVec;
 use std::{
+    collections::{VecDeque, hash_map::Entry},
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
     num::NonZeroUsize,
-    path::PathBuf,
-    sync::{LazyLock, atomic::AtomicBool},
+    path::{Path, PathBuf},
+    sync::{
+        LazyLock,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering::Relaxed},
+    },
+    time::{Duration, Instant},
 };
-use xml_rpc::{Fault, Server};
+use xml_rpc::{Fault, Params, Response, Server, Value, from_params, into_params};
 
 type Result<T> = std::result::Result<T, Fault>;
 
 pub(crate) static EXIT_REQUESTED: AtomicBool = const { AtomicBool::new(false) };
+/// Whether mutating calls are rejected with [`fault::forbidden`]. Set once on startup.
+pub(crate) static READ_ONLY: AtomicBool = const { AtomicBool::new(false) };
+/// How many calls may be handled at once before more are rejected. See [`admit`]. Set once on startup.
+pub(crate) static MAX_CONNECTIONS: AtomicUsize =
+    const { AtomicUsize::new(DEFAULT_MAX_CONNECTIONS) };
+/// How many calls are being handled right now. See [`admit`].
+static IN_FLIGHT: AtomicUsize = const { AtomicUsize::new(0) };
+/// When the server should close on its own. See [`shutdown_after`].
+static SHUTDOWN_AT: Mutex<Option<Instant>> = Mutex::new(None);
+/// Incremented every time backend data is mutated. See [`changes_since`].
+pub(crate) static VERSION: AtomicU64 = const { AtomicU64::new(0) };
 pub(crate) static SLOTS: RwLock<LazyLock<SlotMap>> = RwLock::new(LazyLock::new(SlotMap::default));
 pub(crate) static TASKS: RwLock<LazyLock<TaskMap>> = RwLock::new(LazyLock::new(TaskMap::default));
 pub(crate) static USERS: RwLock<LazyLock<UserMap>> = RwLock::new(LazyLock::new(UserMap::default));
+/// Assignments kept across schedule generations. See [`pin_assignment`].
+pub(crate) static PINNED: RwLock<LazyLock<Pins>> = RwLock::new(LazyLock::new(Pins::default));
+/// Reusable slot definitions. See [`add_templates`].
+pub(crate) static TEMPLATES: RwLock<LazyLock<SlotTemplateMap>> =
+    RwLock::new(LazyLock::new(SlotTemplateMap::default));
+/// The configuration schedules generated by the server start from, before [`PINNED`] is added.
+/// Set once on startup.
+pub(crate) static SCHEDULER_CONFIG: RwLock<LazyLock<SchedulerConfig>> =
+    RwLock::new(LazyLock::new(SchedulerConfig::default));
+/// The step new slots and rules are aligned to, if any. See [`align`]. Set once on startup.
+pub(crate) static SLOT_GRANULARITY: RwLock<Option<Granularity>> = RwLock::new(None);
+/// How long the `get_*` endpoints wait on a contended collection before failing with
+/// [`fault::unavailable`]. [`None`] to wait indefinitely. Set once on startup.
+pub(crate) static READ_TIMEOUT: RwLock<Option<Duration>> = RwLock::new(None);
+/// The schedule last generated by [`ReadAll::generate`], until the data next [changes](notify_mutation).
+static LAST_SCHEDULE: Mutex<Option<Schedule>> = Mutex::new(None);
+
+/// Acquire `lock` for reading, giving up after [`READ_TIMEOUT`] if set.
+///
+/// Produces a [`fault::unavailable`] error naming `name` if the lock could not be acquired in time.
+fn read_or_busy<T>(lock: &'static RwLock<T>, name: &str) -> Result<RwLockReadGuard<'static, T>> {
+    match *READ_TIMEOUT.read() {
+        None => Ok(lock.read()),
+        Some(timeout) => lock.try_read_for(timeout).ok_or_else(|| {
+            fault::unavailable(format_args!(
+                "{name} are busy; retry after {}ms",
+                timeout.as_millis()
+            ))
+        }),
+    }
+}
+
+/// Shared access to every collection at once, acquired in [lock order](self#locking).
+pub(crate) struct ReadAll {
+    pub slots: RwLockReadGuard<'static, LazyLock<SlotMap>>,
+    pub tasks: RwLockReadGuard<'static, LazyLock<TaskMap>>,
+    pub users: RwLockReadGuard<'static, LazyLock<UserMap>>,
+    pub pins: RwLockReadGuard<'static, LazyLock<Pins>>,
+}
+
+impl ReadAll {
+    /// Acquire every read lock, blocking until all are held.
+    pub fn lock() -> Self {
+        let slots = SLOTS.read();
+        let tasks = TASKS.read();
+        let users = USERS.read();
+        let pins = PINNED.read();
+        Self {
+            slots,
+            tasks,
+            users,
+            pins,
+        }
+    }
+
+    /// The configuration schedules generated by the server use: [`SCHEDULER_CONFIG`],
+    /// with the current [`PINNED`] assignments.
+    pub fn scheduler_config(&self) -> SchedulerConfig {
+        SchedulerConfig {
+            pins: Pins::clone(&self.pins),
+            ..SchedulerConfig::clone(&SCHEDULER_CONFIG.read())
+        }
+    }
+
+    /// Generate a schedule from the locked data, or reuse the [last one](LAST_SCHEDULE)
+    /// if nothing has changed since.
+    pub fn generate(&self) -> std::result::Result<Schedule, SchedulingError> {
+        let mut last = LAST_SCHEDULE.lock();
+        if let Some(schedule) = &*last {
+            return Ok(schedule.clone());
+        }
+        let schedule = Schedule::generate_with(
+            &self.slots,
+            &self.tasks,
+            &self.users,
+            &self.scheduler_config(),
+        )?;
+        *last = Some(schedule.clone());
+        Ok(schedule)
+    }
+}
+
+/// Exclusive access to every collection at once, acquired in [lock order](self#locking).
+pub(crate) struct WriteAll {
+    pub slots: RwLockWriteGuard<'static, LazyLock<SlotMap>>,
+    pub tasks: RwLockWriteGuard<'static, LazyLock<TaskMap>>,
+    pub users: RwLockWriteGuard<'static, LazyLock<UserMap>>,
+    pub pins: RwLockWriteGuard<'static, LazyLock<Pins>>,
+}
+
+impl WriteAll {
+    /// Acquire every write lock, blocking until all are held.
+    pub fn lock() -> Self {
+        let slots = SLOTS.write();
+        let tasks = TASKS.write();
+        let users = USERS.write();
+        let pins = PINNED.write();
+        Self {
+            slots,
+            tasks,
+            users,
+            pins,
+        }
+    }
+}
+
+/// A copy of all backend data and the next value of every ID counter.
+/// See [`snapshot_state`] and [`restore_state`].
+#[derive(Debug, Clone)]
+pub(crate) struct WorldSnapshot {
+    slots: SlotMap,
+    tasks: TaskMap,
+    users: UserMap,
+    pins: Pins,
+    templates: SlotTemplateMap,
+    next_slot: SlotId,
+    next_task: TaskId,
+    next_user: UserId,
+    next_rule: RuleId,
+    next_skill: SkillId,
+    next_template: SlotTemplateId,
+}
+
+/// Copy all backend data and ID counters, so that they can later be put back with [`restore_state`].
+///
+/// Every collection is locked for the duration, so the snapshot is consistent.
+#[allow(
+    dead_code,
+    reason = "shared by features that need to roll back changes"
+)]
+pub(crate) fn snapshot_state() -> WorldSnapshot {
+    let data = ReadAll::lock();
+    let templates = TEMPLATES.read();
+    WorldSnapshot {
+        slots: SlotMap::clone(&data.slots),
+        tasks: TaskMap::clone(&data.tasks),
+        users: UserMap::clone(&data.users),
+        pins: Pins::clone(&data.pins),
+        templates: SlotTemplateMap::clone(&templates),
+        next_slot: SlotId(SlotId::load()),
+        next_task: TaskId(TaskId::load()),
+        next_user: UserId(UserId::load()),
+        next_rule: RuleId(RuleId::load()),
+        next_skill: SkillId(SkillId::load()),
+        next_template: SlotTemplateId(SlotTemplateId::load()),
+    }
+}
+
+/// Replace all backend data and ID counters with those in `snapshot`, discarding anything
+/// changed since it was taken by [`snapshot_state`].
+///
+/// Every collection is locked for the duration, so no request observes a partial restore.
+#[allow(
+    dead_code,
+    reason = "shared by features that need to roll back changes"
+)]
+pub(crate) fn restore_state(snapshot: WorldSnapshot) {
+    let mut data = WriteAll::lock();
+    let mut templates = TEMPLATES.write();
+    **data.slots = snapshot.slots;
+    **data.tasks = snapshot.tasks;
+    **data.users = snapshot.users;
+    **data.pins = snapshot.pins;
+    **templates = snapshot.templates;
+    SlotId::store(snapshot.next_slot.0);
+    TaskId::store(snapshot.next_task.0);
+    UserId::store(snapshot.next_user.0);
+    RuleId::store(snapshot.next_rule.0);
+    SkillId::store(snapshot.next_skill.0);
+    SlotTemplateId::store(snapshot.next_template.0);
+    notify_mutation(Mutation::all());
+}
+
+/// Constructors for the [`Fault`]s returned by every endpoint, so that codes are consistent.
+///
+/// | Code | Constructor         | Meaning                                             |
+/// |-----:|---------------------|-----------------------------------------------------|
+/// |  400 | [`bad_request`]     | The request could not be decoded                    |
+/// |  404 | [`not_found`]       | The requested item or file does not exist           |
+/// |  409 | [`conflict`]        | The request conflicts with the current data         |
+/// |  422 | [`unprocessable`]   | The request was understood, but its content is invalid |
+/// |  500 | [`internal`]        | The server failed for reasons outside the request   |
+///
+/// Endpoints that take a batch of items report which item failed with [`at`], whose message is a
+/// JSON object rather than plain text:
+///
+/// ```json
+/// { "index": 1, "id": "s3", "reason": "end must not be before start" }
+/// ```
+///
+/// `index` is the position of the failing item in the request, and `id` is the ID of the item it
+/// refers to, or `null` if the item does not have one yet.
+pub mod fault {
+    use serde::{Deserialize, Serialize};
+    use std::fmt::Display;
+    use xml_rpc::Fault;
+
+    /// See [`bad_request`].
+    pub const BAD_REQUEST: i32 = 400;
+    /// See [`forbidden`].
+    pub const FORBIDDEN: i32 = 403;
+    /// See [`not_found`].
+    pub const NOT_FOUND: i32 = 404;
+    /// See [`conflict`].
+    pub const CONFLICT: i32 = 409;
+    /// See [`unprocessable`].
+    pub const UNPROCESSABLE: i32 = 422;
+    /// See [`internal`].
+    pub const INTERNAL: i32 = 500;
+    /// See [`unavailable`].
+    pub const UNAVAILABLE: i32 = 503;
+
+    /// The request could not be decoded.
+    #[inline]
+    pub fn bad_request(msg: impl Display) -> Fault {
+        Fault::new(BAD_REQUEST, msg.to_string())
+    }
+
+    /// The server does not allow this request, such as a change while [read-only](super::READ_ONLY).
+    #[inline]
+    pub fn forbidden(msg: impl Display) -> Fault {
+        Fault::new(FORBIDDEN, msg.to_string())
+    }
+
+    /// The requested item or file does not exist.
+    #[inline]
+    pub fn not_found(msg: impl Display) -> Fault {
+        Fault::new(NOT_FOUND, msg.to_string())
+    }
+
+    /// The request conflicts with the current data.
+    #[inline]
+    pub fn conflict(msg: impl Display) -> Fault {
+        Fault::new(CONFLICT, msg.to_string())
+    }
+
+    /// The request was understood, but its content is invalid.
+    #[inline]
+    pub fn unprocessable(msg: impl Display) -> Fault {
+        Fault::new(UNPROCESSABLE, msg.to_string())
+    }
+
+    /// The server failed for reasons outside the request.
+    #[inline]
+    pub fn internal(msg: impl Display) -> Fault {
+        Fault::new(INTERNAL, msg.to_string())
+    }
+
+    /// The data is busy; the request may be retried later.
+    #[inline]
+    pub fn unavailable(msg: impl Display) -> Fault {
+        Fault::new(UNAVAILABLE, msg.to_string())
+    }
+
+    /// The message of a fault produced by [`at`].
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    pub struct BatchFault {
+        /// The position of the failing item in the request.
+        pub index: usize,
+        /// The ID of the item, if it has one.
+        pub id: Option<String>,
+        /// Why the item failed.
+        pub reason: String,
+    }
+
+    impl BatchFault {
+        /// Describe the item at `index` of a batch request.
+        pub fn new(index: usize, id: Option<impl Display>, reason: impl Display) -> Self {
+            Self {
+                index,
+                id: id.map(|id| id.to_string()),
+                reason: reason.to_string(),
+            }
+        }
+    }
+
+    /// The item at `index` of a batch request failed with `code`.
+    pub fn at(code: i32, index: usize, id: Option<impl Display>, reason: impl Display) -> Fault {
+        let detail = BatchFault::new(index, id, reason);
+        Fault::new(
+            code,
+            serde_json::to_string(&detail).unwrap_or(detail.reason),
+        )
+    }
+
+    /// [`not_found`] if the file does not exist, otherwise [`internal`].
+    pub fn file(e: csv::Error) -> Fault {
+        match e.kind() {
+            csv::ErrorKind::Io(io) if io.kind() == std::io::ErrorKind::NotFound => not_found(e),
+            _ => internal(e),
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// The collections a mutating call changed. See [`notify_mutation`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(crate) struct Mutation: u8 {
+        /// [`SLOTS`]
+        const SLOTS = 1 << 0;
+        /// [`TASKS`]
+        const TASKS = 1 << 1;
+        /// [`USERS`], including their rules and skills
+        const USERS = 1 << 2;
+        /// [`PINNED`]
+        const PINS = 1 << 3;
+        /// [`TEMPLATES`]
+        const TEMPLATES = 1 << 4;
+    }
+}
+
+thread_local! {
+    /// What the call in progress on this thread has changed so far. See [`call_audited`].
+    static CHANGED: std::cell::Cell<Mutation> = const { std::cell::Cell::new(Mutation::empty()) };
+}
+
+/// Mark the backend data as changed, once per mutating call, while still holding the changed collections' locks.
+///
+/// Bumps the [version](changes_since), drops the [cached schedule](LAST_SCHEDULE),
+/// and notes `kind` for the [audit log](open_audit_log).
+/// Debug builds check that the locks are held.
+fn notify_mutation(kind: Mutation) {
+    debug_assert!(
+        [
+            (Mutation::SLOTS, SLOTS.is_locked_exclusive()),
+            (Mutation::TASKS, TASKS.is_locked_exclusive()),
+            (Mutation::USERS, USERS.is_locked_exclusive()),
+            (Mutation::PINS, PINNED.is_locked_exclusive()),
+            (Mutation::TEMPLATES, TEMPLATES.is_locked_exclusive()),
+        ]
+        .into_iter()
+        .all(|(changed, locked)| locked || !kind.contains(changed)),
+        "{kind:?} changed without holding its lock"
+    );
+    VERSION.fetch_add(1, Relaxed);
+    // every collection feeds into the schedule
+    *LAST_SCHEDULE.lock() = None;
+    CHANGED.set(CHANGED.get() | kind);
+}
+
+/// Produces a [`fault::bad_request`] error if both bounds of a filter range
+/// are provided and `min` is greater than `max`, as the filter could never match.
+fn check_range<T: PartialOrd + std::fmt::Display>(
+    (min_name, min): (&str, Option<T>),
+    (max_name, max): (&str, Option<T>),
+) -> Result<()> {
+    match (min, max) {
+        (Some(min), Some(max)) if min > max => Err(fault::bad_request(format_args!(
+            "inverted range: `{min_name}` ({min}) must not be greater than `{max_name}` ({max})"
+        ))),
+        _ => Ok(()),
+    }
+}
 
 mod re_serde {
     use regex::Regex;
@@ -63,6 +462,68 @@ mod re_serde {
     }
 }
 
+/// A dictionary that rejects duplicate keys when deserialized,
+/// rather than silently keeping only the last one.
+///
+/// XML-RPC struct member names are strings, so distinct names
+/// (such as `"1"` and `"01"`) can still deserialize to the same key.
+#[derive(Debug, Clone)]
+pub struct UniqueKeys<K, V>(pub FxHashMap<K, V>);
+
+impl<'de, K, V> Deserialize<'de> for UniqueKeys<K, V>
+where
+    K: Deserialize<'de> + Eq + std::hash::Hash + std::fmt::Display,
+    V: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct UniqueKeysVisitor<K, V>(std::marker::PhantomData<(K, V)>);
+
+        impl<'de, K, V> serde::de::Visitor<'de> for UniqueKeysVisitor<K, V>
+        where
+            K: Deserialize<'de> + Eq + std::hash::Hash + std::fmt::Display,
+            V: Deserialize<'de>,
+        {
+            type Value = UniqueKeys<K, V>;
+
+            #[inline]
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a map with unique keys")
+            }
+
+            fn visit_map<A>(self, mut access: A) -> std::result::Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                use std::collections::hash_map::Entry;
+
+                let mut map = FxHashMap::with_capacity_and_hasher(
+                    access.size_hint().unwrap_or(0),
+                    Default::default(),
+                );
+                while let Some((key, value)) = access.next_entry()? {
+                    match map.entry(key) {
+                        Entry::Occupied(entry) => {
+                            return Err(serde::de::Error::custom(format_args!(
+                                "duplicate key: {}",
+                                entry.key()
+                            )));
+                        }
+                        Entry::Vacant(entry) => {
+                            entry.insert(value);
+                        }
+                    }
+                }
+                Ok(UniqueKeys(map))
+            }
+        }
+
+        deserializer.deserialize_map(UniqueKeysVisitor(std::marker::PhantomData))
+    }
+}
+
 /// A string filter.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Pattern {
@@ -85,39 +546,38 @@ pub enum Pattern {
 impl Pattern {
     /// Construct a [`Pattern`] that matches any string starting with literal `s`.
     #[inline]
-    pub const fn starts_with(s: String) -> Result<Self> {
-        Ok(Self::StartsWith(s))
+    pub const fn starts_with(s: String) -> Self {
+        Self::StartsWith(s)
     }
 
     /// Construct a [`Pattern`] that matches any string ending with literal `s`.
     #[inline]
-    pub const fn ends_with(s: String) -> Result<Self> {
-        Ok(Self::EndsWith(s))
+    pub const fn ends_with(s: String) -> Self {
+        Self::EndsWith(s)
     }
 
     /// Construct a [`Pattern`] that matches any string containging literal `s`.
     #[inline]
-    pub const fn contains(s: String) -> Result<Self> {
-        Ok(Self::Contains(s))
+    pub const fn contains(s: String) -> Self {
+        Self::Contains(s)
     }
 
     /// Construct a [`Pattern`] that matches any string exactly equal to literal `s`.
     #[inline]
-    pub const fn exactly(s: String) -> Result<Self> {
-        Ok(Self::Exactly(s))
+    pub const fn exactly(s: String) -> Self {
+        Self::Exactly(s)
     }
 
     /// Construct a [`Pattern`] that uses [`regex`] to match strings.
     ///
     /// # Errors
     ///
-    /// Produces a [422 Unprocessable Content](https://developer.mozilla.org/en-US/docs/Web/HTTP/Reference/Status/422)
-    /// error if the argument is not valid [`regex`].
+    /// Produces a [`fault::unprocessable`] error if the argument is not valid [`regex`].
     #[inline]
     pub fn regex(s: String) -> Result<Self> {
         Regex::new(&s)
             .map(Pattern::Regex)
-            .map_err(|e| Fault::new(422, format!("invalid regex: {e}")))
+            .map_err(|e| fault::unprocessable(format_args!("invalid regex: {e}")))
     }
 
     /// Test if `haystack` matches the [`Pattern`].
@@ -234,12 +694,16 @@ impl From<Repetition> for PyRep {
 }
 
 /// Python requirements for constructing a [`Rule`]
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PyRule {
     /// The specific intervals this rule involves, before repeating.
     pub include: SmallVec<[TimeInterval; 1]>,
 
-    /// How often `include` repeats.
+    /// Windows carved out of `include`, before repeating.
+    #[serde(default)]
+    pub exclude: SmallVec<[TimeInterval; 0]>,
+
+    /// How often `include` and `exclude` repeat.
     /// [`None`] if one-off.
     pub repeat: Option<PyRep>,
 
@@ -247,22 +711,33 @@ pub struct PyRule {
     ///
     /// See [`Preference`]
     pub preference: f32,
+
+    /// When `preference` was stated.
+    /// [`None`] if unknown, in which case it never decays.
+    #[serde(default)]
+    pub set_at: Option<DateTime<Utc>>,
 }
 
-impl From<(RuleId, PyRule)> for Rule {
+impl TryFrom<(RuleId, PyRule)> for Rule {
+    type Error = InvalidPreference;
+
     #[inline]
-    fn from((id, value): (RuleId, PyRule)) -> Self {
+    fn try_from((id, value): (RuleId, PyRule)) -> std::result::Result<Self, Self::Error> {
         let PyRule {
             include,
+            exclude,
             repeat,
             preference,
+            set_at,
         } = value;
-        Self {
+        Ok(Self {
             id,
             include,
+            exclude,
             rep: repeat.map(From::from),
-            pref: Preference(preference),
-        }
+            pref: Preference::try_from(preference)?,
+            set_at,
+        })
     }
 }
 
@@ -272,15 +747,19 @@ impl From<Rule> for (RuleId, PyRule) {
         let Rule {
             id,
             include,
+            exclude,
             rep,
             pref: Preference(preference),
+            set_at,
         } = value;
         (
             id,
             PyRule {
                 include,
+                exclude,
                 repeat: rep.map(From::from),
                 preference,
+                set_at,
             },
         )
     }
@@ -292,15 +771,19 @@ impl From<&Rule> for (RuleId, PyRule) {
         let Rule {
             id,
             include,
+            exclude,
             rep,
             pref: Preference(preference),
+            set_at,
         } = value;
         (
             *id,
             PyRule {
                 include: include.clone(),
+                exclude: exclude.clone(),
                 repeat: rep.as_ref().cloned().map(From::from),
                 preference: *preference,
+                set_at: *set_at,
             },
         )
     }
@@ -318,6 +801,14 @@ pub struct PySlot {
     /// The minimum number of [`User`]s that must be assigned to the slot
     pub min_staff: Option<usize>,
 
+    /// The maximum number of [`User`]s that can be assigned to the slot
+    #[serde(default)]
+    pub max_staff: Option<usize>,
+
+    /// Tasks that should be done during the slot when feasible
+    #[serde(default)]
+    pub preferred_tasks: Option<TaskSet>,
+
     /// Optional name for the slot
     pub name: Option<String>,
 }
@@ -329,12 +820,16 @@ impl From<(SlotId, PySlot)> for Slot {
             start,
             end,
             min_staff,
+            max_staff,
+            preferred_tasks,
             name,
         } = slot;
         Self {
             id,
             interval: TimeInterval { start, end },
             min_staff: min_staff.and_then(NonZeroUsize::new),
+            max_staff: max_staff.and_then(NonZeroUsize::new),
+            preferred_tasks: preferred_tasks.unwrap_or_default(),
             name: name.unwrap_or_default(),
         }
     }
@@ -347,6 +842,8 @@ impl From<Slot> for (SlotId, PySlot) {
             id,
             interval: TimeInterval { start, end },
             min_staff,
+            max_staff,
+            preferred_tasks,
             name,
         } = slot;
         (
@@ -355,6 +852,8 @@ impl From<Slot> for (SlotId, PySlot) {
                 start,
                 end,
                 min_staff: min_staff.map(NonZeroUsize::get),
+                max_staff: max_staff.map(NonZeroUsize::get),
+                preferred_tasks: (!preferred_tasks.is_empty()).then_some(preferred_tasks),
                 name: (!name.is_empty()).then_some(name),
             },
         )
@@ -368,8 +867,65 @@ impl From<&Slot> for (SlotId, PySlot) {
     }
 }
 
-/// Python requirements for constructing a [`Task`]
+/// Python requirements for constructing a [`SlotTemplate`]
 #[derive(Debug, Serialize, Deserialize)]
+pub struct PySlotTemplate {
+    /// How long each instantiated slot lasts, in seconds
+    #[serde(with = "crate::data::seconds")]
+    pub duration: TimeDelta,
+
+    /// The minimum number of [`User`]s that must be assigned to each slot
+    pub min_staff: Option<usize>,
+
+    /// The maximum number of [`User`]s that can be assigned to each slot
+    #[serde(default)]
+    pub max_staff: Option<usize>,
+
+    /// Tasks that should be done during each slot when feasible
+    #[serde(default)]
+    pub preferred_tasks: Option<TaskSet>,
+
+    /// Optional name for each slot
+    pub name: Option<String>,
+}
+
+impl From<(SlotTemplateId, PySlotTemplate)> for SlotTemplate {
+    #[inline]
+    fn from((id, template): (SlotTemplateId, PySlotTemplate)) -> Self {
+        let PySlotTemplate {
+            duration,
+            min_staff,
+            max_staff,
+            preferred_tasks,
+            name,
+        } = template;
+        Self {
+            id,
+            duration,
+            min_staff: min_staff.and_then(NonZeroUsize::new),
+            max_staff: max_staff.and_then(NonZeroUsize::new),
+            preferred_tasks: preferred_tasks.unwrap_or_default(),
+            name: name.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<&SlotTemplate> for PySlotTemplate {
+    #[inline]
+    fn from(template: &SlotTemplate) -> Self {
+        Self {
+            duration: template.duration,
+            min_staff: template.min_staff.map(NonZeroUsize::get),
+            max_staff: template.max_staff.map(NonZeroUsize::get),
+            preferred_tasks: (!template.preferred_tasks.is_empty())
+                .then(|| template.preferred_tasks.clone()),
+            name: (!template.name.is_empty()).then(|| template.name.clone()),
+        }
+    }
+}
+
+/// Python requirements for constructing a [`Task`]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct PyTask {
     /// The title of the task
     pub title: String,
@@ -379,25 +935,83 @@ pub struct PyTask {
 
     /// When the task should be completed by
     /// ([`None`] if no deadline)
+    ///
+    /// Sets both [`Task::soft_deadline`] and [`Task::hard_deadline`] unless they are provided.
+    /// When reading, this is [`Task::deadline`].
     pub deadline: Option<DateTime<Utc>>,
 
+    /// See [`Task::soft_deadline`]
+    #[serde(default)]
+    pub soft_deadline: Option<DateTime<Utc>>,
+
+    /// See [`Task::hard_deadline`]
+    #[serde(default)]
+    pub hard_deadline: Option<DateTime<Utc>>,
+
+    /// See [`Task::deadline_offset`]
+    #[serde(default, with = "seconds::option")]
+    pub deadline_offset: Option<TimeDelta>,
+
     /// Tasks that must be completed before this one can start
     pub awaiting: Option<TaskSet>,
+
+    /// See [`Task::preferred_users`]
+    ///
+    /// Out-of-range values are [clamped](Preference::saturate).
+    #[serde(default)]
+    pub preferred_users: Option<UserMap<f32>>,
+}
+
+/// One warning for each of a task's preferences towards users that is out of range,
+/// naming task `id` as the item at `index` of the request.
+fn clamp_warnings<'a>(
+    prefs: impl IntoIterator<Item = (&'a UserId, &'a f32)>,
+    index: usize,
+    id: TaskId,
+) -> impl Iterator<Item = fault::BatchFault> {
+    prefs.into_iter().filter_map(move |(user, &pref)| {
+        let (pref, clamped) = Preference::clamped(pref);
+        clamped.map(|e| {
+            let reason = format!("user {user}: {e}; clamped to {}", pref.0);
+            fault::BatchFault::new(index, Some(id), reason)
+        })
+    })
+}
+
+impl PyTask {
+    /// One warning for each of [`PyTask::preferred_users`] that is out of range,
+    /// naming this task as the item at `index` of the request.
+    fn clamp_warnings(&self, index: usize, id: TaskId) -> impl Iterator<Item = fault::BatchFault> {
+        clamp_warnings(self.preferred_users.iter().flatten(), index, id)
+    }
 }
 
 impl From<(TaskId, PyTask)> for Task {
     #[inline]
     fn from((id, task): (TaskId, PyTask)) -> Self {
         let PyTask {
-            title, deadline, ..
+            title,
+            deadline,
+            soft_deadline,
+            hard_deadline,
+            deadline_offset,
+            preferred_users,
+            ..
         } = task;
         Task {
             id,
             title,
             desc: task.desc.unwrap_or_default(),
             skills: FxHashMap::default(),
-            deadline,
+            soft_deadline: soft_deadline.or(deadline),
+            hard_deadline: hard_deadline.or(deadline),
+            deadline_offset,
             deps: task.awaiting.map(FxHashSet::from_iter).unwrap_or_default(),
+            preferred_users: preferred_users
+                .into_iter()
+                .flatten()
+                .map(|(user, pref)| (user, Preference::clamped(pref).0))
+                .collect(),
         }
     }
 }
@@ -405,13 +1019,17 @@ impl From<(TaskId, PyTask)> for Task {
 impl From<Task> for (TaskId, PyTask) {
     #[inline]
     fn from(task: Task) -> Self {
+        let deadline = task.deadline();
         let Task {
             id,
             title,
             desc,
             skills: _,
-            deadline,
+            soft_deadline,
+            hard_deadline,
+            deadline_offset,
             deps,
+            preferred_users,
         } = task;
         (
             id,
@@ -419,7 +1037,16 @@ impl From<Task> for (TaskId, PyTask) {
                 title,
                 desc: (!desc.is_empty()).then_some(desc),
                 deadline,
+                soft_deadline,
+                hard_deadline,
+                deadline_offset,
                 awaiting: (!deps.is_empty()).then(|| deps.clone()),
+                preferred_users: (!preferred_users.is_empty()).then(|| {
+                    preferred_users
+                        .into_iter()
+                        .map(|(user, Preference(pref))| (user, pref))
+                        .collect()
+                }),
             },
         )
     }
@@ -433,16 +1060,28 @@ impl From<&Task> for (TaskId, PyTask) {
             title,
             desc,
             skills: _,
-            deadline,
+            soft_deadline,
+            hard_deadline,
+            deadline_offset,
             deps,
+            preferred_users,
         } = task;
         (
             *id,
             PyTask {
                 title: title.clone(),
                 desc: (!desc.is_empty()).then(|| desc.clone()),
-                deadline: *deadline,
+                deadline: task.deadline(),
+                soft_deadline: *soft_deadline,
+                hard_deadline: *hard_deadline,
+                deadline_offset: *deadline_offset,
                 awaiting: (!deps.is_empty()).then(|| deps.iter().copied().collect()),
+                preferred_users: (!preferred_users.is_empty()).then(|| {
+                    preferred_users
+                        .iter()
+                        .map(|(&user, &Preference(pref))| (user, pref))
+                        .collect()
+                }),
             },
         )
     }
@@ -463,6 +1102,8 @@ impl From<(UserId, PyUser)> for User {
             id,
             name,
             availability: RuleMap::default(),
+            rule_order: Vec::new(),
+            default_availability: None,
             user_prefs: UserMap::default(),
             skills: SkillMap::default(),
         }
@@ -485,12 +1126,122 @@ impl From<&User> for (UserId, PyUser) {
     }
 }
 
+/// Python representation of the staffing of one [`Slot`] in a [`Schedule`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PyAssignment {
+    /// The slot being staffed
+    pub slot: SlotId,
+
+    /// The users staffed on the slot, in ascending order of ID
+    pub users: Vec<UserId>,
+}
+
+/// Python representation of a [`Schedule`]
+///
+/// The scheduler does not assign tasks to slots yet, so only staffing is included.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PySchedule(
+    /// One assignment per slot, in ascending order of slot ID
+    pub Vec<PyAssignment>,
+);
+
+impl From<PySchedule> for Schedule {
+    #[inline]
+    fn from(PySchedule(assignments): PySchedule) -> Self {
+        Schedule(
+            assignments
+                .into_iter()
+                .map(|PyAssignment { slot, users }| (slot, UserSet::from_iter(users)))
+                .collect(),
+        )
+    }
+}
+
+impl From<&Schedule> for PySchedule {
+    #[inline]
+    fn from(schedule: &Schedule) -> Self {
+        let mut assignments = schedule
+            .into_iter()
+            .map(|(&slot, staff)| {
+                let mut users = Vec::from_iter(staff.iter().copied());
+                users.sort_by_key(|id| id.0);
+                PyAssignment { slot, users }
+            })
+            .collect::<Vec<_>>();
+        assignments.sort_by_key(|assignment| assignment.slot.0);
+        PySchedule(assignments)
+    }
+}
+
+impl From<Schedule> for PySchedule {
+    #[inline]
+    fn from(schedule: Schedule) -> Self {
+        (&schedule).into()
+    }
+}
+
+/// The result of an endpoint that clamps out-of-range values rather than rejecting them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Warned<T> {
+    /// What the endpoint produced.
+    pub value: T,
+
+    /// One entry for each value that was clamped, in the same form as a [`fault::at`] message.
+    pub warnings: Vec<fault::BatchFault>,
+}
+
+/// A record, or only some of its fields. See [`TaskFilter::fields`] and [`SlotFilter::fields`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Projected<T> {
+    /// Every field was requested.
+    Full(T),
+
+    /// Only the requested fields; the rest are omitted.
+    Partial(serde_json::Map<String, serde_json::Value>),
+}
+
+/// Keep only the `fields` of `value`, or all of them if [`None`].
+///
+/// `F` must serialize as the name of a field of `T`.
+fn project<T: Serialize, F: Serialize>(value: T, fields: Option<&[F]>) -> Result<Projected<T>> {
+    let Some(fields) = fields else {
+        return Ok(Projected::Full(value));
+    };
+    let keep = fields
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<serde_json::Result<Vec<_>>>()
+        .map_err(fault::internal)?;
+    match serde_json::to_value(&value).map_err(fault::internal)? {
+        serde_json::Value::Object(mut map) => {
+            map.retain(|k, _| keep.iter().any(|field| field.as_str() == Some(k)));
+            Ok(Projected::Partial(map))
+        }
+        _ => Err(fault::internal("only structs can be projected")),
+    }
+}
+
 /// Add one or more availability rules to one or more users.
 ///
 /// Returns the generated IDs of the newly created rules in the order they were provided.
 ///
 /// If a provided user does not exist, those rules will not be created and that user will be missing from the returned dictionary.
 ///
+/// # Errors
+///
+/// The request is rejected if the same user is provided more than once (see [`UniqueKeys`]).
+///
+/// Produces a [`fault::at`] [`fault::UNPROCESSABLE`] error for the first rule that
+/// [repeats too many times](Repetition::exceeds_max_occurrences), or that is not aligned to the
+/// [slot granularity](SLOT_GRANULARITY) when it rejects unaligned times. No rules are added if any fail.
+///
+/// Otherwise, the ends of `include` and `exclude` intervals are rounded to the slot granularity if one is set.
+///
+/// Finite preferences outside of -1 to +1 are [clamped](Preference::saturate) rather than rejected,
+/// with a warning giving the rule's index and user.
+///
 /// # Signature
 /// ```py
 /// def add_rules(to_add: dict[
@@ -498,27 +1249,94 @@ impl From<&User> for (UserId, PyUser) {
 ///   list[{
 ///     'start': datetime,
 ///     'end': datetime,  # must be >=`start`
-///     'pref': float,    # must be between -1 and +1, or exactly +/-infinity
+///     'pref': float,    # clamped to between -1 and +1, unless exactly +/-infinity
 ///   }]
-/// ]) -> set[UserId];
+/// ]) -> {
+///   'value': dict[UserId, list[RuleId]],
+///   'warnings': list[{'index': int, 'id': str | None, 'reason': str}],
+/// };
 /// ```
-pub fn add_rules(to_add: UserMap<Vec<PyRule>>) -> Result<UserMap<Vec<RuleId>>> {
+pub fn add_rules(
+    UniqueKeys(to_add): UniqueKeys<UserId, Vec<PyRule>>,
+) -> Result<Warned<UserMap<Vec<RuleId>>>> {
+    if to_add.is_empty() {
+        return Ok(Warned::default());
+    }
     let mut users = USERS.write();
-    Ok(to_add
+    let mut converted = Vec::with_capacity(to_add.len());
+    let mut warnings = Vec::new();
+    for (user_id, rules) in to_add {
+        if !users.contains_key(&user_id) {
+            continue;
+        }
+        let ids = RuleId::take(rules.len() as u128);
+        let rules = ids
+            .zip(rules)
+            .enumerate()
+            .map(|(index, (id, mut rule))| {
+                let (pref, clamped) = Preference::clamped(rule.preference);
+                if let Some(e) = clamped {
+                    let reason = format!("{e}; clamped to {}", pref.0);
+                    warnings.push(fault::BatchFault::new(index, Some(user_id), reason));
+                }
+                rule.preference = pref.0;
+                align_all(rule.include.iter_mut().chain(rule.exclude.iter_mut()))
+                    .map_err(|e| e.to_string())
+                    .and_then(|()| {
+                        check_occurrences(rule.repeat.clone().map(Repetition::from).as_ref())
+                    })
+                    .and_then(|()| Rule::try_from((id, rule)).map_err(|e| e.to_string()))
+                    .map_err(|reason| fault::at(fault::UNPROCESSABLE, index, Some(user_id), reason))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        converted.push((user_id, rules));
+    }
+
+    let added = converted
         .into_iter()
         .filter_map(|(user_id, rules)| {
-            users.get_mut(&user_id).map(|user| {
-                let ids = RuleId::take(rules.len().try_into().unwrap());
-                user.availability.extend(
-                    ids.clone()
-                        .zip(rules)
-                        .map(Rule::from)
-                        .map(|rule| (rule.id, rule)),
-                );
-                (user_id, ids.collect())
-            })
+            let user = users.get_mut(&user_id)?;
+            let ids = rules.iter().map(|rule| rule.id).collect::<Vec<_>>();
+            user.availability
+                .extend(rules.into_iter().map(|rule| (rule.id, rule)));
+            user.rule_order.extend(ids.iter().copied());
+            Some((user_id, ids))
         })
-        .collect())
+        .collect();
+    notify_mutation(Mutation::USERS);
+    Ok(Warned {
+        value: added,
+        warnings,
+    })
+}
+
+/// Align `interval` to [`SLOT_GRANULARITY`], if set.
+fn align(interval: TimeInterval) -> std::result::Result<TimeInterval, AlignError> {
+    match *SLOT_GRANULARITY.read() {
+        Some(granularity) => granularity.align(interval),
+        None => Ok(interval),
+    }
+}
+
+/// [Align](align) each of `intervals` in place, stopping at the first that fails.
+fn align_all<'a>(
+    intervals: impl IntoIterator<Item = &'a mut TimeInterval>,
+) -> std::result::Result<(), AlignError> {
+    intervals.into_iter().try_for_each(|interval| {
+        *interval = align(*interval)?;
+        Ok(())
+    })
+}
+
+/// Reject a repetition that would expand past [`MAX_OCCURRENCES`].
+fn check_occurrences(rep: Option<&Repetition>) -> std::result::Result<(), String> {
+    match rep {
+        Some(rep) if rep.exceeds_max_occurrences() => Err(format!(
+            "repetition exceeds the maximum of {} occurrences",
+            MAX_OCCURRENCES.load(Relaxed)
+        )),
+        _ => Ok(()),
+    }
 }
 
 /// Insert one or more slots into the slot list.
@@ -527,12 +1345,22 @@ pub fn add_rules(to_add: UserMap<Vec<PyRule>>) -> Result<UserMap<Vec<RuleId>>> {
 ///
 /// Argument must be an array, even if only adding one.
 ///
+/// # Errors
+///
+/// Produces a [`fault::at`] [`fault::UNPROCESSABLE`] error for the first slot that ends before it starts,
+/// or that is not aligned to the [slot granularity](SLOT_GRANULARITY) when it rejects unaligned times.
+/// No slots are added if any fail.
+///
+/// Otherwise, start and end are rounded to the slot granularity if one is set.
+///
 /// # Signature
 /// ```py
 /// def add_slots(list[{
 ///   'start': datetime,
 ///   'end':   datetime,        # must be >=`start`
 ///   'min_staff': int | None,  # cannot be negative; None is equivalent to 0
+///   'max_staff': int | None,  # cannot be negative; None or 0 is unlimited
+///   'preferred_tasks': set[TaskId] | None,
 ///   'name': str | None,
 /// }]) -> list[SlotId];
 /// ```
@@ -546,41 +1374,232 @@ pub fn add_rules(to_add: UserMap<Vec<PyRule>>) -> Result<UserMap<Vec<RuleId>>> {
 ///   'min_staff': 3,
 /// }])
 /// ```
-pub fn add_slots(to_add: Vec<PySlot>) -> Result<Vec<SlotId>> {
-    let ids = SlotId::take(to_add.len().try_into().unwrap());
-    SLOTS.write().extend(
+pub fn add_slots(mut to_add: Vec<PySlot>) -> Result<Vec<SlotId>> {
+    if to_add.is_empty() {
+        return Ok(Vec::new());
+    }
+    for (index, slot) in to_add.iter_mut().enumerate() {
+        let interval = TimeInterval {
+            start: slot.start,
+            end: slot.end,
+        };
+        let TimeInterval { start, end } = align(interval)
+            .map_err(|e| fault::at(fault::UNPROCESSABLE, index, None::<SlotId>, e))?;
+        (slot.start, slot.end) = (start, end);
+    }
+    if let Some(index) = to_add.iter().position(|slot| slot.end < slot.start) {
+        return Err(fault::at(
+            fault::UNPROCESSABLE,
+            index,
+            None::<SlotId>,
+            "end must not be before start",
+        ));
+    }
+    let ids = SlotId::take(to_add.len() as u128);
+    let mut slots = SLOTS.write();
+    slots.extend(
         ids.clone()
             .zip(to_add)
             .map(Slot::from)
             .map(|slot| (slot.id, slot)),
     );
+    notify_mutation(Mutation::SLOTS);
     Ok(ids.collect())
 }
 
-/// Insert one or more tasks into the user table.
-///
-/// Returns the generated IDs of the newly created tasks in the order they were provided.
+/// Insert one or more slot templates, for creating slots with [`instantiate_template`].
 ///
-/// Argument must be an array, even if only adding one.
+/// Returns the generated IDs of the newly created templates in the order they were provided.
 ///
 /// # Signature
 /// ```py
-/// def add_tasks(to_add: list[{
-///   'title': str,
-///   'desc': str | None,
-///   'deadline': datetime | None,
-///   'awaiting': set[TaskId] | None,
-/// }]) -> list[TaskId];
+/// def add_templates(to_add: list[{
+///   'duration': int,  # seconds
+///   'min_staff': int | None,
+///   'max_staff': int | None,
+///   'preferred_tasks': set[TaskId] | None,
+///   'name': str | None,
+/// }]) -> list[SlotTemplateId];
 /// ```
 ///
 /// # Examples
 /// ```py
-/// # add a single task titled "wash dishes"
-/// proxy.add_tasks([{'title': "wash dishes"}])
-///
-/// # add a task titled "train intern" with a description
-/// proxy.add_tasks([{
-///   'title': "train intern",
+/// # an 8-hour weekday shift needing at least 2 staff
+/// [weekday] = proxy.add_templates([{'duration': 8 * 60 * 60, 'min_staff': 2, 'name': "weekday"}])
+/// ```
+pub fn add_templates(to_add: Vec<PySlotTemplate>) -> Result<Vec<SlotTemplateId>> {
+    if to_add.is_empty() {
+        return Ok(Vec::new());
+    }
+    let ids = SlotTemplateId::take(to_add.len() as u64);
+    let mut templates = TEMPLATES.write();
+    templates.extend(
+        ids.clone()
+            .zip(to_add)
+            .map(SlotTemplate::from)
+            .map(|template| (template.id, template)),
+    );
+    notify_mutation(Mutation::TEMPLATES);
+    Ok(ids.collect())
+}
+
+/// Returns a dictionary of all slot templates.
+///
+/// # Signature
+/// ```py
+/// def get_templates(_: {}) -> dict[SlotTemplateId, {
+///   'duration': int,  # seconds
+///   'min_staff': int | None,  # will always be >=1 if not None
+///   'max_staff': int | None,  # will always be >=1 if not None
+///   'preferred_tasks': set[TaskId] | None,  # will never be empty if not None
+///   'name': str | None,
+/// }];
+/// ```
+pub fn get_templates((): ()) -> Result<SlotTemplateMap<PySlotTemplate>> {
+    Ok(read_or_busy(&TEMPLATES, "templates")?
+        .values()
+        .map(|template| (template.id, template.into()))
+        .collect())
+}
+
+/// Create a slot from a template, starting at `start`.
+///
+/// Returns the generated ID of the new slot.
+///
+/// # Errors
+///
+/// Produces a [`fault::not_found`] error if the template does not exist,
+/// or a [`fault::unprocessable`] error if the slot would end too far in the future to represent
+/// or is not aligned to the [slot granularity](SLOT_GRANULARITY) when it rejects unaligned times.
+///
+/// Otherwise, the slot is rounded to the slot granularity if one is set.
+///
+/// # Signature
+/// ```py
+/// def instantiate_template(template_id: SlotTemplateId, start: datetime) -> SlotId;
+/// ```
+///
+/// # Examples
+/// ```py
+/// # staff the weekday shift on Monday morning
+/// proxy.instantiate_template(weekday, datetime(2025, 4, 14, 9))
+/// ```
+pub fn instantiate_template(
+    (template_id, start): (SlotTemplateId, DateTime<Utc>),
+) -> Result<SlotId> {
+    let mut slots = SLOTS.write();
+    let templates = TEMPLATES.read();
+    let template = templates
+        .get(&template_id)
+        .ok_or_else(|| fault::not_found(format_args!("template {template_id} does not exist")))?;
+    let id = SlotId::next().ok_or_else(|| fault::internal("out of slot IDs"))?;
+    let mut slot = template
+        .instantiate(id, start)
+        .ok_or_else(|| fault::unprocessable(format_args!("cannot be instantiated at {start}")))?;
+    slot.interval = align(slot.interval).map_err(fault::unprocessable)?;
+    slots.insert(id, slot);
+    notify_mutation(Mutation::SLOTS);
+    Ok(id)
+}
+
+/// Copy a slot onto each of the provided dates, keeping its duration, staffing, and name.
+///
+/// Returns the generated IDs of the copies in the order their dates were provided.
+///
+/// # Errors
+///
+/// Produces a [`fault::not_found`] error if the slot does not exist,
+/// a [`fault::unprocessable`] error if `onto` has more than [`MAX_OCCURRENCES`] dates,
+/// or a [`fault::at`] [`fault::UNPROCESSABLE`] error indexing the first date in `onto`
+/// where a copy would end too far in the future to represent, or is not aligned to the
+/// [slot granularity](SLOT_GRANULARITY) when it rejects unaligned times.
+/// No copies are made if any fail.
+///
+/// Otherwise, copies are rounded to the slot granularity if one is set.
+///
+/// # Signature
+/// ```py
+/// def duplicate_slot(slot_id: SlotId, onto: list[datetime]) -> list[SlotId];
+/// ```
+///
+/// # Examples
+/// ```py
+/// # repeat a slot on the next four Mondays
+/// proxy.duplicate_slot(slot_id, [monday + timedelta(weeks=n) for n in range(1, 5)])
+/// ```
+pub fn duplicate_slot((slot_id, onto): (SlotId, Vec<DateTime<Utc>>)) -> Result<Vec<SlotId>> {
+    let max = MAX_OCCURRENCES.load(Relaxed);
+    if onto.len() > max {
+        return Err(fault::unprocessable(format_args!(
+            "cannot make more than {max} copies at once, got {}",
+            onto.len()
+        )));
+    }
+    let mut slots = SLOTS.write();
+    let slot = slots
+        .get(&slot_id)
+        .ok_or_else(|| fault::not_found(format_args!("slot {slot_id} does not exist")))?;
+    let intervals = onto
+        .into_iter()
+        .enumerate()
+        .map(|(index, start)| {
+            slot.interval
+                .shifted(start - slot.interval.start)
+                .ok_or_else(|| format!("cannot be copied onto {start}"))
+                .and_then(|interval| align(interval).map_err(|e| e.to_string()))
+                .map_err(|reason| fault::at(fault::UNPROCESSABLE, index, Some(slot_id), reason))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let ids = SlotId::take(intervals.len() as u128);
+    let copies = ids
+        .clone()
+        .zip(intervals)
+        .map(|(id, interval)| Slot {
+            id,
+            interval,
+            ..slot.clone()
+        })
+        .collect::<Vec<_>>();
+    slots.extend(copies.into_iter().map(|slot| (slot.id, slot)));
+    notify_mutation(Mutation::SLOTS);
+    Ok(ids.collect())
+}
+
+/// Insert one or more tasks into the user table.
+///
+/// Returns the generated IDs of the newly created tasks in the order they were provided.
+///
+/// Argument must be an array, even if only adding one.
+///
+/// Finite preferences outside of -1 to +1 are [clamped](Preference::saturate) rather than rejected,
+/// with a warning giving the task's index and ID.
+///
+/// # Signature
+/// ```py
+/// def add_tasks(to_add: list[{
+///   'title': str,
+///   'desc': str | None,
+///   'deadline': datetime | None,       # sets both deadlines
+///   'soft_deadline': datetime | None,  # missing it is penalized
+///   'hard_deadline': datetime | None,  # missing it is a scheduling failure
+///   'deadline_offset': int | None,  # seconds after the latest dependency
+///   'awaiting': set[TaskId] | None,
+///   'preferred_users': dict[UserId, float] | None,  # +inf to always staff, -inf to never
+/// }]) -> {
+///   'value': list[TaskId],
+///   'warnings': list[{'index': int, 'id': str | None, 'reason': str}],
+/// };
+/// ```
+///
+/// # Examples
+/// ```py
+/// # add a single task titled "wash dishes"
+/// proxy.add_tasks([{'title': "wash dishes"}])
+///
+/// # add a task titled "train intern" with a description
+/// proxy.add_tasks([{
+///   'title': "train intern",
 ///   'desc': "the new intern, joel, needs to be trained on how to work the register.",
 /// }])
 ///
@@ -597,15 +1616,99 @@ pub fn add_slots(to_add: Vec<PySlot>) -> Result<Vec<SlotId>> {
 /// ```
 ///
 /// **See also:** [`datetime`](https://docs.python.org/3/library/datetime.html)
-pub fn add_tasks(to_add: Vec<PyTask>) -> Result<Vec<TaskId>> {
-    let ids = TaskId::take(to_add.len().try_into().unwrap());
-    TASKS.write().extend(
-        ids.clone()
-            .zip(to_add)
-            .map(Task::from)
-            .map(|task| (task.id, task)),
-    );
-    Ok(ids.collect())
+pub fn add_tasks(to_add: Vec<PyTask>) -> Result<Warned<Vec<TaskId>>> {
+    if to_add.is_empty() {
+        return Ok(Warned::default());
+    }
+    let ids = TaskId::take(to_add.len() as u64);
+    let mut warnings = Vec::new();
+    let mut tasks = TASKS.write();
+    tasks.extend(ids.clone().zip(to_add).enumerate().map(|(index, (id, task))| {
+        warnings.extend(task.clamp_warnings(index, id));
+        (id, Task::from((id, task)))
+    }));
+    notify_mutation(Mutation::TASKS);
+    Ok(Warned {
+        value: ids.collect(),
+        warnings,
+    })
+}
+
+/// The result of [`add_tasks_unique`].
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UniqueTasks {
+    /// The ID of each provided task in the order they were provided,
+    /// whether newly created or already existing.
+    pub ids: Vec<TaskId>,
+
+    /// The positions of the provided tasks whose title was already taken,
+    /// and so were mapped onto the existing task instead of being created.
+    pub reused: Vec<usize>,
+}
+
+/// Insert one or more tasks into the task table, skipping any whose title
+/// (compared case-insensitively) is already taken by an existing task or an
+/// earlier task in the same call.
+///
+/// Returns the ID of each task in the order they were provided, along with
+/// the positions of those which were mapped onto an existing task.
+///
+/// Preferences are clamped as in [`add_tasks`]. Tasks that are reused are not checked.
+///
+/// # Signature
+/// ```py
+/// def add_tasks_unique(to_add: list[PyTask]) -> {
+///   'value': {
+///     'ids': list[TaskId],
+///     'reused': list[int],
+///   },
+///   'warnings': list[{'index': int, 'id': str | None, 'reason': str}],
+/// };
+/// ```
+///
+/// # Examples
+/// ```py
+/// # "Wash Dishes" is mapped onto the existing "wash dishes" task
+/// [a] = proxy.add_tasks_unique([{'title': "wash dishes"}])['value']['ids']
+/// assert proxy.add_tasks_unique([{'title': "Wash Dishes"}])['value'] == {'ids': [a], 'reused': [0]}
+/// ```
+///
+/// **See also:** [`add_tasks`]
+pub fn add_tasks_unique(to_add: Vec<PyTask>) -> Result<Warned<UniqueTasks>> {
+    let mut tasks = TASKS.write();
+    let mut by_title = FxHashMap::<String, TaskId>::default();
+    for task in tasks.values() {
+        by_title
+            .entry(task.title.to_lowercase())
+            .and_modify(|id| *id = TaskId((id.0).min(task.id.0)))
+            .or_insert(task.id);
+    }
+    let mut ids = Vec::with_capacity(to_add.len());
+    let mut reused = Vec::new();
+    let mut warnings = Vec::new();
+    for (index, task) in to_add.into_iter().enumerate() {
+        match by_title.entry(task.title.to_lowercase()) {
+            Entry::Occupied(entry) => {
+                ids.push(*entry.get());
+                reused.push(index);
+            }
+            Entry::Vacant(entry) => {
+                let id = TaskId::next().ok_or_else(|| fault::internal("out of task IDs"))?;
+                entry.insert(id);
+                warnings.extend(task.clamp_warnings(index, id));
+                tasks.insert(id, Task::from((id, task)));
+                ids.push(id);
+            }
+        }
+    }
+    if ids.len() > reused.len() {
+        notify_mutation(Mutation::TASKS);
+    }
+    drop(tasks);
+    Ok(Warned {
+        value: UniqueTasks { ids, reused },
+        warnings,
+    })
 }
 
 /// Insert one or more users into the user table.
@@ -628,20 +1731,26 @@ pub fn add_tasks(to_add: Vec<PyTask>) -> Result<Vec<TaskId>> {
 /// proxy.add_users([{'name': "tom"}, {'name': "sally"}])
 /// ```
 pub fn add_users(to_add: Vec<PyUser>) -> Result<Vec<UserId>> {
-    let ids = UserId::take(to_add.len().try_into().unwrap());
-    USERS.write().extend(
+    if to_add.is_empty() {
+        return Ok(Vec::new());
+    }
+    let ids = UserId::take(to_add.len() as u64);
+    let mut users = USERS.write();
+    users.extend(
         ids.clone()
             .zip(to_add)
             .map(User::from)
             .map(|user| (user.id, user)),
     );
+    notify_mutation(Mutation::USERS);
     Ok(ids.collect())
 }
 
 /// A filter for selecting [`Rule`]s from the backend database.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RuleFilter {
     /// A whitelist of the exact [`Rule::id`]s that should be included.
+    /// An empty set includes nothing, while [`None`] does not filter by ID.
     pub ids: Option<RuleSet>,
 
     /// The least preference the [`Rule`] can require.
@@ -651,13 +1760,28 @@ pub struct RuleFilter {
     pub max_pref: Option<f32>,
 }
 
+impl RuleFilter {
+    /// Whether `rule` satisfies every condition.
+    fn matches(&self, rule: &Rule) -> bool {
+        self.min_pref.is_none_or(|x| rule.pref.0 >= x)
+            && self.max_pref.is_none_or(|x| rule.pref.0 <= x)
+            // note that None => "do not filter", which is distinct from {} => "never"
+            && self.ids.as_ref().is_none_or(|x| x.contains(&rule.id))
+    }
+}
+
 /// Returns an dictionary of all current availability rules associated with each user, filtered by the parameters.
 ///
 /// Users that do not exist will be missing from the returned dictionary.
+/// Dictionaries are unordered; use [`get_all_rules`] for each user's rules in [rule order](get_rule_order).
 ///
 /// Each filter parameter is combined as "and" (tasks must satisfy *all* conditions to be included).
 /// Parameters that are [`None`] will be ignored.
 ///
+/// # Errors
+///
+/// Produces a [`fault::bad_request`] error if any filter has a `min_pref` greater than its `max_pref`.
+///
 /// # Signature
 /// ```py
 /// def get_rules(filter: dict[UserId, {
@@ -667,6 +1791,8 @@ pub struct RuleFilter {
 /// }]) -> list[(
 ///   {
 ///     'include': list[range[datetime]],
+///     'exclude': list[range[datetime]],
+///     'set_at': datetime | None,
 ///     'repeat': {
 ///       'every': {
 ///         seconds: int | None,  # will always be >=1 if not None
@@ -685,27 +1811,19 @@ pub struct RuleFilter {
 /// )];
 /// ```
 pub fn get_rules(filter: UserMap<RuleFilter>) -> Result<UserMap<RuleMap<PyRule>>> {
-    let users = USERS.read();
+    for filter in filter.values() {
+        check_range(("min_pref", filter.min_pref), ("max_pref", filter.max_pref))?;
+    }
+    let users = read_or_busy(&USERS, "users")?;
     filter
         .into_iter()
         .flat_map(|(user_id, filter)| {
             users.get(&user_id).map(|user| {
-                let RuleFilter {
-                    ids,
-                    min_pref,
-                    max_pref,
-                } = filter;
-                let ids = ids.as_ref();
                 Ok((
                     user_id,
                     user.availability
                         .values()
-                        .filter(|rule| {
-                            min_pref.is_none_or(|x| rule.pref.0 >= x)
-                                && max_pref.is_none_or(|x| rule.pref.0 <= x)
-                                // note that None => "do not filter", which is distinct from {} => "never"
-                                && ids.is_none_or(|x| x.contains(&rule.id))
-                        })
+                        .filter(|rule| filter.matches(rule))
                         .map(From::from)
                         .collect(),
                 ))
@@ -714,10 +1832,349 @@ pub fn get_rules(filter: UserMap<RuleFilter>) -> Result<UserMap<RuleMap<PyRule>>
         .collect()
 }
 
+/// Like [`get_rules`], but applies the same filter to each of `users`,
+/// or to every user if `users` is empty.
+///
+/// Users that do not exist will be missing from the returned dictionary.
+///
+/// # Errors
+///
+/// Produces a [`fault::bad_request`] error if `min_pref` is greater than `max_pref`.
+///
+/// # Signature
+/// ```py
+/// def get_rules_for(users: list[UserId], filter: {
+///     'ids': set[RuleId] | None,
+///     'min_pref': float | None,
+///     'max_pref': float | None,  # must be >=`min_pref`
+/// }) -> dict[UserId, dict[RuleId, Rule]];  # see `get_rules` for the shape of `Rule`
+/// ```
+///
+/// # Examples
+/// ```py
+/// # the rules users 0 and 1 would like to work
+/// proxy.get_rules_for([0, 1], {'min_pref': 0.0})
+/// ```
+pub fn get_rules_for(
+    (users, filter): (Vec<UserId>, RuleFilter),
+) -> Result<UserMap<RuleMap<PyRule>>> {
+    check_range(("min_pref", filter.min_pref), ("max_pref", filter.max_pref))?;
+    let all_users = read_or_busy(&USERS, "users")?;
+    let rules_of = |user: &User| {
+        user.availability
+            .values()
+            .filter(|rule| filter.matches(rule))
+            .map(From::from)
+            .collect()
+    };
+    Ok(if users.is_empty() {
+        all_users
+            .values()
+            .map(|user| (user.id, rules_of(user)))
+            .collect()
+    } else {
+        users
+            .into_iter()
+            .filter_map(|id| all_users.get(&id).map(|user| (id, rules_of(user))))
+            .collect()
+    })
+}
+
+/// Returns every availability rule of every user that matches the filter, as `(user, rule id, rule)`.
+///
+/// Sorted by user ID, then in each user's [rule order](get_rule_order).
+///
+/// # Errors
+///
+/// Produces a [`fault::bad_request`] error if `min_pref` is greater than `max_pref`.
+///
+/// # Signature
+/// ```py
+/// def get_all_rules(filter: {
+///     'ids': set[RuleId] | None,
+///     'min_pref': float | None,
+///     'max_pref': float | None,  # must be >=`min_pref`
+/// }) -> list[tuple[UserId, RuleId, Rule]];  # see `get_rules` for the shape of `Rule`
+/// ```
+pub fn get_all_rules(filter: RuleFilter) -> Result<Vec<(UserId, RuleId, PyRule)>> {
+    check_range(("min_pref", filter.min_pref), ("max_pref", filter.max_pref))?;
+    let users = read_or_busy(&USERS, "users")?;
+    let mut users = Vec::from_iter(users.values());
+    users.sort_by_key(|user| user.id.0);
+    Ok(users
+        .into_iter()
+        .flat_map(|user| {
+            user.rules_in_order()
+                .filter(|rule| filter.matches(rule))
+                .map(|rule| {
+                    let (rule_id, rule) = rule.into();
+                    (user.id, rule_id, rule)
+                })
+        })
+        .collect())
+}
+
+/// Returns the order a user's availability rules should be presented in.
+///
+/// # Errors
+///
+/// Produces a [`fault::not_found`] error if the user does not exist.
+///
+/// # Signature
+/// ```py
+/// def get_rule_order(user_id: UserId) -> list[RuleId];
+/// ```
+pub fn get_rule_order(user_id: UserId) -> Result<Vec<RuleId>> {
+    read_or_busy(&USERS, "users")?
+        .get(&user_id)
+        .map(|user| user.rules_in_order().map(|rule| rule.id).collect())
+        .ok_or_else(|| fault::not_found(format_args!("user {user_id} does not exist")))
+}
+
+/// Change the order a user's availability rules should be presented in.
+///
+/// # Errors
+///
+/// Produces a [`fault::not_found`] error if the user does not exist,
+/// or a [`fault::unprocessable`] error if `order` is not a permutation
+/// of the user's rule IDs.
+///
+/// # Signature
+/// ```py
+/// def reorder_rules(user_id: UserId, order: list[RuleId]) -> None;
+/// ```
+///
+/// # Examples
+/// ```py
+/// # move the last rule to the front
+/// order = proxy.get_rule_order(user_id)
+/// proxy.reorder_rules(user_id, order[-1:] + order[:-1])
+/// ```
+pub fn reorder_rules((user_id, order): (UserId, Vec<RuleId>)) -> Result<()> {
+    let mut users = USERS.write();
+    let user = users
+        .get_mut(&user_id)
+        .ok_or_else(|| fault::not_found(format_args!("user {user_id} does not exist")))?;
+
+    let mut remaining = user.availability.keys().copied().collect::<RuleSet>();
+    if order.len() != remaining.len() || !order.iter().all(|id| remaining.remove(id)) {
+        return Err(fault::unprocessable(format_args!(
+            "order must contain each rule of user {user_id} exactly once"
+        )));
+    }
+    user.rule_order = order;
+    notify_mutation(Mutation::USERS);
+    Ok(())
+}
+
+/// Drop a user's availability rules that make no difference to their preferences,
+/// such as duplicates and rules within a broader rule of the same preference.
+/// See [`User::normalize_rules`].
+///
+/// Returns the IDs of the dropped rules.
+///
+/// # Errors
+///
+/// Produces a [`fault::not_found`] error if the user does not exist.
+///
+/// # Signature
+/// ```py
+/// def normalize_user_rules(user_id: UserId) -> list[RuleId];
+/// ```
+pub fn normalize_user_rules(user_id: UserId) -> Result<Vec<RuleId>> {
+    let mut users = USERS.write();
+    let user = users
+        .get_mut(&user_id)
+        .ok_or_else(|| fault::not_found(format_args!("user {user_id} does not exist")))?;
+    let dropped = user.normalize_rules();
+    if !dropped.is_empty() {
+        notify_mutation(Mutation::USERS);
+    }
+    Ok(dropped)
+}
+
+/// Set each user's proficiency with a skill, keeping any expiration the skill already had.
+///
+/// Returns the IDs of the users that do not exist.
+///
+/// A negative `proficiency` is [clamped](Proficiency::saturate) rather than rejected, with a warning
+/// whose `index` is that of the `proficiency` argument.
+///
+/// # Errors
+///
+/// Produces a [`fault::unprocessable`] error if `proficiency` is infinite or NaN.
+///
+/// # Signature
+/// ```py
+/// def grant_skill(users: list[UserId], skill: SkillId, proficiency: float) -> {
+///   'value': list[UserId],
+///   'warnings': list[{'index': int, 'id': str | None, 'reason': str}],
+/// };
+/// ```
+///
+/// # Examples
+/// ```py
+/// # onboard a cohort as baseline cashiers
+/// missing = proxy.grant_skill(cohort, cashier, 1.0)['value']
+/// ```
+pub fn grant_skill(
+    (user_ids, skill, proficiency): (Vec<UserId>, SkillId, f32),
+) -> Result<Warned<UserSet>> {
+    if proficiency.is_nan() {
+        return Err(fault::unprocessable("proficiency must not be NaN"));
+    }
+    let (proficiency, clamped) = Proficiency::clamped(proficiency).map_err(fault::unprocessable)?;
+    let warnings = Vec::from_iter(clamped.map(|e| {
+        let reason = format!("{e}; clamped to {}", f32::from(proficiency));
+        fault::BatchFault::new(2, Some(skill), reason)
+    }));
+    let mut users = USERS.write();
+    let mut missing = UserSet::default();
+    for user_id in user_ids {
+        if let Some(user) = users.get_mut(&user_id) {
+            user.skills.entry(skill).or_default().proficiency = proficiency;
+            user.compact();
+        } else {
+            missing.insert(user_id);
+        }
+    }
+    notify_mutation(Mutation::USERS);
+    Ok(Warned {
+        value: missing,
+        warnings,
+    })
+}
+
+/// Returns every user with `skill`, and their proficiency with it, most proficient first.
+///
+/// Users whose proficiency is below `min_proficiency` are left out, as are users without the skill
+/// or whose skill has [expired](UserSkill::expires). Ties are ordered by user ID.
+///
+/// # Signature
+/// ```py
+/// def qualified_users(skill: SkillId, min_proficiency: float | None) -> list[tuple[UserId, float]];
+/// ```
+///
+/// # Examples
+/// ```py
+/// # everyone who can run the register unsupervised
+/// proxy.qualified_users(cashier, 0.8)
+/// ```
+pub fn qualified_users(
+    (skill, min_proficiency): (SkillId, Option<f32>),
+) -> Result<Vec<(UserId, f32)>> {
+    let now = Utc::now();
+    let mut qualified = read_or_busy(&USERS, "users")?
+        .values()
+        .filter_map(|user| {
+            let skill = user.skills.get(&skill)?;
+            let proficiency = *skill.proficiency;
+            let expired = skill.expires.is_some_and(|expires| expires < now);
+            (!expired && proficiency > 0.0 && min_proficiency.is_none_or(|min| proficiency >= min))
+                .then_some((user.id, proficiency))
+        })
+        .collect::<Vec<_>>();
+    qualified.sort_by(|(a_id, a), (b_id, b)| b.total_cmp(a).then(a_id.0.cmp(&b_id.0)));
+    Ok(qualified)
+}
+
+/// Fold the [`User`] `from` into `into`, then delete `from`.
+///
+/// - Skills are unioned, keeping whichever entry has the greater proficiency.
+/// - `from`'s availability [`Rule`]s are appended after `into`'s.
+/// - [`User::user_prefs`] are combined; where both have an opinion of the same user, the stronger one is kept.
+/// - Every other user's preference towards `from` is moved onto `into` the same way,
+///   as is every task's [preference](Task::preferred_users) towards `from`,
+///   and any assignments pinned to `from`.
+///
+/// # Errors
+///
+/// Produces a [`fault::bad_request`] error if `from` and `into` are the same user,
+/// or a [`fault::not_found`] error if either does not exist.
+///
+/// # Signature
+/// ```py
+/// def merge_users(from: UserId, into: UserId) -> None;
+/// ```
+pub fn merge_users((from, into): (UserId, UserId)) -> Result<()> {
+    /// Keep the stronger of two preferences towards the same user.
+    fn combine(prefs: &mut UserMap<Preference>, id: UserId, pref: Preference) {
+        prefs
+            .entry(id)
+            .and_modify(|old| {
+                if pref.abs() > old.abs() {
+                    *old = pref;
+                }
+            })
+            .or_insert(pref);
+    }
+
+    if from == into {
+        return Err(fault::bad_request(format_args!(
+            "cannot merge user {from} into itself"
+        )));
+    }
+    let mut tasks = TASKS.write();
+    let mut users = USERS.write();
+    if !users.contains_key(&into) {
+        return Err(fault::not_found(format_args!("user {into} does not exist")));
+    }
+    let absorbed = users
+        .remove(&from)
+        .ok_or_else(|| fault::not_found(format_args!("user {from} does not exist")))?;
+
+    let Some(survivor) = users.get_mut(&into) else {
+        unreachable!("checked above")
+    };
+    for (skill_id, skill) in absorbed.skills {
+        survivor
+            .skills
+            .entry(skill_id)
+            .and_modify(|old| {
+                if skill.proficiency > old.proficiency {
+                    *old = skill;
+                }
+            })
+            .or_insert(skill);
+    }
+    survivor.availability.extend(absorbed.availability);
+    survivor.rule_order.extend(absorbed.rule_order);
+    survivor.sync_rule_order();
+    for (id, pref) in absorbed.user_prefs {
+        combine(&mut survivor.user_prefs, id, pref);
+    }
+    survivor.user_prefs.remove(&from);
+    survivor.user_prefs.remove(&into);
+    survivor.compact();
+
+    for user in users.values_mut() {
+        if let Some(pref) = user.user_prefs.remove(&from)
+            && user.id != into
+        {
+            combine(&mut user.user_prefs, into, pref);
+        }
+    }
+    for task in tasks.values_mut() {
+        if let Some(pref) = task.preferred_users.remove(&from) {
+            combine(&mut task.preferred_users, into, pref);
+        }
+    }
+
+    let mut pins = PINNED.write();
+    for pinned in pins.users.values_mut() {
+        if pinned.remove(&from) {
+            pinned.insert(into);
+        }
+    }
+    notify_mutation(Mutation::TASKS | Mutation::USERS | Mutation::PINS);
+    Ok(())
+}
+
 /// A filter for selecting [`Slot`]s from the backend database.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SlotFilter {
     /// A whitelist of the exact [`Slot::id`]s that should be included.
+    /// An empty set includes nothing, while [`None`] does not filter by ID.
     pub ids: Option<SlotSet>,
 
     /// The ealiest datetime the [`Slot`] can start at.
@@ -740,15 +2197,98 @@ pub struct SlotFilter {
 
     /// A [`Pattern`] the [`Slot::name`] must [match](Pattern::is_match).
     pub name_pat: Option<Pattern>,
+
+    /// The only fields of each [`PySlot`] to return. [`None`] for all of them.
+    #[serde(default)]
+    pub fields: Option<Vec<SlotField>>,
 }
 
-/// Returns an array of all current slots.
-///
-/// Each filter parameter is combined as "and" (tasks must satisfy *all* conditions to be included).
-/// Parameters that are [`None`] will be ignored.
-///
-/// Patterns should use `^$` (match start followed immediately by end) to match against empty names,
-/// as an empty pattern will always match (the empty set is a subset of every set).
+impl SlotFilter {
+    /// Produces a [`fault::bad_request`] error if any pair of range parameters is inverted.
+    fn check(&self) -> Result<()> {
+        check_range(
+            ("starting_after", self.starting_after),
+            ("starting_before", self.starting_before),
+        )?;
+        check_range(
+            ("ending_after", self.ending_after),
+            ("ending_before", self.ending_before),
+        )?;
+        check_range(
+            ("min_staff_min", self.min_staff_min),
+            ("min_staff_max", self.min_staff_max),
+        )
+    }
+
+    /// Whether `slot` satisfies every condition.
+    fn matches(&self, slot: &Slot) -> bool {
+        let min_staff = slot.min_staff.map_or(0, NonZeroUsize::get);
+        self.starting_before.is_none_or(|x| slot.start <= x)
+            && self.starting_after.is_none_or(|x| slot.start >= x)
+            && self.ending_before.is_none_or(|x| slot.end <= x)
+            && self.ending_after.is_none_or(|x| slot.end >= x)
+            && self.min_staff_min.is_none_or(|x| min_staff >= x)
+            && self.min_staff_max.is_none_or(|x| min_staff <= x)
+            // note that None => "do not filter", which is distinct from {} => "never"
+            && self.ids.as_ref().is_none_or(|x| x.contains(&slot.id))
+            // use "^$" to match against empty names
+            && self.name_pat.as_ref().is_none_or(|x| x.is_match(&slot.name))
+    }
+
+    /// Whether every slot [matches](Self::matches), because no condition is set.
+    fn is_unconditional(&self) -> bool {
+        let Self {
+            ids,
+            starting_after,
+            starting_before,
+            ending_after,
+            ending_before,
+            min_staff_min,
+            min_staff_max,
+            name_pat,
+            fields: _,
+        } = self;
+        ids.is_none()
+            && starting_after.is_none()
+            && starting_before.is_none()
+            && ending_after.is_none()
+            && ending_before.is_none()
+            && min_staff_min.is_none()
+            && min_staff_max.is_none()
+            && name_pat.is_none()
+    }
+}
+
+/// A field of [`PySlot`]. See [`SlotFilter::fields`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlotField {
+    /// [`PySlot::start`]
+    Start,
+    /// [`PySlot::end`]
+    End,
+    /// [`PySlot::min_staff`]
+    MinStaff,
+    /// [`PySlot::max_staff`]
+    MaxStaff,
+    /// [`PySlot::preferred_tasks`]
+    PreferredTasks,
+    /// [`PySlot::name`]
+    Name,
+}
+
+/// Returns an array of all current slots.
+///
+/// Each filter parameter is combined as "and" (tasks must satisfy *all* conditions to be included).
+/// Parameters that are [`None`] will be ignored.
+///
+/// Patterns should use `^$` (match start followed immediately by end) to match against empty names,
+/// as an empty pattern will always match (the empty set is a subset of every set).
+///
+/// # Errors
+///
+/// Produces a [`fault::bad_request`] error if any pair of range parameters is inverted
+/// (e.g. `min_staff_min` greater than `min_staff_max`).
 ///
 /// # Signature
 /// ```py
@@ -761,49 +2301,63 @@ pub struct SlotFilter {
 ///   'min_staff_min': int | None,         # must be positive
 ///   'min_staff_max': int | None,         # must be positive and >=`min_staff_min`
 ///   'name_pat': Pattern | None,
+///   'fields': list[SlotField] | None,  # omit the other fields of each slot
 /// }) -> list[{
 ///   'start': datetime,
 ///   'end':   datetime,        # will always be >=`start`
 ///   'min_staff': int | None,  # will always be >=1 if not None
+///   'max_staff': int | None,  # will always be >=1 if not None
+///   'preferred_tasks': set[TaskId] | None,  # will never be empty if not None
 ///   'name': str | None,
 /// }];
 /// ```
-pub fn get_slots(filter: SlotFilter) -> Result<SlotMap<PySlot>> {
-    let SlotFilter {
-        ids,
-        starting_before,
-        starting_after,
-        ending_before,
-        ending_after,
-        min_staff_min,
-        min_staff_max,
-        name_pat,
-    } = filter;
-    let ids = ids.as_ref();
-    let name_pat = name_pat.as_ref();
-    Ok(SLOTS
-        .read()
+pub fn get_slots(filter: SlotFilter) -> Result<SlotMap<Projected<PySlot>>> {
+    filter.check()?;
+    read_or_busy(&SLOTS, "slots")?
         .values()
-        .filter(|slot| {
-            starting_before.is_none_or(|x| slot.start <= x)
-                && starting_after.is_none_or(|x| slot.start >= x)
-                && ending_before.is_none_or(|x| slot.end <= x)
-                && ending_after.is_none_or(|x| slot.end >= x)
-                && min_staff_min.is_none_or(|x| slot.min_staff.map_or(0, NonZeroUsize::get) >= x)
-                && min_staff_max.is_none_or(|x| slot.min_staff.map_or(0, NonZeroUsize::get) <= x)
-                // note that None => "do not filter", which is distinct from {} => "never"
-                && ids.is_none_or(|x| x.contains(&slot.id))
-                // use "^$" to match against empty names
-                && name_pat.is_none_or(|x| x.is_match(&slot.name))
+        .filter(|slot| filter.matches(slot))
+        .map(|slot| {
+            let (id, slot): (SlotId, PySlot) = slot.into();
+            Ok((id, project(slot, filter.fields.as_deref())?))
         })
+        .collect()
+}
+
+/// Returns a dictionary of all slots that are active at `at`
+/// (i.e. `start <= at < end`).
+///
+/// # Signature
+/// ```py
+/// def active_slots(at: datetime) -> dict[
+///   SlotId, {
+///     'start': datetime,
+///     'end':   datetime,        # will always be >=`start`
+///     'min_staff': int | None,  # will always be >=1 if not None
+///     'max_staff': int | None,  # will always be >=1 if not None
+///     'preferred_tasks': set[TaskId] | None,  # will never be empty if not None
+///     'name': str | None,
+///   }
+/// ];
+/// ```
+///
+/// # Examples
+/// ```py
+/// # which slots are active right now?
+/// proxy.active_slots(datetime.now(timezone.utc))
+/// ```
+pub fn active_slots(at: DateTime<Utc>) -> Result<SlotMap<PySlot>> {
+    Ok(read_or_busy(&SLOTS, "slots")?
+        .values()
+        .filter(|slot| slot.contains_instant(at))
         .map(From::from)
         .collect())
 }
 
 /// A filter for selecting [`Task`]s from the backend database.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TaskFilter {
     /// A whitelist of the exact [`Task::id`]s that should be included.
+    /// An empty set includes nothing, while [`None`] does not filter by ID.
     pub ids: Option<TaskSet>,
 
     /// A [`Pattern`] the [`Task::title`] must [match](Pattern::is_match).
@@ -812,11 +2366,37 @@ pub struct TaskFilter {
     /// A [`Pattern`] the [`Task::desc`] must [match](Pattern::is_match).
     pub desc_pat: Option<Pattern>,
 
-    /// The ealiest datetime the [`Task::deadline`] can be.
+    /// The ealiest datetime the task's [effective deadline](algo::effective_deadlines) can be.
     pub deadline_after: Option<DateTime<Utc>>,
 
-    /// The latest datetime the [`Task::deadline`] can be.
+    /// The latest datetime the task's [effective deadline](algo::effective_deadlines) can be.
     pub deadline_before: Option<DateTime<Utc>>,
+
+    /// The only fields of each [`PyTask`] to return. [`None`] for all of them.
+    #[serde(default)]
+    pub fields: Option<Vec<TaskField>>,
+}
+
+/// A field of [`PyTask`]. See [`TaskFilter::fields`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskField {
+    /// [`PyTask::title`]
+    Title,
+    /// [`PyTask::desc`]
+    Desc,
+    /// [`PyTask::deadline`]
+    Deadline,
+    /// [`PyTask::soft_deadline`]
+    SoftDeadline,
+    /// [`PyTask::hard_deadline`]
+    HardDeadline,
+    /// [`PyTask::deadline_offset`]
+    DeadlineOffset,
+    /// [`PyTask::awaiting`]
+    Awaiting,
+    /// [`PyTask::preferred_users`]
+    PreferredUsers,
 }
 
 /// Returns a dictionary of all current tasks, filtered by the parameters.
@@ -824,6 +2404,10 @@ pub struct TaskFilter {
 /// Each filter parameter is combined as "and" (tasks must satisfy *all* conditions to be included).
 /// Parameters that are [`None`] will be ignored.
 ///
+/// # Errors
+///
+/// Produces a [`fault::bad_request`] error if `deadline_after` is later than `deadline_before`.
+///
 /// # Signature
 /// ```py
 /// def get_tasks(filter: {
@@ -832,49 +2416,214 @@ pub struct TaskFilter {
 ///   'desc_pat':  Pattern | None,
 ///   'deadline_before': datetime | None,  # inclusive
 ///   'deadline_after':  datetime | None,  # inclusive
+///   'fields': list[TaskField] | None,  # omit the other fields of each task
 /// }) -> dict[
 ///   TaskId, {
 ///     'title': str,
 ///     'desc':  str | None,
-///     'deadline': datetime | None,
+///     'deadline': datetime | None,  # hard_deadline, or soft_deadline if none
+///     'soft_deadline': datetime | None,
+///     'hard_deadline': datetime | None,
+///     'deadline_offset': int | None,  # seconds
 ///     'awaiting': set[TaskId] | None,
+///     'preferred_users': dict[UserId, float] | None,
 ///   }
 /// ];
 /// ```
 ///
 /// **See also:** [`Pattern`]
-pub fn get_tasks(filter: TaskFilter) -> Result<TaskMap<PyTask>> {
+pub fn get_tasks(filter: TaskFilter) -> Result<TaskMap<Projected<PyTask>>> {
     let TaskFilter {
         ids,
         title_pat,
         desc_pat,
         deadline_before,
         deadline_after,
+        fields,
     } = filter;
+    check_range(
+        ("deadline_after", deadline_after),
+        ("deadline_before", deadline_before),
+    )?;
     let ids = ids.as_ref();
     let title_pat = title_pat.as_ref();
     let desc_pat = desc_pat.as_ref();
-    Ok(TASKS
-        .read()
+    let tasks = read_or_busy(&TASKS, "tasks")?;
+    let deadlines = algo::effective_deadlines(&tasks);
+    tasks
         .values()
         .filter(|task| {
+            let deadline = deadlines[&task.id].latest();
             // lack of deadline is equivalent to infinite deadline. there exists no inf<=datetime.
-            deadline_before.is_none_or(|x| task.deadline.is_some_and(|d| d <= x))
+            deadline_before.is_none_or(|x| deadline.is_some_and(|d| d <= x))
                 // lack of deadline is equivalent to infinite deadline. every no datetime<=inf.
-                && deadline_after.is_none_or(|x| task.deadline.is_none_or(|d| d >= x))
+                && deadline_after.is_none_or(|x| deadline.is_none_or(|d| d >= x))
                 // note that None => "do not filter", which is distinct from {} => "never"
                 && ids.is_none_or(|x| x.contains(&task.id))
                 && title_pat.is_none_or(|x| x.is_match(&task.title))
                 && desc_pat.is_none_or(|x| x.is_match(&task.desc))
         })
-        .map(From::from)
-        .collect())
+        .map(|task| {
+            let (id, task): (TaskId, PyTask) = task.into();
+            Ok((id, project(task, fields.as_deref())?))
+        })
+        .collect()
+}
+
+/// Returns the tasks that `task_id` is waiting on, directly or indirectly, as of `as_of`.
+///
+/// A dependency is considered satisfied once its deadline has passed.
+///
+/// # Errors
+///
+/// Produces a [`fault::not_found`] error if the task, or any task it depends on, does not exist.
+///
+/// # Signature
+/// ```py
+/// def blocking_tasks(task_id: TaskId, as_of: datetime) -> set[TaskId];
+/// ```
+pub fn blocking_tasks((task_id, as_of): (TaskId, DateTime<Utc>)) -> Result<TaskSet> {
+    algo::blocking_tasks(&TASKS.read(), task_id, as_of).map_err(|e| match e {
+        SchedulingError::NonExistentTask(_) => fault::not_found(e),
+        _ => fault::internal(e),
+    })
+}
+
+/// Returns the tasks that could still be worked during slot `slot_id`: those due after it starts,
+/// whose dependencies could all be completed in earlier slots.
+///
+/// See [`algo::schedulable_tasks`].
+///
+/// # Errors
+///
+/// Produces a [`fault::not_found`] error if the slot does not exist.
+///
+/// # Signature
+/// ```py
+/// def schedulable_tasks_for_slot(slot_id: SlotId) -> set[TaskId];
+/// ```
+pub fn schedulable_tasks_for_slot(slot_id: SlotId) -> Result<TaskSet> {
+    let slots = SLOTS.read();
+    let slot = slots
+        .get(&slot_id)
+        .ok_or_else(|| fault::not_found(format_args!("slot {slot_id} does not exist")))?;
+    Ok(algo::schedulable_tasks(
+        &slots,
+        &TASKS.read(),
+        &slot.interval,
+    ))
+}
+
+/// Returns the shortest chain of dependencies from `from` to `to`, explaining why `from` is blocked by `to`.
+///
+/// Each task in the chain depends on the next. [`None`] if `from` does not depend on `to`, directly or indirectly.
+/// See [`algo::dependency_path`].
+///
+/// # Errors
+///
+/// Produces a [`fault::not_found`] error if either task does not exist.
+///
+/// # Signature
+/// ```py
+/// def dependency_path(from_task: TaskId, to_task: TaskId) -> list[TaskId] | None;
+/// ```
+pub fn dependency_path((from, to): (TaskId, TaskId)) -> Result<Option<Vec<TaskId>>> {
+    let tasks = TASKS.read();
+    if let Some(id) = [from, to].into_iter().find(|id| !tasks.contains_key(id)) {
+        return Err(fault::not_found(format_args!("task {id} does not exist")));
+    }
+    Ok(algo::dependency_path(&tasks, from, to))
+}
+
+/// The result of [`dep_graph_edges`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepGraphEdges {
+    /// Every `(dependent, dependency)` pair, sorted by ID.
+    pub edges: Vec<(TaskId, TaskId)>,
+
+    /// Tasks that do not depend on any other task.
+    pub roots: TaskSet,
+
+    /// Tasks that no other task depends on.
+    pub leaves: TaskSet,
+}
+
+/// Produces a [`fault::unprocessable`] error if a task depends on a task that does not exist,
+/// as [`algo::dep_graph`] requires.
+fn check_deps_exist(tasks: &TaskMap) -> Result<()> {
+    for task in tasks.values() {
+        if let Some(dep) = task.deps.iter().find(|dep| !tasks.contains_key(dep)) {
+            return Err(fault::unprocessable(format_args!(
+                "task {} depends on task {dep}, which does not exist",
+                task.id
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the task dependency graph, for drawing it.
+///
+/// A task with no dependencies and no dependents is both a root and a leaf.
+///
+/// # Errors
+///
+/// Produces a [`fault::unprocessable`] error if a task depends on a task that does not exist,
+/// or if the dependencies are cyclic.
+///
+/// # Signature
+/// ```py
+/// def dep_graph_edges(_: {}) -> {
+///   'edges': list[tuple[TaskId, TaskId]],  # (dependent, dependency)
+///   'roots': set[TaskId],
+///   'leaves': set[TaskId],
+/// };
+/// ```
+pub fn dep_graph_edges((): ()) -> Result<DepGraphEdges> {
+    let tasks = TASKS.read();
+    check_deps_exist(&tasks)?;
+    let graph = algo::dep_graph(&tasks).map_err(fault::unprocessable)?;
+    let mut edges = algo::dep_edges(&graph).collect::<Vec<_>>();
+    edges.sort_by_key(|(dependent, dependency)| (dependent.0, dependency.0));
+    let mut roots = TaskSet::from_iter(tasks.keys().copied());
+    let mut leaves = roots.clone();
+    for (dependent, dependency) in &edges {
+        roots.remove(dependent);
+        leaves.remove(dependency);
+    }
+    Ok(DepGraphEdges {
+        edges,
+        roots,
+        leaves,
+    })
+}
+
+/// Returns the longest chain of dependent tasks (the critical path), from the first to be done to the last.
+///
+/// Tasks do not have durations yet, so each task counts equally and the path is the one with the most tasks.
+/// See [`algo::critical_path`].
+///
+/// # Errors
+///
+/// Produces a [`fault::unprocessable`] error if a task depends on a task that does not exist,
+/// or if the dependencies are cyclic.
+///
+/// # Signature
+/// ```py
+/// def critical_path(_: {}) -> list[TaskId];
+/// ```
+pub fn critical_path((): ()) -> Result<Vec<TaskId>> {
+    let tasks = TASKS.read();
+    check_deps_exist(&tasks)?;
+    let graph = algo::dep_graph(&tasks).map_err(fault::unprocessable)?;
+    Ok(algo::critical_path(&graph, |_| 1usize))
 }
 
 /// A filter for selecting [`User`]s from the backend database.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserFilter {
     /// A whitelist of the exact [`User::id`]s that should be included.
+    /// An empty list includes nothing, while [`None`] does not filter by ID.
     pub ids: Option<Vec<UserId>>,
 
     /// A [`Pattern`] the [`User::name`] must [match](Pattern::is_match).
@@ -899,8 +2648,7 @@ pub fn get_users(filter: UserFilter) -> Result<UserMap<PyUser>> {
     let UserFilter { ids, name_pat } = filter;
     let ids = ids.as_ref();
     let name_pat = name_pat.as_ref();
-    Ok(USERS
-        .read()
+    Ok(read_or_busy(&USERS, "users")?
         .values()
         .filter(|user| {
             ids.is_none_or(|x| x.contains(&user.id))
@@ -910,6 +2658,41 @@ pub fn get_users(filter: UserFilter) -> Result<UserMap<PyUser>> {
         .collect())
 }
 
+/// What became of a [`Delta`] once applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeltaOutcome {
+    /// Every requested change was made.
+    Applied,
+
+    /// Some keys to be removed or replaced were missing from the target.
+    /// Those keys are left behind in the delta.
+    Partial,
+}
+
+impl DeltaOutcome {
+    /// [`Self::Partial`] if either outcome is.
+    #[inline]
+    #[must_use]
+    pub fn and(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Applied, Self::Applied) => Self::Applied,
+            _ => Self::Partial,
+        }
+    }
+}
+
+/// A requested change to a value.
+///
+/// Applying a delta consumes the parts of it that succeeded,
+/// so anything left over afterwards is what could not be applied.
+pub trait Delta {
+    /// The type of value the delta changes.
+    type Target;
+
+    /// Apply the delta to `target`.
+    fn apply(&mut self, target: &mut Self::Target) -> DeltaOutcome;
+}
+
 /// A change to a set ([`HashSet`](std::collections::HashSet) or [`BTreeSet`](std::collections::BTreeSet)).
 #[derive(Debug, Clone, Deserialize)]
 pub struct KeySetDelta<K: Eq + std::hash::Hash> {
@@ -931,30 +2714,16 @@ impl<K: Eq + std::hash::Hash> Default for KeySetDelta<K> {
     }
 }
 
-impl<K: Eq + std::hash::Hash> KeySetDelta<K> {
-    fn apply(&mut self, target: &mut FxHashSet<K>) {
+impl<K: Eq + std::hash::Hash> Delta for KeySetDelta<K> {
+    type Target = FxHashSet<K>;
+
+    fn apply(&mut self, target: &mut Self::Target) -> DeltaOutcome {
         target.retain(|k| !self.delete.remove(k));
         target.extend(std::mem::take(&mut self.create));
-    }
-}
-
-/// A change to a collection that cannot create new elements, only remove or modify.
-#[derive(Debug, Clone, Deserialize)]
-pub struct NoGrowSetDelta<K: Eq + std::hash::Hash, V> {
-    /// Key(s) to be removed from the collection.
-    #[serde(default = "Default::default")]
-    pub delete: FxHashSet<K>,
-
-    /// Key(s) in the collection to be replaced with new value(s).
-    #[serde(default = "Default::default")]
-    pub update: FxHashMap<K, V>,
-}
-
-impl<K: Eq + std::hash::Hash, V> Default for NoGrowSetDelta<K, V> {
-    fn default() -> Self {
-        Self {
-            delete: Default::default(),
-            update: Default::default(),
+        if self.delete.is_empty() {
+            DeltaOutcome::Applied
+        } else {
+            DeltaOutcome::Partial
         }
     }
 }
@@ -985,13 +2754,28 @@ impl<K: Eq + std::hash::Hash, V, U> Default for SetDelta<K, V, U> {
     }
 }
 
-impl<K: Eq + std::hash::Hash, V> SetDelta<K, V, (K, V)> {
-    fn apply(&mut self, target: &mut FxHashMap<K, V>) {
+impl<K: Eq + std::hash::Hash, V, U> SetDelta<K, V, U> {
+    /// Whether every key to be removed or replaced has been.
+    #[inline]
+    fn outcome(&self) -> DeltaOutcome {
+        if self.delete.is_empty() && self.update.is_empty() {
+            DeltaOutcome::Applied
+        } else {
+            DeltaOutcome::Partial
+        }
+    }
+}
+
+impl<K: Eq + std::hash::Hash, V> Delta for SetDelta<K, V, (K, V)> {
+    type Target = FxHashMap<K, V>;
+
+    fn apply(&mut self, target: &mut Self::Target) -> DeltaOutcome {
         target.retain(|k, _| !self.delete.remove(k));
         for (k, v) in target.iter_mut() {
             self.update.remove(k).apply(v);
         }
         target.extend(std::mem::take(&mut self.create));
+        self.outcome()
     }
 }
 
@@ -999,24 +2783,45 @@ impl<K: Eq + std::hash::Hash, V> SetDelta<K, V, (K, V)> {
 /// Example: pushing to a [`Vec`].
 pub type AutoIdSetDelta<K, V> = SetDelta<K, V, V>;
 
+impl<V> AutoIdSetDelta<usize, V> {
+    /// Apply to a list, where keys are indices from before any deletions.
+    fn apply_indexed<A: smallvec::Array<Item = V>>(
+        &mut self,
+        target: &mut SmallVec<A>,
+    ) -> DeltaOutcome {
+        let mut next_index = 0;
+        target.retain(|v| {
+            let i = next_index;
+            next_index += 1;
+            if self.delete.remove(&i) {
+                false
+            } else {
+                // update has to be included in retain because
+                // indices will change when removals happen
+                if let Some(replacement) = self.update.remove(&i) {
+                    *v = replacement;
+                }
+                true
+            }
+        });
+        target.extend(std::mem::take(&mut self.create));
+        self.outcome()
+    }
+}
+
 /// [`None`] to ignore and keep existing value.
 /// [`Some`] to replace the value.
 pub type Update<T> = Option<T>;
 
-trait ApplyUpdate {
-    type Target;
-
-    fn apply(self, target: &mut Self::Target);
-}
-
-impl<T> ApplyUpdate for Update<T> {
+impl<T> Delta for Update<T> {
     type Target = T;
 
     #[inline]
-    fn apply(self, target: &mut T) {
-        if let Some(new_value) = self {
+    fn apply(&mut self, target: &mut T) -> DeltaOutcome {
+        if let Some(new_value) = self.take() {
             *target = new_value;
         }
+        DeltaOutcome::Applied
     }
 }
 
@@ -1027,6 +2832,10 @@ pub struct RuleDelta {
     #[serde(default)]
     pub include: AutoIdSetDelta<usize, TimeInterval>,
 
+    /// See [`Rule::exclude`]
+    #[serde(default)]
+    pub exclude: AutoIdSetDelta<usize, TimeInterval>,
+
     /// See [`Rule::rep`]
     #[serde(default)]
     pub rep: Update<Option<Repetition>>,
@@ -1034,6 +2843,55 @@ pub struct RuleDelta {
     /// See [`Rule::pref`]
     #[serde(default)]
     pub pref: Update<Preference>,
+
+    /// See [`Rule::set_at`]
+    #[serde(default)]
+    pub set_at: Update<Option<DateTime<Utc>>>,
+}
+
+impl Delta for RuleDelta {
+    type Target = Rule;
+
+    fn apply(&mut self, target: &mut Rule) -> DeltaOutcome {
+        self.include
+            .apply_indexed(&mut target.include)
+            .and(self.exclude.apply_indexed(&mut target.exclude))
+            .and(self.rep.apply(&mut target.rep))
+            .and(self.pref.apply(&mut target.pref))
+            .and(self.set_at.apply(&mut target.set_at))
+    }
+}
+
+impl Delta for SetDelta<RuleId, RuleDelta, PyRule> {
+    type Target = RuleMap;
+
+    /// Created rules are given new IDs.
+    /// Those with an out-of-range preference are left behind in [`SetDelta::create`].
+    fn apply(&mut self, target: &mut RuleMap) -> DeltaOutcome {
+        target.retain(|k, _| !self.delete.remove(k));
+        let mut outcome = DeltaOutcome::Applied;
+        for (k, rule) in target.iter_mut() {
+            if let Some(mut delta) = self.update.remove(k) {
+                outcome = outcome.and(delta.apply(rule));
+            }
+        }
+        let (create, invalid) = std::mem::take(&mut self.create)
+            .into_iter()
+            .partition::<Vec<_>, _>(|rule| Preference::try_from(rule.preference).is_ok());
+        self.create = invalid;
+        if !create.is_empty() {
+            let ids = RuleId::take(create.len() as u128);
+            target.extend(
+                ids.zip(create)
+                    .filter_map(|rule| Rule::try_from(rule).ok())
+                    .map(|rule| (rule.id, rule)),
+            );
+        }
+        if !self.create.is_empty() {
+            outcome = DeltaOutcome::Partial;
+        }
+        self.outcome().and(outcome)
+    }
 }
 
 /// A mutation request for a [`Slot`].
@@ -1047,30 +2905,114 @@ pub struct SlotDelta {
     #[serde(default)]
     pub min_staff: Update<Option<NonZeroUsize>>,
 
+    /// See [`Slot::max_staff`]
+    #[serde(default)]
+    pub max_staff: Update<Option<NonZeroUsize>>,
+
+    /// See [`Slot::preferred_tasks`]
+    #[serde(default)]
+    pub preferred_tasks: Update<TaskSet>,
+
     /// See [`Slot::name`]
     #[serde(default)]
     pub name: Update<String>,
 }
 
+impl Delta for SlotDelta {
+    type Target = Slot;
+
+    fn apply(&mut self, target: &mut Slot) -> DeltaOutcome {
+        self.interval
+            .apply(&mut target.interval)
+            .and(self.min_staff.apply(&mut target.min_staff))
+            .and(self.max_staff.apply(&mut target.max_staff))
+            .and(self.preferred_tasks.apply(&mut target.preferred_tasks))
+            .and(self.name.apply(&mut target.name))
+    }
+}
+
 /// Mutate [`Slot`]s.
 ///
 /// Returns a collection of all failed changes.
 /// If all requested changes were successful, the list will be empty.
-pub fn mut_slots(delta: SlotMap<SlotDelta>) -> Result<SlotSet> {
+///
+/// # Errors
+///
+/// Produces a [`fault::unprocessable`] error if any new interval is not aligned to the
+/// [slot granularity](SLOT_GRANULARITY) when it rejects unaligned times. No slots are changed if any fail.
+///
+/// Otherwise, new intervals are rounded to the slot granularity if one is set.
+pub fn mut_slots(mut delta: SlotMap<SlotDelta>) -> Result<SlotSet> {
+    if delta.is_empty() {
+        return Ok(SlotSet::default());
+    }
+    for (slot_id, delta) in &mut delta {
+        align_all(&mut delta.interval)
+            .map_err(|e| fault::unprocessable(format_args!("slot {slot_id}: {e}")))?;
+    }
     let mut slots = SLOTS.write();
-    Ok(delta
+    let failed = delta
         .into_iter()
-        .filter_map(|(slot_id, delta)| {
-            if let Some(slot) = slots.get_mut(&slot_id) {
-                delta.interval.apply(&mut slot.interval);
-                delta.min_staff.apply(&mut slot.min_staff);
-                delta.name.apply(&mut slot.name);
+        .filter_map(|(slot_id, mut delta)| match slots.get_mut(&slot_id) {
+            Some(slot) => {
+                delta.apply(slot);
                 None
-            } else {
-                Some(slot_id)
             }
+            None => Some(slot_id),
         })
-        .collect())
+        .collect();
+    notify_mutation(Mutation::SLOTS);
+    Ok(failed)
+}
+
+/// Apply the same `delta` to every [`Slot`] matching `filter`.
+///
+/// Returns the IDs of the slots that were changed.
+///
+/// # Errors
+///
+/// Produces a [`fault::bad_request`] error if any pair of range parameters in `filter` is inverted,
+/// or if `filter` sets no conditions (and so would change every slot) without `confirm_all`.
+/// Produces a [`fault::unprocessable`] error if `delta`'s interval is not aligned to the
+/// [slot granularity](SLOT_GRANULARITY) when it rejects unaligned times.
+///
+/// Otherwise, `delta`'s interval is rounded to the slot granularity if one is set.
+///
+/// # Signature
+/// ```py
+/// def mut_slots_where(
+///   filter: SlotFilter,  # see get_slots
+///   delta: SlotDelta,    # see mut_slots
+///   confirm_all: bool,   # required to change every slot
+/// ) -> set[SlotId];
+/// ```
+///
+/// # Examples
+/// ```py
+/// # every slot ending by noon needs two staff
+/// proxy.mut_slots_where({'ending_before': noon}, {'min_staff': 2}, False)
+/// ```
+pub fn mut_slots_where(
+    (filter, mut delta, confirm_all): (SlotFilter, SlotDelta, bool),
+) -> Result<SlotSet> {
+    filter.check()?;
+    if filter.is_unconditional() && !confirm_all {
+        return Err(fault::bad_request(
+            "filter matches every slot; pass `confirm_all` to change them all",
+        ));
+    }
+    align_all(&mut delta.interval).map_err(fault::unprocessable)?;
+    let mut slots = SLOTS.write();
+    let changed = slots
+        .values_mut()
+        .filter(|slot| filter.matches(slot))
+        .map(|slot| {
+            _ = delta.clone().apply(slot);
+            slot.id
+        })
+        .collect();
+    notify_mutation(Mutation::SLOTS);
+    Ok(changed)
 }
 
 /// A mutation request for a [`Task`].
@@ -1088,36 +3030,126 @@ pub struct TaskDelta {
     #[serde(default)]
     pub skills: SetDelta<SkillId, ProficiencyReq>,
 
-    /// See [`Task::deadline`]
+    /// Sets both deadlines; see [`Task::set_deadline`].
+    /// Applied before [`Self::soft_deadline`] and [`Self::hard_deadline`].
     #[serde(default)]
     pub deadline: Update<Option<DateTime<Utc>>>,
 
+    /// See [`Task::soft_deadline`]
+    #[serde(default)]
+    pub soft_deadline: Update<Option<DateTime<Utc>>>,
+
+    /// See [`Task::hard_deadline`]
+    #[serde(default)]
+    pub hard_deadline: Update<Option<DateTime<Utc>>>,
+
+    /// See [`Task::deadline_offset`]
+    #[serde(default, deserialize_with = "seconds::update::deserialize")]
+    pub deadline_offset: Update<Option<TimeDelta>>,
+
     /// See [`Task::deps`]
     #[serde(default)]
     pub deps: KeySetDelta<TaskId>,
+
+    /// See [`Task::preferred_users`]
+    ///
+    /// Out-of-range values are [clamped](Preference::saturate).
+    #[serde(default)]
+    pub preferred_users: SetDelta<UserId, f32>,
+}
+
+impl TaskDelta {
+    /// One warning for each of [`TaskDelta::preferred_users`] that is out of range,
+    /// naming task `id` as the item at `index` of the request.
+    fn clamp_warnings(&self, index: usize, id: TaskId) -> impl Iterator<Item = fault::BatchFault> {
+        let SetDelta { create, update, .. } = &self.preferred_users;
+        let created = create.iter().map(|(user, pref)| (user, pref));
+        clamp_warnings(created.chain(update), index, id)
+    }
+
+    /// Take [`TaskDelta::preferred_users`], with every value [clamped](Preference::clamped).
+    fn clamped_preferred_users(&mut self) -> SetDelta<UserId, Preference> {
+        let SetDelta {
+            delete,
+            create,
+            update,
+        } = std::mem::take(&mut self.preferred_users);
+        let clamp = |(user, pref)| (user, Preference::clamped(pref).0);
+        SetDelta {
+            delete,
+            create: create.into_iter().map(clamp).collect(),
+            update: update.into_iter().map(clamp).collect(),
+        }
+    }
+}
+
+impl Delta for TaskDelta {
+    type Target = Task;
+
+    fn apply(&mut self, target: &mut Task) -> DeltaOutcome {
+        if let Some(deadline) = self.deadline.take() {
+            target.set_deadline(deadline);
+        }
+        self.title
+            .apply(&mut target.title)
+            .and(self.desc.apply(&mut target.desc))
+            .and(self.skills.apply(&mut target.skills))
+            .and(self.soft_deadline.apply(&mut target.soft_deadline))
+            .and(self.hard_deadline.apply(&mut target.hard_deadline))
+            .and(self.deadline_offset.apply(&mut target.deadline_offset))
+            .and(self.deps.apply(&mut target.deps))
+            .and(self.clamped_preferred_users().apply(&mut target.preferred_users))
+    }
 }
 
 /// Mutate [`Task`]s.
 ///
 /// Returns a collection of all failed changes.
 /// If all requested changes were successful, the list will be empty.
-pub fn mut_tasks(delta: TaskMap<TaskDelta>) -> Result<TaskSet> {
+///
+/// Finite preferences towards users outside of -1 to +1 are [clamped](Preference::saturate) rather than rejected,
+/// with a warning giving the task's ID.
+///
+/// # Errors
+///
+/// Produces a [`fault::conflict`] error naming the cycle if the changed dependencies
+/// would form one. No tasks are changed in that case.
+pub fn mut_tasks(delta: TaskMap<TaskDelta>) -> Result<Warned<TaskSet>> {
+    if delta.is_empty() {
+        return Ok(Warned::default());
+    }
     let mut tasks = TASKS.write();
-    Ok(delta
-        .into_iter()
-        .filter_map(|(task_id, mut delta)| {
-            if let Some(task) = tasks.get_mut(&task_id) {
-                delta.title.apply(&mut task.title);
-                delta.desc.apply(&mut task.desc);
-                delta.skills.apply(&mut task.skills);
-                delta.deadline.apply(&mut task.deadline);
-                delta.deps.apply(&mut task.deps);
-                None
-            } else {
-                Some(task_id)
+    let mut failed = TaskSet::default();
+    let mut warnings = Vec::new();
+    let mut changed = Vec::with_capacity(delta.len());
+    for (task_id, mut delta) in delta {
+        match tasks.get(&task_id) {
+            Some(task) => {
+                warnings.extend(delta.clamp_warnings(0, task_id));
+                let mut task = task.clone();
+                delta.apply(&mut task);
+                changed.push(task);
             }
-        })
-        .collect())
+            None => {
+                failed.insert(task_id);
+            }
+        }
+    }
+    let originals = changed
+        .into_iter()
+        .filter_map(|task| tasks.insert(task.id, task))
+        .collect::<Vec<_>>();
+    // only trace the cycle if there is one
+    if !algo::is_dag(&tasks) {
+        let cycle = algo::find_cycle(&tasks).unwrap_or_default();
+        tasks.extend(originals.into_iter().map(|task| (task.id, task)));
+        return Err(fault::conflict(SchedulingError::WouldCycle(cycle)));
+    }
+    notify_mutation(Mutation::TASKS);
+    Ok(Warned {
+        value: failed,
+        warnings,
+    })
 }
 
 /// A mutation request for a [`User`].
@@ -1127,15 +3159,15 @@ pub struct UserDelta {
     #[serde(default)]
     pub name: Update<String>,
 
-    /// Cannot grow. Use [`add_rules`] to create new rules.
-    ///
-    /// **Reasoning:**
-    /// Newly created [`Rule`]s would become inaccessible because
-    /// successful items do not have their IDs returned by `mut_` methods.
+    /// The IDs of created [`Rule`]s are returned in [`MutUsersResult::created`].
     ///
     /// See [`User::availability`]
     #[serde(default)]
-    pub availability: NoGrowSetDelta<RuleId, RuleDelta>,
+    pub availability: SetDelta<RuleId, RuleDelta, PyRule>,
+
+    /// See [`User::default_availability`]
+    #[serde(default)]
+    pub default_availability: Update<Option<Preference>>,
 
     /// See [`User::user_prefs`]
     #[serde(default)]
@@ -1143,65 +3175,130 @@ pub struct UserDelta {
 
     /// See [`User::skills`]
     #[serde(default)]
-    pub skills: SetDelta<SkillId, Proficiency>,
+    pub skills: SetDelta<SkillId, UserSkill>,
+}
+
+impl Delta for UserDelta {
+    type Target = User;
+
+    /// Rule order is kept in sync and the user is [compacted](User::compact) afterward.
+    fn apply(&mut self, target: &mut User) -> DeltaOutcome {
+        let outcome = self
+            .name
+            .apply(&mut target.name)
+            .and(self.availability.apply(&mut target.availability))
+            .and(
+                self.default_availability
+                    .apply(&mut target.default_availability),
+            )
+            .and(self.user_prefs.apply(&mut target.user_prefs))
+            .and(self.skills.apply(&mut target.skills));
+        target.sync_rule_order();
+        target.compact();
+        outcome
+    }
+}
+
+/// The result of [`mut_users`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MutUsersResult {
+    /// The [`Rule`]s that failed to be deleted or updated, by user.
+    /// Users that do not exist are included with all of their requested rule changes.
+    ///
+    /// If all requested changes were successful, this will be empty.
+    pub failed: UserMap<RuleSet>,
+
+    /// The IDs of the newly created [`Rule`]s, by user,
+    /// in the order they were provided.
+    pub created: UserMap<Vec<RuleId>>,
 }
 
 /// Mutate [`User`]s.
 ///
-/// Returns a collection of all failed changes.
-/// If all requested changes were successful, the list will be empty.
-pub fn mut_users(delta: UserMap<UserDelta>) -> Result<UserMap<RuleSet>> {
+/// Returns all failed changes along with the IDs of any newly created rules.
+/// Created rules with an out-of-range preference are [clamped](Preference::clamped)
+/// with a warning, indexed by the rule's position in that user's `create` list.
+///
+/// # Errors
+///
+/// Produces a [`fault::unprocessable`] error if any created or updated rule would
+/// [repeat too many times](Repetition::exceeds_max_occurrences)
+/// or has an interval not aligned to the [slot granularity](SLOT_GRANULARITY) when it rejects unaligned times.
+/// No users are changed if any fail.
+///
+/// Otherwise, created and updated intervals are rounded to the slot granularity if one is set.
+pub fn mut_users(mut delta: UserMap<UserDelta>) -> Result<Warned<MutUsersResult>> {
+    if delta.is_empty() {
+        return Ok(Warned::default());
+    }
+    let mut warnings = Vec::new();
+    for (user_id, delta) in &mut delta {
+        for (index, rule) in delta.availability.create.iter_mut().enumerate() {
+            let (pref, clamped) = Preference::clamped(rule.preference);
+            if let Some(e) = clamped {
+                let reason = format!("{e}; clamped to {}", pref.0);
+                warnings.push(fault::BatchFault::new(index, Some(*user_id), reason));
+            }
+            rule.preference = pref.0;
+        }
+        let created = delta
+            .availability
+            .create
+            .iter_mut()
+            .flat_map(|rule| rule.include.iter_mut().chain(rule.exclude.iter_mut()));
+        let updated = delta.availability.update.values_mut().flat_map(|rule| {
+            (rule.include.create.iter_mut())
+                .chain(rule.include.update.values_mut())
+                .chain(rule.exclude.create.iter_mut())
+                .chain(rule.exclude.update.values_mut())
+        });
+        align_all(created.chain(updated))
+            .map_err(|e| fault::unprocessable(format_args!("user {user_id}: {e}")))?;
+        let created = delta
+            .availability
+            .create
+            .iter()
+            .map(|rule| rule.repeat.clone().map(Repetition::from));
+        let updated = delta
+            .availability
+            .update
+            .values()
+            .filter_map(|rule| rule.rep.flatten());
+        for rep in created.flatten().chain(updated) {
+            check_occurrences(Some(&rep))
+                .map_err(|reason| fault::unprocessable(format_args!("user {user_id}: {reason}")))?;
+        }
+    }
     let mut users = USERS.write();
-    Ok(delta
-        .into_iter()
-        .filter_map(|(user_id, mut delta)| {
-            if let Some(user) = users.get_mut(&user_id) {
-                delta.name.apply(&mut user.name);
-                {
-                    let NoGrowSetDelta { delete, update } = &mut delta.availability;
-                    user.availability.retain(|k, _| !delete.remove(k));
-                    for (k, rule) in &mut user.availability {
-                        if let Some(mut delta) = update.remove(k) {
-                            {
-                                let mut it = 0..;
-                                rule.include.retain(|v| {
-                                    let i = it.next().unwrap();
-                                    if delta.include.delete.remove(&i) {
-                                        false
-                                    } else {
-                                        // update has to be included in retain because
-                                        // indices will change when removals happen
-                                        if let Some(replacement) = delta.include.update.remove(&i) {
-                                            *v = replacement;
-                                        }
-                                        true
-                                    }
-                                });
-                                rule.include.extend(delta.include.create);
-                            }
-                            delta.rep.apply(&mut rule.rep);
-                            delta.pref.apply(&mut rule.pref);
-                        }
-                    }
-                }
-                delta.user_prefs.apply(&mut user.user_prefs);
-                delta.skills.apply(&mut user.skills);
-
-                if delta.availability.delete.is_empty() && delta.availability.update.is_empty() {
-                    return None;
-                }
+    let mut result = MutUsersResult::default();
+    for (user_id, mut delta) in delta {
+        if let Some(user) = users.get_mut(&user_id) {
+            let n_created = delta.availability.create.len();
+            delta.apply(user);
+            if n_created > 0 {
+                // new rules are appended to the rule order, in the order they were created
+                let created = &user.rule_order[user.rule_order.len() - n_created..];
+                result.created.insert(user_id, created.to_vec());
             }
-            Some((
-                user_id,
-                delta
-                    .availability
-                    .delete
-                    .into_iter()
-                    .chain(delta.availability.update.into_keys())
-                    .collect(),
-            ))
-        })
-        .collect())
+            if delta.availability.outcome() == DeltaOutcome::Applied {
+                continue;
+            }
+        }
+        result.failed.insert(
+            user_id,
+            delta
+                .availability
+                .delete
+                .into_iter()
+                .chain(delta.availability.update.into_keys())
+                .collect(),
+        );
+    }
+    notify_mutation(Mutation::USERS);
+    Ok(Warned {
+        value: result,
+        warnings,
+    })
 }
 
 /// Removes one or more rules from one or more users.
@@ -1216,17 +3313,23 @@ pub fn mut_users(delta: UserMap<UserDelta>) -> Result<UserMap<RuleSet>> {
 /// def pop_rules(to_pop: dict[UserId, set[RuleId]]) -> dict[UserId, set[RuleId]];
 /// ```
 pub fn pop_rules(to_pop: UserMap<RuleSet>) -> Result<UserMap<RuleSet>> {
+    if to_pop.is_empty() {
+        return Ok(UserMap::default());
+    }
     let mut users = USERS.write();
-    Ok(to_pop
+    let failed = to_pop
         .into_iter()
         .map(|(user, mut rules)| {
             if let Some(user) = users.get_mut(&user) {
                 user.availability.retain(|id, _| !rules.remove(id));
+                user.sync_rule_order();
             }
             (user, rules)
         })
         .filter(|(_user, rules)| !rules.is_empty())
-        .collect())
+        .collect();
+    notify_mutation(Mutation::USERS);
+    Ok(failed)
 }
 
 /// Removes slots by ID.
@@ -1241,7 +3344,12 @@ pub fn pop_rules(to_pop: UserMap<RuleSet>) -> Result<UserMap<RuleSet>> {
 /// def pop_slots(to_pop: set[SlotId]) -> set[SlotId];
 /// ```
 pub fn pop_slots(mut to_pop: SlotSet) -> Result<SlotSet> {
-    SLOTS.write().retain(|id, _| !to_pop.remove(id));
+    if to_pop.is_empty() {
+        return Ok(SlotSet::default());
+    }
+    let mut slots = SLOTS.write();
+    slots.retain(|id, _| !to_pop.remove(id));
+    notify_mutation(Mutation::SLOTS);
     Ok(to_pop)
 }
 
@@ -1257,7 +3365,12 @@ pub fn pop_slots(mut to_pop: SlotSet) -> Result<SlotSet> {
 /// def pop_tasks(to_pop: set[TaskId]) -> set[TaskId];
 /// ```
 pub fn pop_tasks(mut to_pop: TaskSet) -> Result<TaskSet> {
-    TASKS.write().retain(|id, _| !to_pop.remove(id));
+    if to_pop.is_empty() {
+        return Ok(TaskSet::default());
+    }
+    let mut tasks = TASKS.write();
+    tasks.retain(|id, _| !to_pop.remove(id));
+    notify_mutation(Mutation::TASKS);
     Ok(to_pop)
 }
 
@@ -1266,6 +3379,8 @@ pub fn pop_tasks(mut to_pop: TaskSet) -> Result<TaskSet> {
 /// Returns a list of any IDs that failed to be removed (ex: user with that ID did not exist).
 /// If all requested removals were successful, the list will be empty.
 ///
+/// Removed users are also removed from every task's [preferred users](Task::preferred_users).
+///
 /// Argument must be an array, even if only adding one.
 ///
 /// # Signature
@@ -1273,7 +3388,28 @@ pub fn pop_tasks(mut to_pop: TaskSet) -> Result<TaskSet> {
 /// def pop_users(to_pop: set[UserId]) -> set[UserId];
 /// ```
 pub fn pop_users(mut to_pop: UserSet) -> Result<UserSet> {
-    USERS.write().retain(|id, _| !to_pop.remove(id));
+    if to_pop.is_empty() {
+        return Ok(UserSet::default());
+    }
+    let mut tasks = TASKS.write();
+    let mut users = USERS.write();
+    let mut popped = UserSet::default();
+    users.retain(|id, _| {
+        let pop = to_pop.remove(id);
+        if pop {
+            popped.insert(*id);
+        }
+        !pop
+    });
+    let mut changed = Mutation::USERS;
+    for task in tasks.values_mut() {
+        let before = task.preferred_users.len();
+        task.preferred_users.retain(|id, _| !popped.contains(id));
+        if task.preferred_users.len() != before {
+            changed |= Mutation::TASKS;
+        }
+    }
+    notify_mutation(changed);
     Ok(to_pop)
 }
 
@@ -1282,7 +3418,7 @@ pub fn save_slots(path: PathBuf) -> Result<()> {
     csv::WriterBuilder::default()
         .from_path(path)
         .and_then(|mut w| w.serialize(SLOTS.read().values().collect::<Vec<_>>()))
-        .map_err(|e| Fault::new(500, e.to_string()))
+        .map_err(fault::file)
 }
 
 /// Save all current [`Task`] data to a file stored at `path`.
@@ -1290,7 +3426,7 @@ pub fn save_tasks(path: PathBuf) -> Result<()> {
     csv::WriterBuilder::default()
         .from_path(path)
         .and_then(|mut w| w.serialize(TASKS.read().values().collect::<Vec<_>>()))
-        .map_err(|e| Fault::new(500, e.to_string()))
+        .map_err(fault::file)
 }
 
 /// Save all current [`User`] data to a file stored at `path`.
@@ -1300,50 +3436,228 @@ pub fn save_users(path: PathBuf) -> Result<()> {
     csv::WriterBuilder::default()
         .from_path(path)
         .and_then(|mut w| w.serialize(USERS.read().values().collect::<Vec<_>>()))
-        .map_err(|e| Fault::new(500, e.to_string()))
+        .map_err(fault::file)
+}
+
+/// Borrowed form of [`Dataset`], for writing without copying.
+#[derive(Serialize)]
+struct DatasetRef<'a> {
+    slots: &'a SlotMap,
+    tasks: &'a TaskMap,
+    users: &'a UserMap,
+    pins: &'a Pins,
+}
+
+/// Everything the server stores, as written by [`export_all`] and read by [`import_all`].
+#[derive(Debug, Deserialize)]
+pub struct Dataset {
+    /// Every [`Slot`], keyed by ID
+    pub slots: SlotMap,
+
+    /// Every [`Task`], keyed by ID
+    pub tasks: TaskMap,
+
+    /// Every [`User`], keyed by ID, with their [`Rule`]s inline
+    pub users: UserMap,
+
+    /// See [`Pins`]
+    #[serde(default)]
+    pub pins: Pins,
+}
+
+/// Save all current data to a single JSON file at `path`, for backup or migration.
+///
+/// Skills are only stored as IDs on users and tasks, so they are included there.
+///
+/// # Signature
+/// ```py
+/// def export_all(path: str) -> None;
+/// ```
+pub fn export_all(path: PathBuf) -> Result<()> {
+    let data = ReadAll::lock();
+    let file = File::create(&path).map_err(|e| fault::file(e.into()))?;
+    serde_json::to_writer(
+        BufWriter::new(file),
+        &DatasetRef {
+            slots: &data.slots,
+            tasks: &data.tasks,
+            users: &data.users,
+            pins: &data.pins,
+        },
+    )
+    .map_err(fault::internal)
+}
+
+/// Replace all current data with the contents of a file written by [`export_all`].
+///
+/// The file is read and checked in full before anything is replaced, and all data is locked
+/// while replacing it, so no request observes a partial import. Every ID counter is set past
+/// the largest ID imported, and remembered idempotency keys are forgotten.
+///
+/// **WARNING:** Current data will be overwitten without saving!
+///
+/// # Errors
+///
+/// Produces a [`fault::not_found`] error if the file does not exist,
+/// or a [`fault::unprocessable`] error if it is not a valid dataset
+/// or an item is keyed by an ID other than its own.
+///
+/// # Signature
+/// ```py
+/// def import_all(path: str) -> None;
+/// ```
+pub fn import_all(path: PathBuf) -> Result<()> {
+    let reader = File::open(&path)
+        .map(BufReader::new)
+        .map_err(|e| fault::file(e.into()))?;
+    let Dataset {
+        slots,
+        tasks,
+        mut users,
+        pins,
+    } = serde_json::from_reader(reader).map_err(fault::unprocessable)?;
+
+    fn check_keys<K, V>(map: &FxHashMap<K, V>, id: impl Fn(&V) -> K) -> Result<()>
+    where
+        K: Eq + std::hash::Hash + std::fmt::Display,
+    {
+        match map.iter().find(|(key, value)| **key != id(value)) {
+            Some((key, value)) => Err(fault::unprocessable(format_args!(
+                "item {} is keyed as {key}",
+                id(value)
+            ))),
+            None => Ok(()),
+        }
+    }
+    check_keys(&slots, |slot| slot.id)?;
+    check_keys(&tasks, |task| task.id)?;
+    check_keys(&users, |user| user.id)?;
+    for user in users.values_mut() {
+        check_keys(&user.availability, |rule| rule.id)?;
+        user.sync_rule_order();
+    }
+
+    let mut keys = IDEMPOTENCY_KEYS.lock();
+    let mut data = WriteAll::lock();
+    let rule_ids = users.values().flat_map(|user| user.availability.keys());
+    let skill_ids = users
+        .values()
+        .flat_map(|user| user.skills.keys())
+        .chain(tasks.values().flat_map(|task| task.skills.keys()));
+    SlotId::store(slots.keys().map(|k| k.0 + 1).max().unwrap_or(0));
+    TaskId::store(tasks.keys().map(|k| k.0 + 1).max().unwrap_or(0));
+    UserId::store(users.keys().map(|k| k.0 + 1).max().unwrap_or(0));
+    RuleId::store(rule_ids.map(|k| k.0 + 1).max().unwrap_or(0));
+    SkillId::store(skill_ids.map(|k| k.0 + 1).max().unwrap_or(0));
+    **data.slots = slots;
+    **data.tasks = tasks;
+    **data.users = users;
+    **data.pins = pins;
+    keys.clear();
+    notify_mutation(Mutation::all().difference(Mutation::TEMPLATES));
+    Ok(())
+}
+
+/// Read items as `format`.
+///
+/// JSON may be either an array of items or an object of items keyed by ID.
+pub(crate) fn load_as<V: DeserializeOwned>(reader: impl BufRead, format: Format) -> Result<Vec<V>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Items<V> {
+        List(Vec<V>),
+        Keyed(FxHashMap<String, V>),
+    }
+
+    match format {
+        Format::Json => match serde_json::from_reader(reader) {
+            Ok(Items::List(items)) => Ok(items),
+            Ok(Items::Keyed(items)) => Ok(items.into_values().collect()),
+            Err(e) => Err(fault::unprocessable(e)),
+        },
+        Format::Csv => csv::Reader::from_reader(reader)
+            .into_deserialize()
+            .collect::<csv::Result<_>>()
+            .map_err(fault::file),
+    }
+}
+
+/// Read items as JSON if the first non-whitespace byte is `{` or `[`, otherwise as CSV.
+///
+/// See [`load_as`].
+pub(crate) fn load_auto<V: DeserializeOwned>(mut reader: impl BufRead) -> Result<Vec<V>> {
+    // leading whitespace is not significant to either format
+    let format = loop {
+        let buf = reader.fill_buf().map_err(fault::internal)?;
+        if buf.is_empty() {
+            return Ok(Vec::new());
+        }
+        let n = buf.iter().take_while(|b| b.is_ascii_whitespace()).count();
+        let format = match buf.get(n) {
+            Some(b'{' | b'[') => Some(Format::Json),
+            Some(_) => Some(Format::Csv),
+            None => None,
+        };
+        reader.consume(n);
+        if let Some(format) = format {
+            break format;
+        }
+    };
+    load_as(reader, format)
+}
+
+/// Read items from the file at `path`, using its extension to pick the format
+/// if it is `.csv` or `.json`, or [sniffing](load_auto) the content otherwise.
+fn load_path<V: DeserializeOwned>(path: &Path) -> Result<Vec<V>> {
+    let reader = File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| fault::file(e.into()))?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("csv") => load_as(reader, Format::Csv),
+        Some(ext) if ext.eq_ignore_ascii_case("json") => load_as(reader, Format::Json),
+        _ => load_auto(reader),
+    }
 }
 
 /// Load all current [`Slot`] data to a file stored at `path`.
 ///
+/// The format is picked by extension (`.csv` or `.json`), or by content if the extension is neither.
+///
 /// **WARNING:** Current data will be overwitten without saving!
 pub fn load_slots(path: PathBuf) -> Result<()> {
     let mut next_id = 0;
-    **SLOTS.write() = csv::ReaderBuilder::default()
-        .from_path(path)
-        .and_then(|r| {
-            r.into_deserialize::<Slot>()
-                .map(|x| {
-                    x.map(|slot| {
-                        next_id = next_id.max(slot.id.0 + 1);
-                        (slot.id, slot)
-                    })
-                })
-                .collect()
+    let loaded = load_path::<Slot>(&path)?
+        .into_iter()
+        .map(|slot| {
+            next_id = next_id.max(slot.id.0 + 1);
+            (slot.id, slot)
         })
-        .map_err(|e| Fault::new(500, e.to_string()))?;
+        .collect();
+    let mut slots = SLOTS.write();
+    **slots = loaded;
     SlotId::store(next_id);
+    notify_mutation(Mutation::SLOTS);
     Ok(())
 }
 
 /// Load all current [`Task`] data to a file stored at `path`.
 ///
+/// The format is picked by extension (`.csv` or `.json`), or by content if the extension is neither.
+///
 /// **WARNING:** Current data will be overwitten without saving!
 pub fn load_tasks(path: PathBuf) -> Result<()> {
     let mut next_id = 0;
-    **TASKS.write() = csv::ReaderBuilder::default()
-        .from_path(path)
-        .and_then(|r| {
-            r.into_deserialize::<Task>()
-                .map(|x| {
-                    x.map(|task| {
-                        next_id = next_id.max(task.id.0 + 1);
-                        (task.id, task)
-                    })
-                })
-                .collect()
+    let loaded = load_path::<Task>(&path)?
+        .into_iter()
+        .map(|task| {
+            next_id = next_id.max(task.id.0 + 1);
+            (task.id, task)
         })
-        .map_err(|e| Fault::new(500, e.to_string()))?;
+        .collect();
+    let mut tasks = TASKS.write();
+    **tasks = loaded;
     TaskId::store(next_id);
+    notify_mutation(Mutation::TASKS);
     Ok(())
 }
 
@@ -1351,28 +3665,28 @@ pub fn load_tasks(path: PathBuf) -> Result<()> {
 ///
 /// Also loads all [`Rule`]s.
 ///
+/// The format is picked by extension (`.csv` or `.json`), or by content if the extension is neither.
+///
 /// **WARNING:** Current data will be overwitten without saving!
 pub fn load_users(path: PathBuf) -> Result<()> {
     let mut next_id = 0;
     let mut rule_id = 0;
-    **USERS.write() = csv::ReaderBuilder::default()
-        .from_path(path)
-        .and_then(|r| {
-            r.into_deserialize::<User>()
-                .map(|x| {
-                    x.map(|user| {
-                        next_id = next_id.max(user.id.0 + 1);
-                        if let Some(max) = user.availability.keys().map(|id| id.0).max() {
-                            rule_id = max.max(rule_id);
-                        }
-                        (user.id, user)
-                    })
-                })
-                .collect()
+    let loaded = load_path::<User>(&path)?
+        .into_iter()
+        .map(|mut user| {
+            user.sync_rule_order();
+            next_id = next_id.max(user.id.0 + 1);
+            if let Some(max) = user.availability.keys().map(|id| id.0).max() {
+                rule_id = max.max(rule_id);
+            }
+            (user.id, user)
         })
-        .map_err(|e| Fault::new(500, e.to_string()))?;
+        .collect();
+    let mut users = USERS.write();
+    **users = loaded;
     UserId::store(next_id);
     RuleId::store(rule_id);
+    notify_mutation(Mutation::USERS);
     Ok(())
 }
 
@@ -1380,8 +3694,12 @@ pub fn load_users(path: PathBuf) -> Result<()> {
 ///
 /// **WARNING:** Current data will not be saved!
 pub fn wipe_slots((): ()) -> Result<()> {
-    SLOTS.write().clear();
+    let mut slots = SLOTS.write();
+    let mut pins = PINNED.write();
+    slots.clear();
+    **pins = Pins::default();
     SlotId::store(0);
+    notify_mutation(Mutation::SLOTS.union(Mutation::PINS));
     Ok(())
 }
 
@@ -1389,8 +3707,12 @@ pub fn wipe_slots((): ()) -> Result<()> {
 ///
 /// **WARNING:** Current data will not be saved!
 pub fn wipe_tasks((): ()) -> Result<()> {
-    TASKS.write().clear();
+    let mut tasks = TASKS.write();
+    let mut pins = PINNED.write();
+    tasks.clear();
+    pins.tasks.clear();
     TaskId::store(0);
+    notify_mutation(Mutation::TASKS.union(Mutation::PINS));
     Ok(())
 }
 
@@ -1400,67 +3722,3513 @@ pub fn wipe_tasks((): ()) -> Result<()> {
 ///
 /// **WARNING:** Current data will not be saved!
 pub fn wipe_users((): ()) -> Result<()> {
-    USERS.write().clear();
+    let mut users = USERS.write();
+    let mut pins = PINNED.write();
+    users.clear();
+    pins.users.clear();
     UserId::store(0);
     RuleId::store(0);
+    notify_mutation(Mutation::USERS.union(Mutation::PINS));
     Ok(())
 }
 
-/// Close the server after completing all ongoing tasks.
+/// Return the server to a pristine state.
+///
+/// Clears all [`Slot`]s, [`Task`]s, [`User`]s, [`Rule`]s, skills, and [`SlotTemplate`]s, along with pinned assignments
+/// and remembered idempotency keys, and resets every ID counter to 0.
+/// All data is locked for the duration, so no request observes a partial reset.
+///
+/// **WARNING:** Current data will not be saved!
 ///
 /// # Signature
 /// ```py
-/// def quit(_: {}) -> None;
+/// def reset(_: {}) -> None;
 /// ```
+pub fn reset((): ()) -> Result<()> {
+    let mut keys = IDEMPOTENCY_KEYS.lock();
+    let mut data = WriteAll::lock();
+    data.slots.clear();
+    data.tasks.clear();
+    data.users.clear();
+    **data.pins = Pins::default();
+    let mut templates = TEMPLATES.write();
+    templates.clear();
+    keys.clear();
+    SlotId::store(0);
+    TaskId::store(0);
+    UserId::store(0);
+    RuleId::store(0);
+    SkillId::store(0);
+    SlotTemplateId::store(0);
+    notify_mutation(Mutation::all());
+    Ok(())
+}
+
+/// The result of [`purge_past`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PurgeResult {
+    /// The [`Slot`]s that were removed.
+    pub slots: SlotSet,
+
+    /// The [`Task`]s that were removed.
+    pub tasks: TaskSet,
+
+    /// The [`Task`]s that were past but kept because another task still depends on them.
+    pub retained: TaskSet,
+}
+
+/// Remove [`Slot`]s ending before `before` and [`Task`]s with [effective deadlines](algo::effective_deadlines) before `before`.
 ///
-/// # Examples
+/// A past task is kept if any task that is not being removed depends on it,
+/// so that no dependency is left dangling.
+///
+/// # Signature
 /// ```py
-/// # request server close
-/// proxy.quit({})
+/// class PurgeResult(TypedDict):
+///     slots: list[SlotId]
+///     tasks: list[TaskId]
+///     retained: list[TaskId]
+///
+/// def purge_past(before: datetime) -> PurgeResult;
 /// ```
-pub fn quit((): ()) -> Result<()> {
-    EXIT_REQUESTED.store(true, std::sync::atomic::Ordering::Relaxed);
+pub fn purge_past(before: DateTime<Utc>) -> Result<PurgeResult> {
+    let mut slots = SLOTS.write();
+    let mut tasks = TASKS.write();
+
+    let purged_slots = slots
+        .values()
+        .filter(|slot| slot.interval.end < before)
+        .map(|slot| slot.id)
+        .collect::<SlotSet>();
+
+    let past_tasks = algo::effective_deadlines(&tasks)
+        .into_iter()
+        .filter(|(_, deadlines)| deadlines.latest().is_some_and(|deadline| deadline < before))
+        .map(|(id, _)| id)
+        .collect::<TaskSet>();
+    let mut purged_tasks = past_tasks.clone();
+    // a task kept for its dependents keeps its own dependencies alive too
+    loop {
+        let depended_on = tasks
+            .values()
+            .filter(|task| !purged_tasks.contains(&task.id))
+            .flat_map(|task| &task.deps)
+            .filter(|dep| purged_tasks.contains(dep))
+            .copied()
+            .collect::<Vec<_>>();
+        if depended_on.is_empty() {
+            break;
+        }
+        for id in depended_on {
+            purged_tasks.remove(&id);
+        }
+    }
+    let retained = past_tasks.difference(&purged_tasks).copied().collect();
+
+    slots.retain(|id, _| !purged_slots.contains(id));
+    tasks.retain(|id, _| !purged_tasks.contains(id));
+    if !purged_slots.is_empty() || !purged_tasks.is_empty() {
+        notify_mutation(Mutation::SLOTS.union(Mutation::TASKS));
+    }
+    Ok(PurgeResult {
+        slots: purged_slots,
+        tasks: purged_tasks,
+        retained,
+    })
+}
+
+/// One row of [`export_schedule_csv`].
+#[derive(Debug, Serialize)]
+struct ScheduleRow<'a> {
+    slot: &'a str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    user: &'a str,
+    tasks: &'a str,
+}
+
+/// Generate a schedule from the current data and export it as CSV for opening in a spreadsheet.
+///
+/// This is a flattened, human-readable view, not a save format; it cannot be loaded back.
+///
+/// Each row is one user assigned to one slot, with the columns
+/// `slot,start,end,user,tasks`. `tasks` lists the titles of the tasks
+/// [worked](algo::worked_tasks) during the slot, separated by `"; "`.
+/// Rows are ordered by slot (see [`TimeInterval` ordering](TimeInterval#ordering)), then by user name.
+///
+/// # Signature
+/// ```py
+/// def export_schedule_csv(_: {}) -> str;
+/// ```
+pub fn export_schedule_csv((): ()) -> Result<String> {
+    let data = ReadAll::lock();
+    let schedule = data.generate().map_err(fault::unprocessable)?;
+    schedule_csv(&schedule, &data.slots, &data.tasks, &data.users, &data.pins)
+        .map_err(fault::internal)
+        .and_then(|buf| String::from_utf8(buf).map_err(fault::internal))
+}
+
+/// The result of [`generate_schedule`].
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeneratedSchedule {
+    /// The seed the schedule was generated with. Pass it again to reproduce the schedule.
+    pub seed: Option<u64>,
+
+    /// The generated schedule.
+    pub schedule: Schedule,
+}
+
+/// Generate a schedule from the current data.
+///
+/// Equally preferred candidates are chosen in an order shuffled by `seed`, if given,
+/// or else by the server's configured seed, if any (see [`SchedulerConfig::seed`]),
+/// so that a surprising schedule can be reproduced exactly. The seed used is returned alongside the schedule.
+///
+/// # Errors
+///
+/// Produces a [`fault::unprocessable`] error if no schedule can be generated.
+///
+/// # Signature
+/// ```py
+/// def generate_schedule(seed: int | None) -> {
+///   'seed': int | None,
+///   'schedule': dict[SlotId, set[UserId]],
+/// };
+/// ```
+pub fn generate_schedule(seed: Option<u64>) -> Result<GeneratedSchedule> {
+    let data = ReadAll::lock();
+    let config = data.scheduler_config();
+    let seed = seed.or(config.seed);
+    let config = SchedulerConfig { seed, ..config };
+    let schedule = Schedule::generate_with(&data.slots, &data.tasks, &data.users, &config)
+        .map_err(fault::unprocessable)?;
+    Ok(GeneratedSchedule { seed, schedule })
+}
+
+/// Suggest slots to add for the tasks no current slot can serve, such as for lack of qualified staff.
+///
+/// Nothing is added; pass the suggestions to [`add_slots`] to accept them.
+/// Each suggested slot prefers the task it is for and is named after it.
+/// Tasks whose deadline has passed get no suggestion. See [`algo::suggest_slots`].
+///
+/// # Signature
+/// ```py
+/// def suggest_slots(_: {}) -> list[{
+///   'start': datetime,
+///   'end':   datetime,
+///   'min_staff': None,
+///   'max_staff': None,
+///   'preferred_tasks': set[TaskId],  # the task the slot is suggested for
+///   'name': str,                     # the title of that task
+/// }];  # ordered by task ID
+/// ```
+pub fn suggest_slots((): ()) -> Result<Vec<PySlot>> {
+    let data = ReadAll::lock();
+    let mut suggestions = Vec::from_iter(algo::suggest_slots(
+        &data.slots,
+        &data.tasks,
+        &data.users,
+        Utc::now(),
+    ));
+    suggestions.sort_by_key(|(task_id, _)| task_id.0);
+    Ok(suggestions
+        .into_iter()
+        .map(|(task_id, interval)| PySlot {
+            start: interval.start,
+            end: interval.end,
+            min_staff: None,
+            max_staff: None,
+            preferred_tasks: Some(TaskSet::from_iter([task_id])),
+            name: Some(data.tasks[&task_id].title.clone()),
+        })
+        .collect())
+}
+
+/// Find users staffed on two overlapping slots in `schedule`, such as after editing it by hand.
+///
+/// Each double-booking is reported once as `(user, a, b)`, where `a` has the lower slot ID.
+///
+/// # Errors
+///
+/// Produces a [`fault::not_found`] error if the schedule refers to a slot that does not exist.
+///
+/// # Signature
+/// ```py
+/// def find_double_bookings(schedule: list[{
+///   'slot': SlotId,
+///   'users': list[UserId],
+/// }]) -> list[tuple[UserId, SlotId, SlotId]];
+/// ```
+pub fn find_double_bookings(schedule: PySchedule) -> Result<Vec<(UserId, SlotId, SlotId)>> {
+    let slots = SLOTS.read();
+    if let Some((index, assignment)) = schedule
+        .0
+        .iter()
+        .enumerate()
+        .find(|(_, assignment)| !slots.contains_key(&assignment.slot))
+    {
+        return Err(fault::at(
+            fault::NOT_FOUND,
+            index,
+            Some(assignment.slot),
+            "slot does not exist",
+        ));
+    }
+    Ok(Schedule::from(schedule).double_bookings(&slots))
+}
+
+/// An assignment to keep fixed across schedule generations. See [`pin_assignment`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PyPin {
+    /// The slot being assigned to.
+    pub slot: SlotId,
+
+    /// The user to staff on the slot.
+    pub user: Option<UserId>,
+
+    /// The task to work on during the slot.
+    pub task: Option<TaskId>,
+}
+
+/// Keep a user staffed on (or a task assigned to) a slot whenever a schedule is generated,
+/// regardless of preference. The rest of the schedule is generated around it.
+///
+/// Generating a schedule fails if a pinned user is forbidden from the slot by one of their rules.
+///
+/// # Errors
+///
+/// Produces a [`fault::bad_request`] error if neither `user` nor `task` is provided,
+/// or a [`fault::not_found`] error if the slot, user, or task does not exist.
+///
+/// # Signature
+/// ```py
+/// def pin_assignment(pin: {
+///   'slot': SlotId,
+///   'user': UserId | None,
+///   'task': TaskId | None,
+/// }) -> None;
+/// ```
+pub fn pin_assignment(pin: PyPin) -> Result<()> {
+    let PyPin { slot, user, task } = pin;
+    if user.is_none() && task.is_none() {
+        return Err(fault::bad_request("pin requires a user or a task"));
+    }
+    // held until the pin is placed, so nothing can be removed after it is checked
+    let slots = SLOTS.read();
+    let tasks = TASKS.read();
+    let users = USERS.read();
+    let mut pins = PINNED.write();
+    if !slots.contains_key(&slot) {
+        return Err(fault::not_found(format_args!("slot {slot} does not exist")));
+    }
+    if let Some(user) = user
+        && !users.contains_key(&user)
+    {
+        return Err(fault::not_found(format_args!("user {user} does not exist")));
+    }
+    if let Some(task) = task
+        && !tasks.contains_key(&task)
+    {
+        return Err(fault::not_found(format_args!("task {task} does not exist")));
+    }
+
+    if let Some(user) = user {
+        pins.users.entry(slot).or_default().insert(user);
+    }
+    if let Some(task) = task {
+        pins.tasks.entry(slot).or_default().insert(task);
+    }
+    notify_mutation(Mutation::PINS);
     Ok(())
 }
 
-pub(crate) fn register(server: &mut Server) {
-    server.register_simple("pat_starts_with", Pattern::starts_with);
-    server.register_simple("pat_ends_with", Pattern::ends_with);
-    server.register_simple("pat_contains", Pattern::contains);
-    server.register_simple("pat_exactly", Pattern::exactly);
-    server.register_simple("pat_regex", Pattern::regex);
-
-    server.register_simple("add_rules", add_rules);
-    server.register_simple("add_slots", add_slots);
-    server.register_simple("add_tasks", add_tasks);
-    server.register_simple("add_users", add_users);
-
-    server.register_simple("get_rules", get_rules);
-    server.register_simple("get_slots", get_slots);
-    server.register_simple("get_tasks", get_tasks);
-    server.register_simple("get_users", get_users);
+/// The result of [`check_assignment`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssignmentCheck {
+    /// Whether none of the problems below apply.
+    pub ok: bool,
 
-    // rules can be mutated through `availability` field of `mut_users`
-    server.register_simple("mut_slots", mut_slots);
-    server.register_simple("mut_tasks", mut_tasks);
-    server.register_simple("mut_users", mut_users);
+    /// Whether any of the user's rules covers the whole slot without forbidding it.
+    pub available: bool,
+
+    /// Staff pinned to the slot that the user refuses to work with, or that refuse to work with the user,
+    /// by a [`Preference::NEG_INFINITY`] [`User::user_prefs`].
+    pub conflicts_with: UserSet,
+
+    /// Whether the assignment would put the slot over its [`Slot::max_staff`], counting pinned staff.
+    pub over_capacity: bool,
+
+    /// A description of each problem, for displaying to the manager.
+    pub reasons: Vec<String>,
+}
+
+/// Check whether staffing a user on a slot would be legal, before [pinning](pin_assignment) it.
+///
+/// # Errors
+///
+/// Produces a [`fault::not_found`] error if the slot or user does not exist.
+///
+/// # Signature
+/// ```py
+/// def check_assignment(slot_id: SlotId, user_id: UserId) -> {
+///   'ok': bool,
+///   'available': bool,
+///   'conflicts_with': set[UserId],
+///   'over_capacity': bool,
+///   'reasons': list[str],
+/// };
+/// ```
+pub fn check_assignment((slot_id, user_id): (SlotId, UserId)) -> Result<AssignmentCheck> {
+    let slots = SLOTS.read();
+    let users = USERS.read();
+    let slot = slots
+        .get(&slot_id)
+        .ok_or_else(|| fault::not_found(format_args!("slot {slot_id} does not exist")))?;
+    let user = users
+        .get(&user_id)
+        .ok_or_else(|| fault::not_found(format_args!("user {user_id} does not exist")))?;
+    let pins = PINNED.read();
+    let pinned = pins.users.get(&slot_id);
+    let staff = pinned
+        .into_iter()
+        .flatten()
+        .filter(|&&id| id != user_id)
+        .filter_map(|id| users.get(id))
+        .collect::<Vec<_>>();
+
+    let mut check = AssignmentCheck {
+        available: algo::is_available(user, &slot.interval),
+        conflicts_with: staff
+            .iter()
+            .filter(|other| {
+                let refuses =
+                    |a: &User, b: &User| a.user_prefs.get(&b.id) == Some(&Preference::NEG_INFINITY);
+                refuses(user, other) || refuses(other, user)
+            })
+            .map(|other| other.id)
+            .collect(),
+        over_capacity: slot
+            .max_staff
+            .is_some_and(|max| staff.len() + 1 > max.get()),
+        ..Default::default()
+    };
+    if !check.available {
+        check
+            .reasons
+            .push(format!("{} is not available for this slot", user.name));
+    }
+    let mut conflicts = staff
+        .iter()
+        .filter(|other| check.conflicts_with.contains(&other.id))
+        .map(|other| other.name.as_str())
+        .collect::<Vec<_>>();
+    conflicts.sort_unstable();
+    for other in conflicts {
+        check
+            .reasons
+            .push(format!("{} cannot work with {other}", user.name));
+    }
+    if check.over_capacity {
+        check
+            .reasons
+            .push(String::from("the slot is already at its maximum staff"));
+    }
+    check.ok = check.reasons.is_empty();
+    Ok(check)
+}
+
+/// Stop keeping an assignment fixed. See [`pin_assignment`].
+///
+/// Returns whether anything was unpinned.
+///
+/// # Signature
+/// ```py
+/// def unpin_assignment(pin: {
+///   'slot': SlotId,
+///   'user': UserId | None,
+///   'task': TaskId | None,
+/// }) -> bool;
+/// ```
+pub fn unpin_assignment(pin: PyPin) -> Result<bool> {
+    let PyPin { slot, user, task } = pin;
+    let mut pins = PINNED.write();
+    let mut removed = false;
+    if let Some(user) = user
+        && let Some(users) = pins.users.get_mut(&slot)
+    {
+        removed |= users.remove(&user);
+        if users.is_empty() {
+            pins.users.remove(&slot);
+        }
+    }
+    if let Some(task) = task
+        && let Some(tasks) = pins.tasks.get_mut(&slot)
+    {
+        removed |= tasks.remove(&task);
+        if tasks.is_empty() {
+            pins.tasks.remove(&slot);
+        }
+    }
+    if removed {
+        notify_mutation(Mutation::PINS);
+    }
+    Ok(removed)
+}
+
+/// Write `schedule` in the format of [`export_schedule_csv`].
+///
+/// The tasks of each slot are those [worked](algo::worked_tasks) during it under `pins`.
+pub(crate) fn schedule_csv(
+    schedule: &Schedule,
+    slots: &SlotMap,
+    tasks: &TaskMap,
+    users: &UserMap,
+    pins: &Pins,
+) -> csv::Result<Vec<u8>> {
+    let deadlines = algo::pinned_deadlines(slots, tasks, pins);
+    let mut assignments = schedule
+        .0
+        .iter()
+        .filter_map(|(slot_id, staff)| slots.get(slot_id).map(|slot| (slot, staff)))
+        .collect::<Vec<_>>();
+    assignments.sort_by_key(|(slot, _)| (slot.interval, slot.id.0));
+
+    let mut w = csv::Writer::from_writer(Vec::new());
+    for (slot, staff) in assignments {
+        let titles = algo::worked_tasks(slot, tasks, &deadlines, pins)
+            .into_iter()
+            .map(|task| task.title.as_str())
+            .collect::<Vec<_>>()
+            .join("; ");
+        let mut staff = staff
+            .iter()
+            .filter_map(|user_id| users.get(user_id))
+            .collect::<Vec<_>>();
+        staff.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.0.cmp(&b.id.0)));
+        for user in staff {
+            w.serialize(ScheduleRow {
+                slot: &slot.name,
+                start: slot.start,
+                end: slot.end,
+                user: &user.name,
+                tasks: &titles,
+            })?;
+        }
+    }
+    w.into_inner().map_err(|e| e.into_error().into())
+}
+
+/// Generate a schedule from the current data and explain why each user was or was not staffed on a slot.
+///
+/// # Errors
+///
+/// Produces a [`fault::not_found`] error if the slot does not exist,
+/// or a [`fault::unprocessable`] error if no schedule can be generated.
+///
+/// # Signature
+/// ```py
+/// def explain_slot(slot_id: SlotId) -> {
+///   'slot': SlotId,
+///   'candidates': dict[UserId, {
+///     'preference': float | None,
+///     'available': bool,
+///     'forbidden_by': list[RuleId],
+///     'selected': bool,
+///   }],
+/// };
+/// ```
+pub fn explain_slot(slot_id: SlotId) -> Result<SlotExplanation> {
+    let data = ReadAll::lock();
+    let slot = data
+        .slots
+        .get(&slot_id)
+        .ok_or_else(|| fault::not_found(format_args!("slot {slot_id} does not exist")))?;
+    let schedule = data.generate().map_err(fault::unprocessable)?;
+    Ok(schedule.explain(slot, &data.users))
+}
+
+/// How well a [`Slot`] can be staffed, judging by its candidates alone. See [`CoverageStat`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CoverageStatus {
+    /// More candidates are available than the slot needs.
+    Ok,
 
-    server.register_simple("pop_rules", pop_rules);
-    server.register_simple("pop_slots", pop_slots);
-    server.register_simple("pop_tasks", pop_tasks);
-    server.register_simple("pop_users", pop_users);
+    /// Exactly as many candidates are available as the slot needs,
+    /// so losing any one of them would leave it understaffed.
+    AtRisk,
 
-    server.register_simple("save_slots", save_slots);
-    server.register_simple("save_tasks", save_tasks);
-    server.register_simple("save_users", save_users);
+    /// Fewer candidates are available than the slot needs.
+    Understaffed,
+}
+
+/// An entry in the result of [`coverage_report`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageStat {
+    /// How many users are [available](algo::is_available) for the whole slot.
+    pub available_candidates: usize,
+
+    /// See [`Slot::min_staff`]
+    pub min_staff: Option<usize>,
 
-    server.register_simple("load_slots", load_slots);
-    server.register_simple("load_tasks", load_tasks);
-    server.register_simple("load_users", load_users);
+    /// See [`Slot::max_staff`]
+    pub max_staff: Option<usize>,
 
-    server.register_simple("wipe_slots", wipe_slots);
-    server.register_simple("wipe_tasks", wipe_tasks);
-    server.register_simple("wipe_users", wipe_users);
+    /// How `available_candidates` compares to `min_staff`.
+    pub status: CoverageStatus,
+}
 
-    server.register_simple("quit", quit);
+/// Count the candidates available for each slot, to spot understaffing before generating a schedule.
+///
+/// Slots without a [`Slot::min_staff`] are never at risk.
+///
+/// # Signature
+/// ```py
+/// def coverage_report(_: {}) -> dict[SlotId, {
+///   'available_candidates': int,
+///   'min_staff': int | None,
+///   'max_staff': int | None,
+///   'status': 'Ok' | 'AtRisk' | 'Understaffed',
+/// }];
+/// ```
+pub fn coverage_report((): ()) -> Result<SlotMap<CoverageStat>> {
+    let slots = SLOTS.read();
+    let users = USERS.read();
+    Ok(slots
+        .values()
+        .map(|slot| {
+            let available_candidates = users
+                .values()
+                .filter(|user| algo::is_available(user, &slot.interval))
+                .count();
+            let min_staff = slot.min_staff.map(NonZeroUsize::get);
+            let status = match min_staff.map(|min| available_candidates.cmp(&min)) {
+                Some(std::cmp::Ordering::Less) => CoverageStatus::Understaffed,
+                Some(std::cmp::Ordering::Equal) => CoverageStatus::AtRisk,
+                Some(std::cmp::Ordering::Greater) | None => CoverageStatus::Ok,
+            };
+            let stat = CoverageStat {
+                available_candidates,
+                min_staff,
+                max_staff: slot.max_staff.map(NonZeroUsize::get),
+                status,
+            };
+            (slot.id, stat)
+        })
+        .collect())
+}
+
+/// The result of [`list_hard_constraints`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HardConstraints {
+    /// Each [`User`]'s availability [`Rule`]s with an infinite preference.
+    pub availability: Vec<(UserId, RuleId)>,
+
+    /// Each [`User`]'s infinite [preferences towards other users](User::user_prefs).
+    pub user_prefs: Vec<(UserId, UserId)>,
+
+    /// Each [`Task`]'s infinite [preferences towards users](Task::preferred_users).
+    pub task_prefs: Vec<(TaskId, UserId)>,
+}
+
+/// List every infinite preference, which the scheduler must obey or fail,
+/// so that they can be reviewed before generating a schedule.
+///
+/// Each list is ordered by the IDs in its pairs. Use the `get_*` endpoints to see which sign each preference has.
+///
+/// # Signature
+/// ```py
+/// def list_hard_constraints(_: {}) -> {
+///   'availability': list[tuple[UserId, RuleId]],
+///   'user_prefs': list[tuple[UserId, UserId]],  # (user, towards)
+///   'task_prefs': list[tuple[TaskId, UserId]],
+/// };
+/// ```
+pub fn list_hard_constraints((): ()) -> Result<HardConstraints> {
+    let tasks = TASKS.read();
+    let users = USERS.read();
+    let mut constraints = HardConstraints::default();
+    for user in users.values() {
+        constraints.availability.extend(
+            user.availability
+                .values()
+                .filter(|rule| rule.pref.is_infinite())
+                .map(|rule| (user.id, rule.id)),
+        );
+        constraints.user_prefs.extend(
+            user.user_prefs
+                .iter()
+                .filter(|(_, pref)| pref.is_infinite())
+                .map(|(other, _)| (user.id, *other)),
+        );
+    }
+    for task in tasks.values() {
+        constraints.task_prefs.extend(
+            task.preferred_users
+                .iter()
+                .filter(|(_, pref)| pref.is_infinite())
+                .map(|(user, _)| (task.id, *user)),
+        );
+    }
+    constraints
+        .availability
+        .sort_by_key(|(user, rule)| (user.0, rule.0));
+    constraints
+        .user_prefs
+        .sort_by_key(|(user, other)| (user.0, other.0));
+    constraints
+        .task_prefs
+        .sort_by_key(|(task, user)| (task.0, user.0));
+    Ok(constraints)
+}
+
+/// The result of [`get_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    /// How many [`User`]s exist.
+    pub users: usize,
+
+    /// How many [`Task`]s exist.
+    pub tasks: usize,
+
+    /// How many [`Slot`]s exist.
+    pub slots: usize,
+
+    /// The sum of each staffed slot's length in hours, times its number of staff.
+    /// [`None`] if no schedule can be generated.
+    pub scheduled_hours: Option<f64>,
+
+    /// The mean [preference](algo::availability_pref) of staff towards the slots they are staffed on,
+    /// with infinities counted as -1 or +1.
+    /// [`None`] if no schedule can be generated, or nobody is staffed on a slot they have a preference towards.
+    pub mean_preference: Option<f32>,
+
+    /// How many tasks no staffed slot prefers or has pinned.
+    /// [`None`] if no schedule can be generated.
+    pub unscheduled_tasks: Option<usize>,
+}
+
+/// Totals for an overview of the current data and the schedule generated from it, in one call.
+///
+/// The schedule is the last one generated, if nothing has changed since; otherwise one is generated.
+///
+/// # Signature
+/// ```py
+/// def get_stats(_: {}) -> {
+///   'users': int,
+///   'tasks': int,
+///   'slots': int,
+///   'scheduled_hours': float | None,    # None if no schedule can be generated
+///   'mean_preference': float | None,    # between -1 and +1
+///   'unscheduled_tasks': int | None,
+/// };
+/// ```
+pub fn get_stats((): ()) -> Result<Stats> {
+    let data = ReadAll::lock();
+    let mut stats = Stats {
+        users: data.users.len(),
+        tasks: data.tasks.len(),
+        slots: data.slots.len(),
+        scheduled_hours: None,
+        mean_preference: None,
+        unscheduled_tasks: None,
+    };
+    let Ok(schedule) = data.generate() else {
+        return Ok(stats);
+    };
+    let staffed = schedule
+        .0
+        .iter()
+        .filter(|(_, staff)| !staff.is_empty())
+        .filter_map(|(slot_id, staff)| Some((data.slots.get(slot_id)?, staff)))
+        .collect::<Vec<_>>();
+    stats.scheduled_hours = Some(
+        staffed
+            .iter()
+            .map(|(slot, staff)| {
+                (slot.end - slot.start).as_seconds_f64() / 3600.0 * staff.len() as f64
+            })
+            .sum(),
+    );
+    let prefs = staffed
+        .iter()
+        .flat_map(|(slot, staff)| {
+            staff
+                .iter()
+                .filter_map(|user_id| data.users.get(user_id))
+                .filter_map(|user| algo::availability_pref(user, &slot.interval))
+        })
+        .map(|pref| pref.0.clamp(-1.0, 1.0))
+        .collect::<Vec<_>>();
+    stats.mean_preference =
+        (!prefs.is_empty()).then(|| prefs.iter().sum::<f32>() / prefs.len() as f32);
+    let deadlines = algo::pinned_deadlines(&data.slots, &data.tasks, &data.pins);
+    let scheduled = staffed
+        .iter()
+        .flat_map(|(slot, _)| algo::worked_tasks(slot, &data.tasks, &deadlines, &data.pins))
+        .map(|task| task.id)
+        .collect::<FxHashSet<_>>();
+    stats.unscheduled_tasks = Some(
+        data.tasks
+            .keys()
+            .filter(|id| !scheduled.contains(id))
+            .count(),
+    );
+    Ok(stats)
+}
+
+/// The result of [`changes_since`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Changes {
+    /// The current data version.
+    pub version: u64,
+
+    /// Whether the data has changed since the version provided.
+    pub changed: bool,
+}
+
+/// Cheaply check whether any data has been mutated since `version`,
+/// so that the frontend only needs to re-fetch when its copy is stale.
+///
+/// The version starts at 0 and increases every time backend data is mutated.
+///
+/// # Signature
+/// ```py
+/// def changes_since(version: int) -> {
+///   'version': int,
+///   'changed': bool,
+/// };
+/// ```
+///
+/// # Examples
+/// ```py
+/// version = proxy.changes_since(0)['version']
+/// # ...
+/// changes = proxy.changes_since(version)
+/// if changes['changed']:
+///   version = changes['version']
+///   # re-fetch
+/// ```
+pub fn changes_since(version: u64) -> Result<Changes> {
+    let current = VERSION.load(Relaxed);
+    Ok(Changes {
+        version: current,
+        changed: current != version,
+    })
+}
+
+/// Return `value` unchanged, after decoding and before re-encoding it,
+/// so that clients can check how each type crosses the wire. Stored data is not touched.
+///
+/// Registered once per type as `echo_<type>`: `echo_rule`, `echo_slot`, `echo_task`, `echo_user`,
+/// `echo_pattern`, `echo_rule_filter`, `echo_slot_filter`, `echo_task_filter`, and `echo_user_filter`.
+///
+/// # Errors
+///
+/// Produces a [`fault::bad_request`] error if `value` does not decode as the type.
+///
+/// # Signature
+/// ```py
+/// def echo_task(value: PyTask) -> PyTask;  # and so on for each type
+/// ```
+///
+/// # Examples
+/// ```py
+/// task = {'title': "wash dishes", 'desc': None, 'deadline': None, 'awaiting': None}
+/// assert proxy.echo_task(task)['title'] == task['title']
+/// ```
+pub fn echo<T>(value: T) -> Result<T> {
+    Ok(value)
+}
+
+/// Close the server after completing all ongoing tasks.
+///
+/// # Signature
+/// ```py
+/// def quit(_: {}) -> None;
+/// ```
+///
+/// # Examples
+/// ```py
+/// # request server close
+/// proxy.quit({})
+/// ```
+pub fn quit((): ()) -> Result<()> {
+    EXIT_REQUESTED.store(true, Relaxed);
+    Ok(())
+}
+
+/// Close the server after `seconds` have passed, as if [`quit`] were called then.
+/// Replaces any timer already armed.
+///
+/// # Errors
+///
+/// Produces a [`fault::bad_request`] error if `seconds` is too far in the future to represent.
+///
+/// # Signature
+/// ```py
+/// def shutdown_after(seconds: int) -> None;
+/// ```
+///
+/// # Examples
+/// ```py
+/// # close the server in an hour
+/// proxy.shutdown_after(60 * 60)
+/// ```
+///
+/// **See also:** [`cancel_shutdown`]
+pub fn shutdown_after(seconds: u64) -> Result<()> {
+    let at = Instant::now()
+        .checked_add(Duration::from_secs(seconds))
+        .ok_or_else(|| fault::bad_request(format_args!("{seconds} seconds is too far away")))?;
+    *SHUTDOWN_AT.lock() = Some(at);
+    Ok(())
+}
+
+/// Disarm the timer set by [`shutdown_after`].
+///
+/// Returns whether a timer was armed.
+///
+/// # Signature
+/// ```py
+/// def cancel_shutdown(_: {}) -> bool;
+/// ```
+pub fn cancel_shutdown((): ()) -> Result<bool> {
+    Ok(SHUTDOWN_AT.lock().take().is_some())
+}
+
+/// Whether the server should close, either by [`quit`] or because the
+/// [`shutdown_after`] timer has fired. Checked by the poll loop between requests.
+pub(crate) fn exit_requested() -> bool {
+    let mut shutdown_at = SHUTDOWN_AT.lock();
+    if shutdown_at.is_some_and(|at| at <= Instant::now()) {
+        *shutdown_at = None;
+        EXIT_REQUESTED.store(true, Relaxed);
+    }
+    EXIT_REQUESTED.load(Relaxed)
+}
+
+/// How many idempotency keys are remembered. See [`call_idempotent`].
+pub const IDEMPOTENCY_CAPACITY: usize = 256;
+
+/// Responses of the most recent keyed requests, least recently used first.
+static IDEMPOTENCY_KEYS: Mutex<VecDeque<(&'static str, String, Params)>> =
+    Mutex::new(VecDeque::new());
+
+/// Call `handler`, allowing the client to provide an idempotency key
+/// as an optional trailing string parameter.
+///
+/// If a request to the same method with the same key was seen recently,
+/// its response is returned again instead of calling `handler`.
+/// This way, retrying an `add_*` request on an unreliable connection
+/// will not create duplicates.
+///
+/// Only the [`IDEMPOTENCY_CAPACITY`] most recently used keys are retained;
+/// a key that has been evicted will be treated as new.
+/// Keys are also forgotten when the server restarts.
+///
+/// # Signature
+/// ```py
+/// def add_tasks(to_add: list[...], key: str | None = None) -> {'value': list[TaskId], ...};
+/// ```
+///
+/// # Examples
+/// ```py
+/// key = str(uuid.uuid4())
+/// added = proxy.add_tasks([{'title': "wash dishes"}], key)
+/// # connection dropped, retry
+/// assert proxy.add_tasks([{'title': "wash dishes"}], key) == added
+/// ```
+fn call_idempotent<Treq, Tres>(
+    name: &'static str,
+    handler: fn(Treq) -> Result<Tres>,
+    mut params: Params,
+) -> Response
+where
+    Treq: DeserializeOwned,
+    Tres: Serialize,
+{
+    let key = if let [_, Value::String(key)] = params.as_mut_slice() {
+        let key = std::mem::take(key);
+        params.pop();
+        Some(key)
+    } else {
+        None
+    };
+
+    // held for the entire call so that concurrent retries cannot both succeed
+    let mut recent = IDEMPOTENCY_KEYS.lock();
+    if let Some(key) = &key
+        && let Some(i) = recent.iter().position(|(n, k, _)| *n == name && k == key)
+    {
+        let entry = recent.remove(i).expect("index should be in bounds");
+        let response = entry.2.clone();
+        recent.push_back(entry);
+        return Ok(response);
+    }
+
+    let response = call_simple(handler, params)?;
+
+    if let Some(key) = key {
+        if recent.len() >= IDEMPOTENCY_CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back((name, key, response.clone()));
+    }
+    Ok(response)
+}
+
+/// Decode `params`, call `handler`, and encode its result, like [`Server::register_simple`] does.
+fn call_simple<Treq, Tres>(handler: impl FnOnce(Treq) -> Result<Tres>, params: Params) -> Response
+where
+    Treq: DeserializeOwned,
+    Tres: Serialize,
+{
+    let request = from_params(params)
+        .map_err(|e| fault::bad_request(format_args!("Failed to decode request: {e}")))?;
+    into_params(&handler(request)?)
+        .map_err(|e| fault::internal(format_args!("Failed to encode response: {e}")))
+}
+
+/// Where mutating calls are recorded, if anywhere. See [`open_audit_log`].
+static AUDIT_LOG: Mutex<Option<File>> = Mutex::new(None);
+
+/// Start appending a record of every mutating call to the file at `path`, creating it if needed.
+///
+/// Each call is written as one line of JSON (NDJSON) once it has finished, with the fields
+/// `timestamp`, `method`, `args` (the shape of the parameters, not their contents),
+/// `status` (`"ok"` or `"fault"`), `fault_code`, and `changed` (the collections the call changed;
+/// see [`Mutation`]).
+pub(crate) fn open_audit_log(path: &Path) -> std::io::Result<()> {
+    let file = File::options().create(true).append(true).open(path)?;
+    *AUDIT_LOG.lock() = Some(file);
+    Ok(())
+}
+
+/// One line of the [audit log](open_audit_log).
+#[derive(Debug, Serialize)]
+struct AuditEntry<'a> {
+    timestamp: DateTime<Utc>,
+    method: &'a str,
+    args: String,
+    status: &'static str,
+    fault_code: Option<i32>,
+    changed: Vec<String>,
+}
+
+/// Describe the shape of `params` without their contents, which may be personal data.
+fn summarize(params: &Params) -> String {
+    fn describe(value: &Value) -> String {
+        match value {
+            Value::Int(_) => "int".to_string(),
+            Value::Bool(_) => "bool".to_string(),
+            Value::String(_) => "str".to_string(),
+            Value::Double(_) => "float".to_string(),
+            Value::DateTime(_) => "datetime".to_string(),
+            Value::Base64(_) => "bytes".to_string(),
+            Value::Array(items) => format!("list[{}]", items.len()),
+            Value::Struct(fields) => format!("dict[{}]", fields.len()),
+        }
+    }
+    params.iter().map(describe).collect::<Vec<_>>().join(", ")
+}
+
+/// Call `call` with `params`, then record it in the [audit log](open_audit_log) if one is open.
+///
+/// Failing to write the log is reported on stderr but does not fail the call.
+fn call_audited(
+    name: &'static str,
+    params: Params,
+    call: impl FnOnce(Params) -> Response,
+) -> Response {
+    let args = summarize(&params);
+    CHANGED.set(Mutation::empty());
+    let response = call(params);
+    let changed = CHANGED.replace(Mutation::empty());
+
+    if let Some(log) = &mut *AUDIT_LOG.lock() {
+        let entry = AuditEntry {
+            timestamp: Utc::now(),
+            method: name,
+            args,
+            status: if response.is_ok() { "ok" } else { "fault" },
+            fault_code: response.as_ref().err().map(|fault| fault.code),
+            changed: changed
+                .iter_names()
+                .map(|(name, _)| name.to_lowercase())
+                .collect(),
+        };
+        let written = serde_json::to_writer(&mut *log, &entry)
+            .map_err(std::io::Error::from)
+            .and_then(|()| writeln!(log));
+        if let Err(e) = written {
+            eprintln!("srv: could not write audit log: {e}");
+        }
+    }
+    response
+}
+
+/// The default for [`MAX_CONNECTIONS`].
+pub const DEFAULT_MAX_CONNECTIONS: usize = 64;
+
+/// A call counted against [`MAX_CONNECTIONS`] until dropped. See [`admit`].
+struct Admission;
+
+impl Drop for Admission {
+    fn drop(&mut self) {
+        IN_FLIGHT.fetch_sub(1, Relaxed);
+    }
+}
+
+/// Count a call against [`MAX_CONNECTIONS`] for as long as the returned [`Admission`] is held.
+///
+/// Produces a [`fault::unavailable`] error if that many calls are already being handled,
+/// so that a flood of requests is turned away rather than left to pile up.
+fn admit() -> Result<Admission> {
+    let max = MAX_CONNECTIONS.load(Relaxed);
+    if IN_FLIGHT.fetch_add(1, Relaxed) >= max {
+        IN_FLIGHT.fetch_sub(1, Relaxed);
+        return Err(fault::unavailable(format_args!(
+            "server is busy handling {max} calls; try again later"
+        )));
+    }
+    Ok(Admission)
+}
+
+/// Register a function that only reads data, or none at all.
+///
+/// Calls are rejected if too many are already being handled. See [`admit`].
+fn register_simple<Treq, Tres>(
+    server: &mut Server,
+    name: &'static str,
+    handler: impl Fn(Treq) -> Result<Tres> + Send + Sync + 'static,
+) where
+    Treq: DeserializeOwned + 'static,
+    Tres: Serialize + 'static,
+{
+    server.register_value(name, move |params| {
+        let _admission = admit()?;
+        call_simple(&handler, params)
+    });
+}
+
+/// Produces a [`fault::forbidden`] error if the server is [read-only](READ_ONLY).
+fn check_writable(name: &str) -> Result<()> {
+    if READ_ONLY.load(Relaxed) {
+        return Err(fault::forbidden(format_args!(
+            "`{name}` is not allowed while the server is read-only"
+        )));
+    }
+    Ok(())
+}
+
+/// Like [`call_audited`], but rejected without calling `call` if the server is [read-only](READ_ONLY).
+/// Rejected calls are still recorded.
+fn call_mutating(
+    name: &'static str,
+    params: Params,
+    call: impl FnOnce(Params) -> Response,
+) -> Response {
+    call_audited(name, params, |params| {
+        check_writable(name)?;
+        call(params)
+    })
+}
+
+/// Register an `add_*` function so that it accepts an idempotency key.
+/// See [`call_idempotent`].
+///
+/// Calls are recorded in the [audit log](open_audit_log), and rejected if the server is [read-only](READ_ONLY).
+fn register_idempotent<Treq, Tres>(
+    server: &mut Server,
+    name: &'static str,
+    handler: fn(Treq) -> Result<Tres>,
+) where
+    Treq: DeserializeOwned + 'static,
+    Tres: Serialize + 'static,
+{
+    server.register_value(name, move |params| {
+        let _admission = admit()?;
+        call_mutating(name, params, |params| {
+            call_idempotent(name, handler, params)
+        })
+    });
+}
+
+/// Register a function that mutates backend data, so that its calls are recorded in the
+/// [audit log](open_audit_log) and rejected if the server is [read-only](READ_ONLY).
+fn register_mutating<Treq, Tres>(
+    server: &mut Server,
+    name: &'static str,
+    handler: fn(Treq) -> Result<Tres>,
+) where
+    Treq: DeserializeOwned + 'static,
+    Tres: Serialize + 'static,
+{
+    server.register_value(name, move |params| {
+        let _admission = admit()?;
+        call_mutating(name, params, |params| call_simple(handler, params))
+    });
+}
+
+/// Register a function that controls the server rather than its data,
+/// so that it is rejected if the server is [read-only](READ_ONLY).
+fn register_control<Treq, Tres>(
+    server: &mut Server,
+    name: &'static str,
+    handler: fn(Treq) -> Result<Tres>,
+) where
+    Treq: DeserializeOwned + 'static,
+    Tres: Serialize + 'static,
+{
+    server.register_value(name, move |params| {
+        let _admission = admit()?;
+        check_writable(name)?;
+        call_simple(handler, params)
+    });
+}
+
+/// Adapt an infallible function to the signature expected by [`register_simple`].
+#[inline]
+fn infallible<T, R>(f: fn(T) -> R) -> impl Fn(T) -> Result<R> {
+    move |x| Ok(f(x))
+}
+
+pub(crate) fn register(server: &mut Server) {
+    register_simple(server, "pat_starts_with", infallible(Pattern::starts_with));
+    register_simple(server, "pat_ends_with", infallible(Pattern::ends_with));
+    register_simple(server, "pat_contains", infallible(Pattern::contains));
+    register_simple(server, "pat_exactly", infallible(Pattern::exactly));
+    register_simple(server, "pat_regex", Pattern::regex);
+
+    register_idempotent(server, "add_rules", add_rules);
+    register_idempotent(server, "add_slots", add_slots);
+    register_idempotent(server, "add_templates", add_templates);
+    register_idempotent(server, "add_tasks", add_tasks);
+    register_idempotent(server, "add_tasks_unique", add_tasks_unique);
+    register_idempotent(server, "add_users", add_users);
+
+    register_simple(server, "get_rules", get_rules);
+    register_simple(server, "get_rules_for", get_rules_for);
+    register_simple(server, "get_all_rules", get_all_rules);
+    register_simple(server, "get_slots", get_slots);
+    register_simple(server, "get_templates", get_templates);
+    register_simple(server, "get_tasks", get_tasks);
+    register_simple(server, "get_users", get_users);
+    register_simple(server, "get_rule_order", get_rule_order);
+    register_simple(server, "active_slots", active_slots);
+    register_simple(server, "blocking_tasks", blocking_tasks);
+    register_simple(server, "dependency_path", dependency_path);
+    register_simple(
+        server,
+        "schedulable_tasks_for_slot",
+        schedulable_tasks_for_slot,
+    );
+    register_simple(server, "dep_graph_edges", dep_graph_edges);
+    register_simple(server, "critical_path", critical_path);
+
+    // rules can be mutated through `availability` field of `mut_users`
+    register_mutating(server, "mut_slots", mut_slots);
+    register_mutating(server, "mut_slots_where", mut_slots_where);
+    register_mutating(server, "duplicate_slot", duplicate_slot);
+    register_mutating(server, "instantiate_template", instantiate_template);
+    register_mutating(server, "mut_tasks", mut_tasks);
+    register_mutating(server, "mut_users", mut_users);
+
+    register_mutating(server, "reorder_rules", reorder_rules);
+    register_mutating(server, "normalize_user_rules", normalize_user_rules);
+    register_mutating(server, "grant_skill", grant_skill);
+    register_simple(server, "qualified_users", qualified_users);
+    register_mutating(server, "merge_users", merge_users);
+
+    register_mutating(server, "pop_rules", pop_rules);
+    register_mutating(server, "pop_slots", pop_slots);
+    register_mutating(server, "pop_tasks", pop_tasks);
+    register_mutating(server, "pop_users", pop_users);
+
+    register_simple(server, "save_slots", save_slots);
+    register_simple(server, "save_tasks", save_tasks);
+    register_simple(server, "save_users", save_users);
+    register_simple(server, "export_all", export_all);
+
+    register_mutating(server, "load_slots", load_slots);
+    register_mutating(server, "load_tasks", load_tasks);
+    register_mutating(server, "load_users", load_users);
+    register_mutating(server, "import_all", import_all);
+
+    register_mutating(server, "wipe_slots", wipe_slots);
+    register_mutating(server, "wipe_tasks", wipe_tasks);
+    register_mutating(server, "wipe_users", wipe_users);
+    register_mutating(server, "reset", reset);
+    register_mutating(server, "purge_past", purge_past);
+
+    register_simple(server, "generate_schedule", generate_schedule);
+    register_simple(server, "export_schedule_csv", export_schedule_csv);
+    register_simple(server, "find_double_bookings", find_double_bookings);
+    register_simple(server, "suggest_slots", suggest_slots);
+    register_simple(server, "explain_slot", explain_slot);
+    register_simple(server, "coverage_report", coverage_report);
+    register_simple(server, "get_stats", get_stats);
+    register_simple(server, "list_hard_constraints", list_hard_constraints);
+    register_mutating(server, "pin_assignment", pin_assignment);
+    register_mutating(server, "unpin_assignment", unpin_assignment);
+    register_simple(server, "check_assignment", check_assignment);
+
+    register_simple(server, "changes_since", changes_since);
+
+    register_simple(server, "echo_rule", echo::<PyRule>);
+    register_simple(server, "echo_slot", echo::<PySlot>);
+    register_simple(server, "echo_task", echo::<PyTask>);
+    register_simple(server, "echo_user", echo::<PyUser>);
+    register_simple(server, "echo_pattern", echo::<Pattern>);
+    register_simple(server, "echo_rule_filter", echo::<RuleFilter>);
+    register_simple(server, "echo_slot_filter", echo::<SlotFilter>);
+    register_simple(server, "echo_task_filter", echo::<TaskFilter>);
+    register_simple(server, "echo_user_filter", echo::<UserFilter>);
+
+    register_control(server, "quit", quit);
+    register_control(server, "shutdown_after", shutdown_after);
+    register_control(server, "cancel_shutdown", cancel_shutdown);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::{Mutex, MutexGuard};
+
+    /// Tests share the global backend data, so they must not run concurrently.
+    fn serial() -> MutexGuard<'static, ()> {
+        static LOCK: Mutex<()> = Mutex::new(());
+        let guard = LOCK.lock();
+        wipe_slots(()).unwrap();
+        wipe_tasks(()).unwrap();
+        wipe_users(()).unwrap();
+        guard
+    }
+
+    fn py_task(title: &str) -> PyTask {
+        PyTask {
+            title: title.to_string(),
+            desc: None,
+            deadline: None,
+            soft_deadline: None,
+            hard_deadline: None,
+            deadline_offset: None,
+            awaiting: None,
+            preferred_users: None,
+        }
+    }
+
+    #[test]
+    fn test_export_schedule_csv() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            4578: "bob" {
+                0: 4/12/2025 @ 6:30 - 6/12/2025 @ 7:30 | 1.0,
+            },
+            4753: "lisa" {
+                1: 4/12/2025 @ 5:30 - 6/12/2025 @ 6:30 | 1.0,
+            },
+            2773: "jones" {
+                2: 4/12/2025 @ 5:30 - 6/12/2025 @ 7:30 | 1.0,
+            },
+        };
+        **SLOTS.write() = slots! {
+            0: 4/12/2025 @ 5:30 - 6/12/2025 @ 6:30 [2] | "a",
+            1: 4/12/2025 @ 6:30 - 6/12/2025 @ 7:30 [2] | "b",
+        };
+        **TASKS.write() = crate::tasks! {
+            0: "stock shelves" {},
+            1: "count register" {},
+            2: "mop floors" [4/12/2025] {},
+        };
+        SLOTS.write().get_mut(&SlotId(0)).unwrap().preferred_tasks =
+            TaskSet::from_iter([TaskId(0), TaskId(2)]);
+        PINNED.write().tasks = SlotMap::from_iter([(SlotId(0), TaskSet::from_iter([TaskId(1)]))]);
+
+        let csv = export_schedule_csv(()).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("slot,start,end,user,tasks"));
+        let rows = lines
+            .map(|line| {
+                let fields = line.split(',').collect::<Vec<_>>();
+                (fields[0], fields[3], fields[4])
+            })
+            .collect::<Vec<_>>();
+        let worked = "count register; stock shelves";
+        assert_eq!(
+            rows,
+            [
+                ("a", "jones", worked),
+                ("a", "lisa", worked),
+                ("b", "bob", ""),
+                ("b", "jones", ""),
+            ],
+            "should have one row per assignment, listing the tasks worked before their deadlines"
+        );
+    }
+
+    #[test]
+    fn test_add_rules_rejects_duplicate_users() {
+        use xml_rpc::{Value, from_params};
+
+        let request = |keys: &[&str]| {
+            vec![Value::Struct(
+                keys.iter()
+                    .map(|key| (key.to_string(), Value::Array(Vec::new())))
+                    .collect(),
+            )]
+        };
+
+        assert!(
+            from_params::<UniqueKeys<UserId, Vec<PyRule>>>(request(&["1", "2"])).is_ok(),
+            "distinct users should be accepted"
+        );
+        assert!(
+            from_params::<UniqueKeys<UserId, Vec<PyRule>>>(request(&["1", "01"])).is_err(),
+            "the same user provided twice should be rejected rather than merged"
+        );
+    }
+
+    #[test]
+    fn test_active_slots() {
+        let _guard = serial();
+        **SLOTS.write() = slots! {
+            0: 4/12/2025 @ 5:30 - 4/12/2025 @ 6:30 | "a",
+            1: 4/12/2025 @ 6:30 - 4/12/2025 @ 7:30 | "b",
+        };
+
+        let active = active_slots(datetime!(4/12/2025 @ 6:30)).unwrap();
+        assert_eq!(
+            active.into_keys().collect::<Vec<_>>(),
+            [SlotId(1)],
+            "only the slot starting at that instant should be active"
+        );
+    }
+
+    #[test]
+    fn test_add_tasks_idempotency_key() {
+        let _guard = serial();
+        let request = || {
+            vec![
+                Value::Array(into_params(&vec![py_task("wash dishes")]).unwrap()),
+                Value::String("retry-me".to_string()),
+            ]
+        };
+
+        let first = call_idempotent("add_tasks", add_tasks, request()).unwrap();
+        let second = call_idempotent("add_tasks", add_tasks, request()).unwrap();
+        assert_eq!(
+            first, second,
+            "a retried request should return the same ids"
+        );
+        assert_eq!(
+            TASKS.read().len(),
+            1,
+            "a retried request should not add a task"
+        );
+
+        call_idempotent("add_tasks", add_tasks, request()[..1].to_vec()).unwrap();
+        assert_eq!(
+            TASKS.read().len(),
+            2,
+            "requests without a key are never deduplicated"
+        );
+    }
+
+    #[test]
+    fn test_reset_during_keyed_add() {
+        let _guard = serial();
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for i in 0..200 {
+                    let request = vec![
+                        Value::Array(into_params(&vec![py_task("a")]).unwrap()),
+                        Value::String(format!("key-{i}")),
+                    ];
+                    call_idempotent("add_tasks", add_tasks, request).unwrap();
+                }
+            });
+            scope.spawn(|| {
+                for _ in 0..200 {
+                    reset(()).unwrap();
+                }
+            });
+        });
+        reset(()).unwrap();
+    }
+
+    #[test]
+    fn test_load_nonexistent_is_not_found() {
+        let _guard = serial();
+        let e = load_tasks(PathBuf::from("./this/file/does/not/exist.csv")).unwrap_err();
+        assert_eq!(e.code, fault::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_regex_is_unprocessable() {
+        let e = Pattern::regex("(".to_string()).unwrap_err();
+        assert_eq!(e.code, fault::UNPROCESSABLE);
+    }
+
+    #[test]
+    fn test_reorder_rules() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "bob" {
+                0: 4/12/2025 @ 6:30 - 6/12/2025 @ 7:30 | 1.0,
+                1: 4/13/2025 @ 6:30 - 6/13/2025 @ 7:30 | 0.5,
+            },
+        };
+        assert_eq!(get_rule_order(UserId(0)).unwrap(), [RuleId(0), RuleId(1)]);
+
+        reorder_rules((UserId(0), vec![RuleId(1), RuleId(0)])).unwrap();
+        assert_eq!(get_rule_order(UserId(0)).unwrap(), [RuleId(1), RuleId(0)]);
+
+        assert_eq!(
+            reorder_rules((UserId(0), vec![RuleId(1), RuleId(1)]))
+                .unwrap_err()
+                .code,
+            fault::UNPROCESSABLE,
+            "order with a duplicate should be rejected"
+        );
+        assert_eq!(
+            reorder_rules((UserId(0), vec![RuleId(1)]))
+                .unwrap_err()
+                .code,
+            fault::UNPROCESSABLE,
+            "order with a missing rule should be rejected"
+        );
+        assert_eq!(
+            reorder_rules((UserId(1), vec![])).unwrap_err().code,
+            fault::NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn test_get_rules_from_users_macro() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "bob" {
+                7: 4/12/2025 @ 6:30 - 6/12/2025 @ 7:30 | 0.5,
+            },
+        };
+
+        let rules = get_rules(UserMap::from_iter([(
+            UserId(0),
+            RuleFilter {
+                ids: None,
+                min_pref: None,
+                max_pref: None,
+            },
+        )]))
+        .unwrap();
+        let rule = &rules[&UserId(0)][&RuleId(7)];
+        assert_eq!(
+            rule.include.as_slice(),
+            [time_interval!(4/12/2025 @ 6:30 - 6/12/2025 @ 7:30)]
+        );
+        assert_eq!(rule.preference, 0.5);
+    }
+
+    #[test]
+    fn test_mut_users_grows_availability() {
+        let _guard = serial();
+        let user_id = add_users(vec![PyUser {
+            name: "bob".to_string(),
+        }])
+        .unwrap()[0];
+
+        let result = mut_users(UserMap::from_iter([(
+            user_id,
+            UserDelta {
+                name: None,
+                availability: SetDelta {
+                    create: vec![PyRule {
+                        include: smallvec::smallvec![time_interval!(4/12/2025 - 4/13/2025)],
+                        exclude: SmallVec::new(),
+                        repeat: None,
+                        preference: 1.0,
+                        set_at: None,
+                    }],
+                    ..Default::default()
+                },
+                default_availability: None,
+                user_prefs: SetDelta::default(),
+                skills: SetDelta::default(),
+            },
+        )]))
+        .unwrap();
+
+        assert!(result.warnings.is_empty());
+        let result = result.value;
+        assert!(result.failed.is_empty());
+        let [rule_id] = result.created[&user_id][..] else {
+            panic!("expected one created rule, got {:?}", result.created);
+        };
+        let users = USERS.read();
+        assert!(users[&user_id].availability.contains_key(&rule_id));
+        assert_eq!(users[&user_id].rule_order, [rule_id]);
+    }
+
+    #[test]
+    fn test_mut_users_clamps_preference() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "bob" {},
+        };
+        let rule = |preference| PyRule {
+            include: smallvec::smallvec![time_interval!(4/12/2025 - 4/13/2025)],
+            exclude: SmallVec::new(),
+            repeat: None,
+            preference,
+            set_at: None,
+        };
+        let delta = UserDelta {
+            name: None,
+            availability: SetDelta {
+                create: vec![rule(0.5), rule(-3.0)],
+                ..Default::default()
+            },
+            default_availability: None,
+            user_prefs: SetDelta::default(),
+            skills: SetDelta::default(),
+        };
+
+        let result = mut_users(UserMap::from_iter([(UserId(0), delta)])).unwrap();
+        let [warning] = result.warnings.as_slice() else {
+            panic!("expected one warning, got {:?}", result.warnings);
+        };
+        assert_eq!(warning.index, 1);
+        assert_eq!(warning.id.as_deref(), Some("u.0"));
+        let created = &result.value.created[&UserId(0)];
+        assert_eq!(created.len(), 2, "the clamped rule should still be created");
+        let users = USERS.read();
+        let mut prefs = Vec::from_iter(
+            created
+                .iter()
+                .map(|id| users[&UserId(0)].availability[id].pref),
+        );
+        prefs.sort_by(|a, b| a.0.total_cmp(&b.0));
+        assert_eq!(prefs, [Preference::MIN, Preference(0.5)]);
+    }
+
+    #[test]
+    fn test_mut_users_drops_zero_preference() {
+        let _guard = serial();
+        let ids = add_users(vec![
+            PyUser {
+                name: "bob".to_string(),
+            },
+            PyUser {
+                name: "lisa".to_string(),
+            },
+        ])
+        .unwrap();
+        let (bob, lisa) = (ids[0], ids[1]);
+
+        let set_pref = |pref| {
+            mut_users(UserMap::from_iter([(
+                bob,
+                UserDelta {
+                    name: None,
+                    availability: SetDelta::default(),
+                    default_availability: None,
+                    user_prefs: SetDelta {
+                        create: vec![(lisa, Preference(pref))],
+                        ..Default::default()
+                    },
+                    skills: SetDelta::default(),
+                },
+            )]))
+            .unwrap()
+        };
+
+        set_pref(0.5);
+        assert_eq!(
+            USERS.read()[&bob].user_prefs.get(&lisa),
+            Some(&Preference(0.5))
+        );
+        set_pref(0.0);
+        assert_eq!(
+            USERS.read()[&bob].user_prefs.get(&lisa),
+            None,
+            "a 0.0 preference should be removed rather than stored"
+        );
+    }
+
+    #[test]
+    fn test_get_slots_rejects_inverted_ranges() {
+        let err = get_slots(SlotFilter {
+            starting_after: Some(datetime!(4/8/2025)),
+            starting_before: Some(datetime!(4/5/2025)),
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert_eq!(err.code, fault::BAD_REQUEST);
+        assert!(
+            err.message.contains("`starting_after`") && err.message.contains("`starting_before`"),
+            "error should name the inverted fields: {}",
+            err.message
+        );
+
+        let err = get_slots(SlotFilter {
+            ending_after: Some(datetime!(4/8/2025)),
+            ending_before: Some(datetime!(4/5/2025)),
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert_eq!(err.code, fault::BAD_REQUEST);
+        assert!(
+            err.message.contains("`ending_after`") && err.message.contains("`ending_before`"),
+            "error should name the inverted fields: {}",
+            err.message
+        );
+
+        let err = get_slots(SlotFilter {
+            min_staff_min: Some(3),
+            min_staff_max: Some(1),
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert_eq!(err.code, fault::BAD_REQUEST);
+        assert!(
+            err.message.contains("`min_staff_min` (3)")
+                && err.message.contains("`min_staff_max` (1)"),
+            "error should name the inverted fields: {}",
+            err.message
+        );
+
+        assert!(
+            get_slots(SlotFilter {
+                min_staff_min: Some(2),
+                min_staff_max: Some(2),
+                ..Default::default()
+            })
+            .is_ok(),
+            "an empty range is not inverted"
+        );
+    }
+
+    #[test]
+    fn test_get_tasks_rejects_inverted_range() {
+        let err = get_tasks(TaskFilter {
+            deadline_after: Some(datetime!(4/8/2025)),
+            deadline_before: Some(datetime!(4/5/2025)),
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert_eq!(err.code, fault::BAD_REQUEST);
+        assert!(
+            err.message.contains("`deadline_after`") && err.message.contains("`deadline_before`"),
+            "error should name the inverted fields: {}",
+            err.message
+        );
+    }
+
+    #[test]
+    fn test_get_tasks_fields() {
+        let _guard = serial();
+        **TASKS.write() = crate::tasks! {
+            0: "buy shelves" [5/1/2025] {},
+            1: "stock shelves" [5/2/2025] { 0 },
+        };
+
+        let tasks = get_tasks(TaskFilter {
+            fields: Some(vec![TaskField::Title]),
+            ..Default::default()
+        })
+        .unwrap();
+        let value = serde_json::to_value(&tasks[&TaskId(1)]).unwrap();
+        assert_eq!(value, serde_json::json!({ "title": "stock shelves" }));
+        for field in ["desc", "deadline", "awaiting"] {
+            assert!(value.get(field).is_none(), "{field} should be omitted");
+        }
+
+        let tasks = get_tasks(TaskFilter::default()).unwrap();
+        assert!(matches!(tasks[&TaskId(1)], Projected::Full(_)));
+
+        TASKS.write().clear();
+    }
+
+    #[test]
+    fn test_get_rules_for() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "bob" {
+                0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 | 0.5,
+                1: 4/13/2025 @ 9:00 - 4/13/2025 @ 17:00 | -0.5,
+            },
+            1: "sue" {
+                2: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 | 1.0,
+            },
+            2: "joe" {
+                3: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 | 1.0,
+            },
+        };
+        let filter = || RuleFilter {
+            min_pref: Some(0.0),
+            ..Default::default()
+        };
+
+        let rules = get_rules_for((vec![UserId(0), UserId(1), UserId(9)], filter())).unwrap();
+        let ids = |user| rules[&UserId(user)].keys().copied().collect::<RuleSet>();
+        assert_eq!(
+            rules.len(),
+            2,
+            "missing and unlisted users should be left out"
+        );
+        assert_eq!(ids(0), RuleSet::from_iter([RuleId(0)]));
+        assert_eq!(ids(1), RuleSet::from_iter([RuleId(2)]));
+
+        let all = get_rules_for((Vec::new(), filter())).unwrap();
+        assert_eq!(all.len(), 3, "an empty list should mean every user");
+    }
+
+    #[test]
+    fn test_get_rules_rejects_inverted_range() {
+        let err = get_rules(UserMap::from_iter([(
+            UserId(0),
+            RuleFilter {
+                min_pref: Some(1.0),
+                max_pref: Some(-1.0),
+                ..Default::default()
+            },
+        )]))
+        .unwrap_err();
+        assert_eq!(err.code, fault::BAD_REQUEST);
+        assert!(
+            err.message.contains("`min_pref` (1)") && err.message.contains("`max_pref` (-1)"),
+            "error should name the inverted fields: {}",
+            err.message
+        );
+    }
+
+    #[test]
+    fn test_delta_update() {
+        let mut value = 1;
+        assert_eq!(None.apply(&mut value), DeltaOutcome::Applied);
+        assert_eq!(value, 1, "None should keep the existing value");
+        let mut delta = Some(2);
+        assert_eq!(delta.apply(&mut value), DeltaOutcome::Applied);
+        assert_eq!(value, 2, "Some should replace the existing value");
+        assert_eq!(delta, None, "applied update should be consumed");
+    }
+
+    #[test]
+    fn test_delta_key_set() {
+        let mut deps = TaskSet::from_iter([TaskId(1), TaskId(2)]);
+        let mut delta = KeySetDelta {
+            delete: TaskSet::from_iter([TaskId(2), TaskId(9)]),
+            create: vec![TaskId(3)],
+        };
+        assert_eq!(delta.apply(&mut deps), DeltaOutcome::Partial);
+        assert_eq!(deps, TaskSet::from_iter([TaskId(1), TaskId(3)]));
+        assert_eq!(
+            delta.delete,
+            TaskSet::from_iter([TaskId(9)]),
+            "only the missing key should be left behind"
+        );
+    }
+
+    #[test]
+    fn test_delta_set() {
+        let mut prefs =
+            UserMap::from_iter([(UserId(1), Preference(1.0)), (UserId(2), Preference(2.0))]);
+        let mut delta = SetDelta {
+            delete: FxHashSet::from_iter([UserId(1)]),
+            create: vec![(UserId(3), Preference(3.0))],
+            update: FxHashMap::from_iter([(UserId(2), Preference(-2.0))]),
+        };
+        assert_eq!(delta.apply(&mut prefs), DeltaOutcome::Applied);
+        assert_eq!(
+            prefs,
+            UserMap::from_iter([(UserId(2), Preference(-2.0)), (UserId(3), Preference(3.0))])
+        );
+
+        let mut delta = SetDelta::<UserId, Preference> {
+            update: FxHashMap::from_iter([(UserId(9), Preference(9.0))]),
+            ..Default::default()
+        };
+        assert_eq!(delta.apply(&mut prefs), DeltaOutcome::Partial);
+        assert!(
+            delta.update.contains_key(&UserId(9)),
+            "the missing key should be left behind"
+        );
+    }
+
+    #[test]
+    fn test_delta_rule() {
+        let mut rule = Rule {
+            include: smallvec::smallvec![
+                time_interval!(4/12/2025 - 4/13/2025),
+                time_interval!(4/14/2025 - 4/15/2025),
+            ],
+            ..crate::rule_lit! { 0: 4/1/2025 - 4/2/2025 | 1.0 }
+        };
+        let mut delta = RuleDelta {
+            include: SetDelta {
+                delete: FxHashSet::from_iter([0]),
+                create: vec![time_interval!(4/20/2025 - 4/21/2025)],
+                update: FxHashMap::from_iter([(2, time_interval!(4/16/2025 - 4/17/2025))]),
+            },
+            exclude: SetDelta {
+                create: vec![time_interval!(4/12/2025 - 4/13/2025)],
+                ..Default::default()
+            },
+            rep: None,
+            pref: Some(Preference(0.5)),
+            set_at: None,
+        };
+        assert_eq!(delta.apply(&mut rule), DeltaOutcome::Partial);
+        assert_eq!(
+            rule.include.as_slice(),
+            [
+                time_interval!(4/14/2025 - 4/15/2025),
+                time_interval!(4/20/2025 - 4/21/2025),
+            ],
+            "deleted interval should be removed and created interval appended"
+        );
+        assert_eq!(
+            rule.exclude.as_slice(),
+            [time_interval!(4/12/2025 - 4/13/2025)]
+        );
+        assert_eq!(rule.pref, Preference(0.5));
+        assert!(
+            delta.include.update.contains_key(&2),
+            "the out of range index should be left behind"
+        );
+    }
+
+    #[test]
+    fn test_delta_rule_set() {
+        let mut rules = crate::rules! {
+            1000: 4/1/2025 - 4/2/2025 | 1.0,
+            1001: 4/3/2025 - 4/4/2025 | 1.0,
+        };
+        let mut delta = SetDelta::<RuleId, RuleDelta, PyRule> {
+            delete: RuleSet::from_iter([RuleId(1000), RuleId(1009)]),
+            create: vec![PyRule {
+                include: smallvec::smallvec![time_interval!(4/5/2025 - 4/6/2025)],
+                exclude: SmallVec::new(),
+                repeat: None,
+                preference: 0.75,
+                set_at: None,
+            }],
+            update: FxHashMap::from_iter([(
+                RuleId(1001),
+                RuleDelta {
+                    include: SetDelta::default(),
+                    exclude: SetDelta::default(),
+                    rep: None,
+                    pref: Some(Preference(-1.0)),
+                    set_at: None,
+                },
+            )]),
+        };
+        assert_eq!(delta.apply(&mut rules), DeltaOutcome::Partial);
+        assert!(!rules.contains_key(&RuleId(1000)));
+        assert_eq!(rules[&RuleId(1001)].pref, Preference(-1.0));
+        assert_eq!(
+            rules.len(),
+            2,
+            "created rule should be added under a new id"
+        );
+        assert!(rules.values().any(|rule| rule.pref == Preference(0.75)));
+        assert_eq!(
+            delta.delete,
+            RuleSet::from_iter([RuleId(1009)]),
+            "only the missing key should be left behind"
+        );
+    }
+
+    #[test]
+    fn test_delta_slot_and_task() {
+        let mut slot = crate::slot_lit! { 0: 4/1/2025 - 4/2/2025 [1] | "a" };
+        let outcome = SlotDelta {
+            interval: None,
+            min_staff: Some(None),
+            max_staff: None,
+            preferred_tasks: None,
+            name: Some("b".to_string()),
+        }
+        .apply(&mut slot);
+        assert_eq!(outcome, DeltaOutcome::Applied);
+        assert_eq!((slot.min_staff, slot.name.as_str()), (None, "b"));
+
+        let mut task = crate::task_lit! { 0: "a" {} };
+        let outcome = TaskDelta {
+            title: Some("b".to_string()),
+            desc: None,
+            skills: SetDelta::default(),
+            deadline: None,
+            soft_deadline: None,
+            hard_deadline: None,
+            deadline_offset: None,
+            deps: KeySetDelta {
+                delete: TaskSet::from_iter([TaskId(9)]),
+                create: vec![TaskId(1)],
+            },
+            preferred_users: SetDelta::default(),
+        }
+        .apply(&mut task);
+        assert_eq!(outcome, DeltaOutcome::Partial);
+        assert_eq!(task.title, "b");
+        assert_eq!(task.deps, TaskSet::from_iter([TaskId(1)]));
+    }
+
+    #[test]
+    fn test_mut_tasks_clamps_preference() {
+        let _guard = serial();
+        **TASKS.write() = crate::tasks! {
+            0: "a" {},
+        };
+        TASKS.write().get_mut(&TaskId(0)).unwrap().preferred_users =
+            UserMap::from_iter([(UserId(1), Preference(0.5))]);
+        let delta = TaskDelta {
+            title: None,
+            desc: None,
+            skills: SetDelta::default(),
+            deadline: None,
+            soft_deadline: None,
+            hard_deadline: None,
+            deadline_offset: None,
+            deps: KeySetDelta::default(),
+            preferred_users: SetDelta {
+                delete: FxHashSet::default(),
+                create: vec![(UserId(0), 2.0), (UserId(2), f32::NEG_INFINITY)],
+                update: FxHashMap::from_iter([(UserId(1), -0.25)]),
+            },
+        };
+
+        let result = mut_tasks(TaskMap::from_iter([(TaskId(0), delta)])).unwrap();
+        assert!(result.value.is_empty());
+        assert_eq!(
+            TASKS.read()[&TaskId(0)].preferred_users,
+            UserMap::from_iter([
+                (UserId(0), Preference::MAX),
+                (UserId(1), Preference(-0.25)),
+                (UserId(2), Preference::NEG_INFINITY),
+            ])
+        );
+        let [warning] = result.warnings.as_slice() else {
+            panic!("expected one warning, got {:?}", result.warnings);
+        };
+        assert_eq!(warning.id.as_deref(), Some("t.0"));
+        assert!(warning.reason.contains("u.0"), "{}", warning.reason);
+    }
+
+    #[test]
+    fn test_mut_tasks_rejects_cycle() {
+        let _guard = serial();
+        **TASKS.write() = crate::tasks! {
+            0: "a" {},
+            1: "b" { 0 },
+        };
+        let delta = |create| TaskDelta {
+            title: None,
+            desc: None,
+            skills: SetDelta::default(),
+            deadline: None,
+            soft_deadline: None,
+            hard_deadline: None,
+            deadline_offset: None,
+            deps: KeySetDelta {
+                delete: TaskSet::default(),
+                create,
+            },
+            preferred_users: SetDelta::default(),
+        };
+
+        let e = mut_tasks(TaskMap::from_iter([(TaskId(0), delta(vec![TaskId(1)]))])).unwrap_err();
+        assert_eq!(e.code, fault::CONFLICT);
+        assert!(
+            e.message.contains("t.0") && e.message.contains("t.1"),
+            "{e:?}"
+        );
+        assert!(
+            TASKS.read()[&TaskId(0)].deps.is_empty(),
+            "nothing should change"
+        );
+
+        **TASKS.write() = crate::tasks! {
+            0: "a" {},
+            1: "b" {},
+        };
+        assert!(
+            mut_tasks(TaskMap::from_iter([(TaskId(0), delta(vec![TaskId(1)]))]))
+                .unwrap()
+                .value
+                .is_empty()
+        );
+        assert_eq!(
+            TASKS.read()[&TaskId(0)].deps,
+            TaskSet::from_iter([TaskId(1)])
+        );
+    }
+
+    #[test]
+    fn test_explain_slot() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "bob" {
+                0: 4/12/2025 - 4/14/2025 | f32::NEG_INFINITY,
+            },
+            1: "lisa" {
+                1: 4/12/2025 - 4/14/2025 | 0.5,
+                2: 4/11/2025 - 4/15/2025 | 1.0,
+            },
+            2: "jones" {
+                3: 4/1/2025 - 4/2/2025 | 1.0,
+            },
+        };
+        **SLOTS.write() = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 [1] | "a",
+        };
+
+        let explanation = explain_slot(SlotId(0)).unwrap();
+        let bob = &explanation.candidates[&UserId(0)];
+        assert!(!bob.available, "bob is only covered by a forbidding rule");
+        assert_eq!(bob.forbidden_by, [RuleId(0)]);
+        assert_eq!(bob.preference, None);
+        assert!(!bob.selected);
+
+        let lisa = &explanation.candidates[&UserId(1)];
+        assert!(lisa.available);
+        assert!(lisa.forbidden_by.is_empty());
+        assert_eq!(
+            lisa.preference,
+            Some(Preference(0.5)),
+            "effective preference should be the narrowest covering rule"
+        );
+        assert!(lisa.selected);
+
+        let jones = &explanation.candidates[&UserId(2)];
+        assert!(!jones.available, "jones has no rule covering the slot");
+        assert!(jones.forbidden_by.is_empty());
+        assert!(!jones.selected);
+
+        assert_eq!(explain_slot(SlotId(1)).unwrap_err().code, fault::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_pinned_user_survives_regeneration() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "bob" {
+                0: 4/12/2025 - 4/13/2025 | 0.1,
+            },
+            1: "lisa" {
+                1: 4/12/2025 - 4/13/2025 | 1.0,
+            },
+        };
+        **SLOTS.write() = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 [1] | "a",
+        };
+        let pin = PyPin {
+            slot: SlotId(0),
+            user: Some(UserId(0)),
+            task: None,
+        };
+
+        assert!(explain_slot(SlotId(0)).unwrap().candidates[&UserId(1)].selected);
+        pin_assignment(pin).unwrap();
+        for _ in 0..2 {
+            let explanation = explain_slot(SlotId(0)).unwrap();
+            assert!(
+                explanation.candidates[&UserId(0)].selected,
+                "bob is pinned to the slot"
+            );
+            assert!(
+                !explanation.candidates[&UserId(1)].selected,
+                "the pin already meets min_staff"
+            );
+        }
+
+        assert!(unpin_assignment(pin).unwrap());
+        assert!(!unpin_assignment(pin).unwrap(), "already unpinned");
+        assert!(explain_slot(SlotId(0)).unwrap().candidates[&UserId(1)].selected);
+
+        let missing = PyPin {
+            user: Some(UserId(9)),
+            ..pin
+        };
+        assert_eq!(pin_assignment(missing).unwrap_err().code, fault::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_load_auto_sniffs_format() {
+        let slots = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 [2] | "a",
+            1: 4/13/2025 @ 9:00 - 4/13/2025 @ 17:00 | "b",
+        };
+        let mut expected = slots.into_values().collect::<Vec<_>>();
+        expected.sort_by_key(|slot| slot.id.0);
+
+        let mut w = csv::Writer::from_writer(Vec::new());
+        for slot in &expected {
+            w.serialize(slot).unwrap();
+        }
+        let csv = w.into_inner().unwrap();
+        let json = serde_json::to_vec(&expected).unwrap();
+        let keyed = serde_json::to_vec(&FxHashMap::from_iter(
+            expected.iter().map(|slot| (slot.id.0.to_string(), slot)),
+        ))
+        .unwrap();
+
+        for (name, bytes) in [("csv", csv), ("json", json), ("keyed json", keyed)] {
+            let padded = [b"\n  ".as_slice(), &bytes].concat();
+            let mut loaded = load_auto::<Slot>(padded.as_slice()).unwrap();
+            loaded.sort_by_key(|slot| slot.id.0);
+            assert_eq!(loaded, expected, "{name} should load the same slots");
+        }
+    }
+
+    #[test]
+    fn test_check_assignment() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "bob" {
+                0: 4/12/2025 - 4/13/2025 | 1.0,
+            },
+            1: "lisa" {
+                1: 4/1/2025 - 4/2/2025 | 1.0,
+            },
+            2: "jones" {
+                2: 4/12/2025 - 4/13/2025 | 1.0,
+            },
+        };
+        **SLOTS.write() = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 | "a",
+        };
+
+        let check = check_assignment((SlotId(0), UserId(0))).unwrap();
+        assert!(check.ok && check.available, "bob is available");
+        assert!(check.reasons.is_empty());
+
+        let check = check_assignment((SlotId(0), UserId(1))).unwrap();
+        assert!(!check.ok && !check.available, "lisa is not available");
+        assert_eq!(check.reasons, ["lisa is not available for this slot"]);
+
+        SLOTS.write().get_mut(&SlotId(0)).unwrap().max_staff = NonZeroUsize::new(1);
+        pin_assignment(PyPin {
+            slot: SlotId(0),
+            user: Some(UserId(0)),
+            task: None,
+        })
+        .unwrap();
+        let check = check_assignment((SlotId(0), UserId(2))).unwrap();
+        assert!(check.available);
+        assert!(
+            !check.ok && check.over_capacity,
+            "bob already fills the slot"
+        );
+        assert!(
+            check_assignment((SlotId(0), UserId(0))).unwrap().ok,
+            "a pinned user does not count against themself"
+        );
+
+        USERS
+            .write()
+            .get_mut(&UserId(2))
+            .unwrap()
+            .user_prefs
+            .insert(UserId(0), Preference::NEG_INFINITY);
+        let check = check_assignment((SlotId(0), UserId(2))).unwrap();
+        assert_eq!(check.conflicts_with, UserSet::from_iter([UserId(0)]));
+
+        assert_eq!(
+            check_assignment((SlotId(0), UserId(9))).unwrap_err().code,
+            fault::NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn test_py_schedule_round_trip() {
+        let schedule = Schedule(SlotMap::from_iter([
+            (SlotId(1), UserSet::from_iter([UserId(2), UserId(0)])),
+            (SlotId(0), UserSet::from_iter([UserId(1)])),
+            (SlotId(2), UserSet::default()),
+        ]));
+
+        let py_schedule = PySchedule::from(&schedule);
+        assert_eq!(
+            py_schedule,
+            PySchedule(vec![
+                PyAssignment {
+                    slot: SlotId(0),
+                    users: vec![UserId(1)],
+                },
+                PyAssignment {
+                    slot: SlotId(1),
+                    users: vec![UserId(0), UserId(2)],
+                },
+                PyAssignment {
+                    slot: SlotId(2),
+                    users: vec![],
+                },
+            ])
+        );
+        assert_eq!(Schedule::from(py_schedule).0, schedule.0);
+    }
+
+    #[test]
+    fn test_slot_and_py_slot_share_shape() {
+        let slot = crate::slot_lit! { 0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 [2] | "a" };
+        let mut persisted = serde_json::to_value(&slot).unwrap();
+        assert_eq!(
+            persisted.as_object_mut().unwrap().remove("id"),
+            Some(serde_json::json!(SlotId(0)))
+        );
+        let (_, py_slot) = <(SlotId, PySlot)>::from(&slot);
+        assert_eq!(persisted, serde_json::to_value(&py_slot).unwrap());
+
+        let round_trip = serde_json::from_str::<Slot>(&serde_json::to_string(&slot).unwrap());
+        assert_eq!(round_trip.unwrap(), slot);
+    }
+
+    #[test]
+    fn test_duplicate_slot() {
+        let _guard = serial();
+        **SLOTS.write() = slots! {
+            0: 4/7/2025 @ 9:00 - 4/7/2025 @ 11:00 [2] | "morning",
+        };
+        SlotId::store(1);
+
+        let onto = [
+            datetime!(4/14/2025 @ 9:00),
+            datetime!(4/21/2025 @ 13:30),
+            datetime!(4/28/2025 @ 9:00),
+        ];
+        let ids = duplicate_slot((SlotId(0), onto.to_vec())).unwrap();
+        assert_eq!(ids.len(), 3);
+
+        let slots = SLOTS.read();
+        assert_eq!(slots.len(), 4, "original should be kept");
+        for (id, start) in ids.iter().zip(onto) {
+            let copy = &slots[id];
+            assert_eq!(copy.interval.start, start);
+            assert_eq!(
+                copy.interval.end - copy.interval.start,
+                chrono::TimeDelta::hours(2)
+            );
+            assert_eq!(copy.min_staff, slots[&SlotId(0)].min_staff);
+            assert_eq!(copy.name, "morning");
+        }
+        drop(slots);
+
+        assert_eq!(
+            duplicate_slot((SlotId(9), onto.to_vec())).unwrap_err().code,
+            fault::NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn test_purge_past_keeps_depended_on_tasks() {
+        let _guard = serial();
+        **SLOTS.write() = slots! {
+            0: 4/1/2025 @ 9:00 - 4/1/2025 @ 11:00 | "past",
+            1: 5/1/2025 @ 9:00 - 5/1/2025 @ 11:00 | "future",
+        };
+        **TASKS.write() = crate::tasks! {
+            0: "past, depended on" [4/1/2025] {},
+            1: "future" [5/1/2025] { 0 },
+        };
+
+        let result = purge_past(datetime!(4/15/2025)).unwrap();
+        assert_eq!(result.slots, SlotSet::from_iter([SlotId(0)]));
+        assert!(result.tasks.is_empty());
+        assert_eq!(result.retained, TaskSet::from_iter([TaskId(0)]));
+        assert!(!SLOTS.read().contains_key(&SlotId(0)));
+        assert!(SLOTS.read().contains_key(&SlotId(1)));
+        assert_eq!(TASKS.read().len(), 2);
+    }
+
+    #[test]
+    fn test_add_slots_reports_failing_index() {
+        let _guard = serial();
+        let to_add = vec![
+            PySlot {
+                start: datetime!(4/7/2025 @ 9:00),
+                end: datetime!(4/7/2025 @ 11:00),
+                min_staff: None,
+                max_staff: None,
+                preferred_tasks: None,
+                name: None,
+            },
+            PySlot {
+                start: datetime!(4/8/2025 @ 11:00),
+                end: datetime!(4/8/2025 @ 9:00),
+                min_staff: None,
+                max_staff: None,
+                preferred_tasks: None,
+                name: None,
+            },
+        ];
+        let e = add_slots(to_add).unwrap_err();
+        assert_eq!(e.code, fault::UNPROCESSABLE);
+        let detail: fault::BatchFault = serde_json::from_str(&e.message).unwrap();
+        assert_eq!(detail.index, 1);
+        assert_eq!(detail.id, None);
+        assert!(SLOTS.read().is_empty(), "no slots should be added");
+    }
+
+    #[test]
+    fn test_reset() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "alice" {
+                0: 4/12/2025 @ 5:30 - 6/12/2025 @ 6:30 | 1.0,
+            },
+        };
+        **SLOTS.write() = slots! {
+            0: 4/12/2025 @ 5:30 - 6/12/2025 @ 6:30 [1] | "a",
+        };
+        **TASKS.write() = crate::tasks! {
+            0: "a" [5/1/2025] {},
+        };
+        SlotId::store(1);
+        TaskId::store(1);
+        UserId::store(1);
+        RuleId::store(1);
+        SkillId::store(1);
+        pin_assignment(PyPin {
+            slot: SlotId(0),
+            user: Some(UserId(0)),
+            task: None,
+        })
+        .unwrap();
+
+        reset(()).unwrap();
+        assert!(SLOTS.read().is_empty());
+        assert!(TASKS.read().is_empty());
+        assert!(USERS.read().is_empty());
+        assert_eq!(**PINNED.read(), Pins::default());
+        assert_eq!(SlotId::next(), Some(SlotId(0)));
+        assert_eq!(TaskId::next(), Some(TaskId(0)));
+        assert_eq!(UserId::next(), Some(UserId(0)));
+        assert_eq!(RuleId::next(), Some(RuleId(0)));
+        assert_eq!(SkillId::next(), Some(SkillId(0)));
+    }
+
+    #[test]
+    fn test_restore_state() {
+        let _guard = serial();
+        reset(()).unwrap();
+        **USERS.write() = users! {
+            0: "alice" {
+                0: 4/12/2025 @ 5:30 - 6/12/2025 @ 6:30 | 1.0,
+            },
+        };
+        **SLOTS.write() = slots! {
+            0: 4/12/2025 @ 5:30 - 6/12/2025 @ 6:30 [1] | "a",
+        };
+        **TASKS.write() = crate::tasks! {
+            0: "a" [5/1/2025] {},
+        };
+        SlotId::store(1);
+        TaskId::store(1);
+        UserId::store(1);
+        RuleId::store(1);
+        SkillId::store(3);
+        pin_assignment(PyPin {
+            slot: SlotId(0),
+            user: Some(UserId(0)),
+            task: None,
+        })
+        .unwrap();
+        let slots = SlotMap::clone(&SLOTS.read());
+        let tasks = TaskMap::clone(&TASKS.read());
+        assert_eq!(tasks.len(), 1);
+        let pins = Pins::clone(&PINNED.read());
+
+        let snapshot = snapshot_state();
+        reset(()).unwrap();
+        SlotId::store(7);
+        SkillId::store(7);
+        restore_state(snapshot);
+
+        assert_eq!(**SLOTS.read(), slots);
+        assert_eq!(TASKS.read()[&TaskId(0)].title, tasks[&TaskId(0)].title);
+        assert_eq!(**PINNED.read(), pins);
+        assert_eq!(USERS.read()[&UserId(0)].name, "alice");
+        assert_eq!(USERS.read()[&UserId(0)].availability.len(), 1);
+        assert_eq!(SlotId::next(), Some(SlotId(1)));
+        assert_eq!(TaskId::next(), Some(TaskId(1)));
+        assert_eq!(UserId::next(), Some(UserId(1)));
+        assert_eq!(RuleId::next(), Some(RuleId(1)));
+        assert_eq!(SkillId::next(), Some(SkillId(3)));
+        assert_eq!(SlotTemplateId::next(), Some(SlotTemplateId(0)));
+        reset(()).unwrap();
+    }
+
+    #[test]
+    fn test_grant_skill() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "alice" {},
+            1: "bob" {},
+        };
+        let skill = SkillId(0);
+
+        let granted = grant_skill((vec![UserId(0), UserId(1), UserId(2)], skill, 1.5)).unwrap();
+        assert_eq!(granted.value, UserSet::from_iter([UserId(2)]));
+        assert_eq!(granted.warnings, vec![]);
+        let users = USERS.read();
+        for id in [UserId(0), UserId(1)] {
+            assert_eq!(
+                users[&id].skills[&skill].proficiency,
+                Proficiency::try_from(1.5).unwrap(),
+                "user {id} should have been granted the skill"
+            );
+        }
+        drop(users);
+
+        assert_eq!(
+            grant_skill((vec![UserId(0)], skill, f32::INFINITY))
+                .unwrap_err()
+                .code,
+            fault::UNPROCESSABLE
+        );
+    }
+
+    #[test]
+    fn test_grant_skill_clamps() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "alice" {},
+        };
+        let skill = SkillId(0);
+
+        let granted = grant_skill((vec![UserId(0)], skill, -0.5)).unwrap();
+        assert!(
+            !USERS.read()[&UserId(0)].skills.contains_key(&skill),
+            "a proficiency clamped to zero should be compacted away"
+        );
+        let [warning] = granted.warnings.as_slice() else {
+            panic!("expected one warning, got {:?}", granted.warnings);
+        };
+        assert_eq!((warning.index, warning.id.as_deref()), (2, Some("k.0")));
+        assert!(warning.reason.contains("-0.5"), "{}", warning.reason);
+    }
+
+    #[test]
+    fn test_merge_users() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "bob" {
+                0: 4/7/2025 - 4/8/2025 | 1.0,
+            },
+            1: "robert" {
+                1: 4/9/2025 - 4/10/2025 | 1.0,
+            },
+            2: "lisa" {},
+        };
+        let (bob, robert, lisa) = (UserId(0), UserId(1), UserId(2));
+        let (cashier, stocker) = (SkillId(0), SkillId(1));
+        {
+            let mut users = USERS.write();
+            let bob = users.get_mut(&bob).unwrap();
+            bob.skills
+                .insert(cashier, Proficiency::try_from(1.0).unwrap().into());
+            let robert = users.get_mut(&robert).unwrap();
+            robert
+                .skills
+                .insert(cashier, Proficiency::try_from(2.0).unwrap().into());
+            robert
+                .skills
+                .insert(stocker, Proficiency::try_from(0.5).unwrap().into());
+            let lisa = users.get_mut(&lisa).unwrap();
+            lisa.user_prefs.insert(UserId(1), Preference(-0.5));
+        }
+        **TASKS.write() = crate::tasks! {
+            0: "count register" {},
+            1: "stock shelves" {},
+        };
+        {
+            let mut tasks = TASKS.write();
+            tasks.get_mut(&TaskId(0)).unwrap().preferred_users =
+                UserMap::from_iter([(bob, Preference(0.25)), (robert, Preference::INFINITY)]);
+            tasks.get_mut(&TaskId(1)).unwrap().preferred_users =
+                UserMap::from_iter([(robert, Preference(-0.5)), (lisa, Preference(0.5))]);
+        }
+
+        merge_users((robert, bob)).unwrap();
+        {
+            let tasks = TASKS.read();
+            assert_eq!(
+                tasks[&TaskId(0)].preferred_users,
+                UserMap::from_iter([(bob, Preference::INFINITY)]),
+                "the stronger preference should be kept"
+            );
+            assert_eq!(
+                tasks[&TaskId(1)].preferred_users,
+                UserMap::from_iter([(bob, Preference(-0.5)), (lisa, Preference(0.5))]),
+                "tasks should point at the surviving user"
+            );
+        }
+        let users = USERS.read();
+        assert!(!users.contains_key(&robert));
+        let survivor = &users[&bob];
+        assert_eq!(
+            survivor.skills[&cashier].proficiency,
+            Proficiency::try_from(2.0).unwrap()
+        );
+        assert_eq!(
+            survivor.skills[&stocker].proficiency,
+            Proficiency::try_from(0.5).unwrap()
+        );
+        assert_eq!(survivor.rule_order, [RuleId(0), RuleId(1)]);
+        assert_eq!(users[&lisa].user_prefs.get(&robert), None);
+        assert_eq!(users[&lisa].user_prefs.get(&bob), Some(&Preference(-0.5)));
+        drop(users);
+
+        assert_eq!(
+            merge_users((robert, bob)).unwrap_err().code,
+            fault::NOT_FOUND
+        );
+        assert_eq!(
+            merge_users((bob, bob)).unwrap_err().code,
+            fault::BAD_REQUEST
+        );
+
+        assert!(pop_users(UserSet::from_iter([bob])).unwrap().is_empty());
+        let tasks = TASKS.read();
+        assert!(tasks[&TaskId(0)].preferred_users.is_empty());
+        assert_eq!(
+            tasks[&TaskId(1)].preferred_users,
+            UserMap::from_iter([(lisa, Preference(0.5))]),
+            "popped users should be removed from every task"
+        );
+    }
+
+    #[test]
+    fn test_add_rules_clamps_preference() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "bob" {},
+        };
+        let rule = |preference| PyRule {
+            include: smallvec::smallvec![
+                crate::time_interval! { 1/1/2025 @ 9:00 - 1/1/2025 @ 9:30 }
+            ],
+            exclude: SmallVec::new(),
+            repeat: None,
+            preference,
+            set_at: None,
+        };
+        let added = add_rules(UniqueKeys(FxHashMap::from_iter([(
+            UserId(0),
+            vec![rule(0.5), rule(1.5)],
+        )])))
+        .unwrap();
+        let ids = &added.value[&UserId(0)];
+        let users = USERS.read();
+        let prefs = ids
+            .iter()
+            .map(|id| users[&UserId(0)].availability[id].pref)
+            .collect::<Vec<_>>();
+        assert_eq!(prefs, vec![Preference(0.5), Preference::MAX]);
+        let [warning] = added.warnings.as_slice() else {
+            panic!("expected one warning, got {:?}", added.warnings);
+        };
+        assert_eq!((warning.index, warning.id.as_deref()), (1, Some("u.0")));
+    }
+
+    #[test]
+    fn test_add_rules_rejects_excessive_repetition() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "bob" {},
+        };
+        let every_second = PyRule {
+            include: smallvec::smallvec![
+                crate::time_interval! { 1/1/2025 @ 9:00 - 1/1/2025 @ 9:30 }
+            ],
+            exclude: SmallVec::new(),
+            repeat: Some(PyRep {
+                every: PyFreq {
+                    seconds: Some(1),
+                    minutes: None,
+                    hours: None,
+                    days: None,
+                    weeks: None,
+                    months: None,
+                    years: None,
+                },
+                start: datetime!(1/1/2025),
+                until: Some(datetime!(1/1/2035)),
+            }),
+            preference: 1.0,
+            set_at: None,
+        };
+        let e = add_rules(UniqueKeys(FxHashMap::from_iter([(
+            UserId(0),
+            vec![every_second.clone()],
+        )])))
+        .unwrap_err();
+        assert_eq!(e.code, fault::UNPROCESSABLE);
+        assert!(USERS.read()[&UserId(0)].availability.is_empty());
+
+        let delta = UserDelta {
+            name: None,
+            availability: SetDelta {
+                create: vec![every_second],
+                ..Default::default()
+            },
+            default_availability: None,
+            user_prefs: SetDelta::default(),
+            skills: SetDelta::default(),
+        };
+        let e = mut_users(UserMap::from_iter([(UserId(0), delta)])).unwrap_err();
+        assert_eq!(e.code, fault::UNPROCESSABLE);
+        assert!(USERS.read()[&UserId(0)].availability.is_empty());
+    }
+
+    #[test]
+    fn test_coverage_report() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "bob" {
+                0: 4/12/2025 - 4/13/2025 | 1.0,
+            },
+            1: "lisa" {
+                1: 4/12/2025 - 4/13/2025 | 0.0,
+            },
+            2: "jones" {
+                2: 4/12/2025 - 4/13/2025 | f32::NEG_INFINITY,
+            },
+        };
+        **SLOTS.write() = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 [3] | "understaffed",
+            1: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 [2] | "at risk",
+            2: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 [1] | "ok",
+        };
+
+        let report = coverage_report(()).unwrap();
+        assert_eq!(report[&SlotId(0)].available_candidates, 2);
+        assert_eq!(report[&SlotId(0)].min_staff, Some(3));
+        assert_eq!(report[&SlotId(0)].status, CoverageStatus::Understaffed);
+        assert_eq!(report[&SlotId(1)].status, CoverageStatus::AtRisk);
+        assert_eq!(report[&SlotId(2)].status, CoverageStatus::Ok);
+    }
+
+    #[test]
+    fn test_list_hard_constraints() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "bob" {
+                0: 4/12/2025 - 4/13/2025 | f32::INFINITY,
+                1: 4/14/2025 - 4/15/2025 | 1.0,
+            },
+            1: "lisa" {
+                2: 4/12/2025 - 4/13/2025 | 0.0,
+            },
+        };
+        USERS
+            .write()
+            .get_mut(&UserId(1))
+            .unwrap()
+            .user_prefs
+            .extend([(UserId(0), Preference::NEG_INFINITY)]);
+        USERS
+            .write()
+            .get_mut(&UserId(0))
+            .unwrap()
+            .user_prefs
+            .extend([(UserId(1), Preference(0.5))]);
+
+        let constraints = list_hard_constraints(()).unwrap();
+        assert_eq!(constraints.availability, vec![(UserId(0), RuleId(0))]);
+        assert_eq!(constraints.user_prefs, vec![(UserId(1), UserId(0))]);
+        assert!(constraints.task_prefs.is_empty());
+    }
+
+    #[test]
+    fn test_get_stats() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "bob" {
+                0: 4/12/2025 - 4/13/2025 | 1.0,
+            },
+            1: "lisa" {
+                1: 4/12/2025 - 4/13/2025 | 0.0,
+            },
+            2: "jones" {
+                2: 4/12/2025 - 4/13/2025 | f32::NEG_INFINITY,
+            },
+        };
+        **SLOTS.write() = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 [2] | "day",
+            1: 4/12/2025 @ 18:00 - 4/12/2025 @ 20:00 | "evening",
+        };
+        SLOTS.write().get_mut(&SlotId(0)).unwrap().preferred_tasks =
+            [TaskId(0)].into_iter().collect();
+        SLOTS.write().get_mut(&SlotId(1)).unwrap().preferred_tasks =
+            [TaskId(1)].into_iter().collect();
+        **TASKS.write() = crate::tasks! {
+            0: "a" [5/1/2025] {},
+            1: "b" [5/1/2025] {},
+            2: "c" [5/1/2025] {},
+        };
+
+        let stats = get_stats(()).unwrap();
+        assert_eq!(stats.users, 3);
+        assert_eq!(stats.tasks, 3);
+        assert_eq!(stats.slots, 2);
+        // only the day slot needs staff, and bob and lisa are the only ones available
+        assert_eq!(stats.scheduled_hours, Some(16.0));
+        assert_eq!(stats.mean_preference, Some(0.5));
+        assert_eq!(stats.unscheduled_tasks, Some(2));
+    }
+
+    #[test]
+    fn test_concurrent_add_pop_and_read() {
+        let _guard = serial();
+        let slot = || PySlot {
+            start: datetime!(4/12/2025 @ 9:00),
+            end: datetime!(4/12/2025 @ 17:00),
+            min_staff: None,
+            max_staff: None,
+            preferred_tasks: None,
+            name: None,
+        };
+        let user = || PyUser {
+            name: "bob".to_string(),
+        };
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for _ in 0..200 {
+                    let ids = add_slots(vec![slot(), slot()]).unwrap();
+                    assert!(pop_slots(SlotSet::from_iter(ids)).unwrap().is_empty());
+                }
+            });
+            scope.spawn(|| {
+                for _ in 0..200 {
+                    let ids = add_users(vec![user()]).unwrap();
+                    assert!(pop_users(UserSet::from_iter(ids)).unwrap().is_empty());
+                }
+            });
+            scope.spawn(|| {
+                for _ in 0..200 {
+                    let ids = add_tasks(vec![py_task("a")]).unwrap().value;
+                    assert!(pop_tasks(TaskSet::from_iter(ids)).unwrap().is_empty());
+                }
+            });
+            scope.spawn(|| {
+                for _ in 0..200 {
+                    let data = ReadAll::lock();
+                    assert!(data.slots.iter().all(|(id, slot)| *id == slot.id));
+                    assert!(data.tasks.iter().all(|(id, task)| *id == task.id));
+                    assert!(data.users.iter().all(|(id, user)| *id == user.id));
+                    assert!(
+                        data.slots.len() <= 2 && data.tasks.len() <= 1 && data.users.len() <= 1
+                    );
+                    drop(data);
+                    export_schedule_csv(()).unwrap();
+                }
+            });
+        });
+
+        let data = ReadAll::lock();
+        assert!(data.slots.is_empty() && data.tasks.is_empty() && data.users.is_empty());
+    }
+
+    #[test]
+    #[should_panic = "without holding its lock"]
+    fn test_notify_mutation_requires_lock() {
+        let _guard = serial();
+        notify_mutation(Mutation::SLOTS);
+    }
+
+    #[test]
+    fn test_mutations_notify_under_lock() {
+        let _guard = serial();
+        let path = std::env::temp_dir().join("sporks-test-notify.json");
+        let slot = PySlot {
+            start: datetime!(4/12/2025 @ 9:00),
+            end: datetime!(4/12/2025 @ 17:00),
+            min_staff: None,
+            max_staff: None,
+            preferred_tasks: None,
+            name: None,
+        };
+        let user = PyUser {
+            name: "bob".to_string(),
+        };
+
+        // each of these panics in `notify_mutation` if it released its lock too early
+        let slots = add_slots(vec![slot]).unwrap();
+        let tasks = add_tasks(vec![py_task("a")]).unwrap().value;
+        let users = add_users(vec![user]).unwrap();
+        add_tasks_unique(vec![py_task("b")]).unwrap();
+        pop_slots(SlotSet::from_iter(slots)).unwrap();
+        pop_tasks(TaskSet::from_iter(tasks)).unwrap();
+        pop_users(UserSet::from_iter(users)).unwrap();
+        for load in [load_slots, load_tasks, load_users] {
+            std::fs::write(&path, "[]").unwrap();
+            load(path.clone()).unwrap();
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_dep_graph_edges() {
+        let _guard = serial();
+        **TASKS.write() = crate::tasks! {
+            0: "buy shelves" [5/1/2025] {},
+            1: "stock shelves" [5/2/2025] { 0 },
+            2: "open store" [5/3/2025] { 1 },
+            3: "unrelated" [5/3/2025] {},
+        };
+
+        let graph = dep_graph_edges(()).unwrap();
+        let expected = TASKS
+            .read()
+            .values()
+            .flat_map(|task| task.deps.iter().map(|&dep| (task.id, dep)))
+            .collect::<FxHashSet<_>>();
+        assert_eq!(
+            graph.edges,
+            [(TaskId(1), TaskId(0)), (TaskId(2), TaskId(1))]
+        );
+        assert_eq!(FxHashSet::from_iter(graph.edges.iter().copied()), expected);
+        assert_eq!(graph.roots, TaskSet::from_iter([TaskId(0), TaskId(3)]));
+        assert_eq!(graph.leaves, TaskSet::from_iter([TaskId(2), TaskId(3)]));
+
+        TASKS
+            .write()
+            .get_mut(&TaskId(0))
+            .unwrap()
+            .deps
+            .insert(TaskId(2));
+        assert_eq!(dep_graph_edges(()).unwrap_err().code, fault::UNPROCESSABLE);
+    }
+
+    #[test]
+    fn test_get_all_rules() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "bob" {
+                0: 4/12/2025 - 4/13/2025 | 1.0,
+                1: 4/14/2025 - 4/15/2025 | -0.5,
+            },
+            1: "lisa" {
+                2: 4/12/2025 - 4/13/2025 | 0.5,
+            },
+            2: "jones" {},
+        };
+
+        let rules = get_all_rules(RuleFilter::default()).unwrap();
+        let attribution = rules
+            .iter()
+            .map(|(user_id, rule_id, _)| (*user_id, *rule_id))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            attribution,
+            [
+                (UserId(0), RuleId(0)),
+                (UserId(0), RuleId(1)),
+                (UserId(1), RuleId(2)),
+            ]
+        );
+        assert_eq!(rules[2].2.preference, 0.5);
+
+        let rules = get_all_rules(RuleFilter {
+            min_pref: Some(0.0),
+            ..Default::default()
+        })
+        .unwrap();
+        let attribution = rules
+            .iter()
+            .map(|(user_id, rule_id, _)| (*user_id, *rule_id))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            attribution,
+            [(UserId(0), RuleId(0)), (UserId(1), RuleId(2))]
+        );
+    }
+
+    #[test]
+    fn test_audit_log() {
+        let _guard = serial();
+        let path = std::env::temp_dir().join("sporks-test-audit-log.ndjson");
+        _ = std::fs::remove_file(&path);
+        open_audit_log(&path).unwrap();
+
+        let add = |params| {
+            call_audited("add_tasks", params, |params| {
+                call_idempotent("add_tasks", add_tasks, params)
+            })
+        };
+        add(vec![Value::Array(
+            into_params(&vec![py_task("a")]).unwrap(),
+        )])
+        .unwrap();
+        add(vec![Value::Array(
+            into_params(&vec![py_task("b"), py_task("c")]).unwrap(),
+        )])
+        .unwrap();
+        call_audited("pop_tasks", vec![Value::Int(0)], |params| {
+            call_simple(pop_tasks, params)
+        })
+        .unwrap_err();
+        *AUDIT_LOG.lock() = None;
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines = log
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3, "one line per call");
+        assert_eq!(lines[0]["method"], "add_tasks");
+        assert_eq!(lines[1]["args"], "list[2]");
+        assert_eq!(lines[1]["status"], "ok");
+        assert_eq!(lines[2]["status"], "fault");
+        assert_eq!(lines[2]["fault_code"], fault::BAD_REQUEST);
+        assert_eq!(lines[0]["changed"], serde_json::json!(["tasks"]));
+        assert_eq!(lines[2]["changed"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_notify_mutation() {
+        let _guard = serial();
+        **SLOTS.write() = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 | "a",
+        };
+        let generate = || ReadAll::lock().generate().unwrap();
+
+        generate();
+        assert!(LAST_SCHEDULE.lock().is_some(), "schedule should be cached");
+        let version = VERSION.load(Relaxed);
+        mut_slots(SlotMap::from_iter([(
+            SlotId(0),
+            SlotDelta {
+                interval: None,
+                min_staff: None,
+                max_staff: None,
+                preferred_tasks: None,
+                name: Some("b".to_string()),
+            },
+        )]))
+        .unwrap();
+        assert_eq!(VERSION.load(Relaxed), version + 1);
+        assert!(
+            LAST_SCHEDULE.lock().is_none(),
+            "mutation should drop the cache"
+        );
+
+        generate();
+        let version = VERSION.load(Relaxed);
+        reset(()).unwrap();
+        assert_eq!(
+            VERSION.load(Relaxed),
+            version + 1,
+            "changing every collection is still one mutation"
+        );
+        assert!(LAST_SCHEDULE.lock().is_none());
+    }
+
+    #[test]
+    fn test_export_import_all() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "alice" {
+                4: 4/12/2025 @ 5:30 - 6/12/2025 @ 6:30 | 1.0,
+            },
+            3: "bob" {},
+        };
+        USERS
+            .write()
+            .get_mut(&UserId(3))
+            .unwrap()
+            .skills
+            .insert(SkillId(7), Proficiency::ONE.into());
+        **SLOTS.write() = slots! {
+            2: 4/12/2025 @ 5:30 - 6/12/2025 @ 6:30 [1] | "a",
+        };
+        **TASKS.write() = crate::tasks! {
+            5: "a" [5/1/2025] {},
+            6: "b" { 5 },
+        };
+        pin_assignment(PyPin {
+            slot: SlotId(2),
+            user: Some(UserId(0)),
+            task: None,
+        })
+        .unwrap();
+        let snapshot = || {
+            let data = ReadAll::lock();
+            (
+                serde_json::to_value(&**data.slots).unwrap(),
+                serde_json::to_value(&**data.tasks).unwrap(),
+                serde_json::to_value(&**data.users).unwrap(),
+                Pins::clone(&data.pins),
+            )
+        };
+        let before = snapshot();
+
+        let path = std::env::temp_dir().join("sporks-test-export-all.json");
+        export_all(path.clone()).unwrap();
+        reset(()).unwrap();
+        let imported = import_all(path.clone());
+        std::fs::remove_file(&path).unwrap();
+        imported.unwrap();
+
+        assert_eq!(snapshot(), before);
+        assert_eq!(SlotId::next(), Some(SlotId(3)));
+        assert_eq!(TaskId::next(), Some(TaskId(7)));
+        assert_eq!(UserId::next(), Some(UserId(4)));
+        assert_eq!(RuleId::next(), Some(RuleId(5)));
+        assert_eq!(SkillId::next(), Some(SkillId(8)));
+        **PINNED.write() = Pins::default();
+    }
+
+    #[test]
+    fn test_mut_slots_where() {
+        let _guard = serial();
+        **SLOTS.write() = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 11:00 | "early",
+            1: 4/12/2025 @ 10:00 - 4/12/2025 @ 12:00 | "late morning",
+            2: 4/12/2025 @ 13:00 - 4/12/2025 @ 17:00 | "afternoon",
+        };
+        let delta = SlotDelta {
+            interval: None,
+            min_staff: Some(NonZeroUsize::new(2)),
+            max_staff: None,
+            preferred_tasks: None,
+            name: None,
+        };
+
+        let err = mut_slots_where((SlotFilter::default(), delta.clone(), false)).unwrap_err();
+        assert_eq!(err.code, fault::BAD_REQUEST, "{}", err.message);
+
+        let changed = mut_slots_where((
+            SlotFilter {
+                ending_before: Some(datetime!(4/12/2025 @ 12:00)),
+                ..Default::default()
+            },
+            delta,
+            false,
+        ))
+        .unwrap();
+        assert_eq!(changed, SlotSet::from_iter([SlotId(0), SlotId(1)]));
+        let slots = SLOTS.read();
+        assert_eq!(slots[&SlotId(0)].min_staff, NonZeroUsize::new(2));
+        assert_eq!(slots[&SlotId(1)].min_staff, NonZeroUsize::new(2));
+        assert_eq!(slots[&SlotId(2)].min_staff, None);
+        drop(slots);
+
+        SLOTS.write().clear();
+    }
+
+    #[test]
+    fn test_read_timeout() {
+        let _guard = serial();
+        *READ_TIMEOUT.write() = Some(Duration::from_millis(10));
+        let slots = SLOTS.write();
+        let start = std::time::Instant::now();
+        let err = get_slots(SlotFilter::default()).unwrap_err();
+        assert!(
+            start.elapsed() < Duration::from_secs(1),
+            "should give up promptly"
+        );
+        assert_eq!(err.code, fault::UNAVAILABLE, "{}", err.message);
+        let err = active_slots(datetime!(1/1/2025)).unwrap_err();
+        assert_eq!(err.code, fault::UNAVAILABLE, "{}", err.message);
+        drop(slots);
+        assert!(get_slots(SlotFilter::default()).is_ok());
+
+        let users = USERS.write();
+        let err = get_rule_order(UserId(0)).unwrap_err();
+        assert_eq!(err.code, fault::UNAVAILABLE, "{}", err.message);
+        drop(users);
+        *READ_TIMEOUT.write() = None;
+    }
+
+    #[test]
+    fn test_generate_schedule_seed() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "bob" { 0: 4/12/2025 - 4/13/2025 | 0.5 },
+            1: "lisa" { 1: 4/12/2025 - 4/13/2025 | 0.5 },
+        };
+        **SLOTS.write() = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 [1] | "a",
+        };
+
+        let first = generate_schedule(Some(42)).unwrap();
+        assert_eq!(first.seed, Some(42));
+        assert_eq!(
+            generate_schedule(first.seed).unwrap().schedule,
+            first.schedule,
+            "the returned seed should reproduce the schedule"
+        );
+
+        SLOTS.write().clear();
+        USERS.write().clear();
+    }
+
+    #[test]
+    fn test_add_tasks_unique() {
+        let _guard = serial();
+        let first = add_tasks_unique(vec![py_task("wash dishes")])
+            .unwrap()
+            .value;
+        assert!(first.reused.is_empty());
+        let second = add_tasks_unique(vec![py_task("Wash Dishes"), py_task("dry dishes")])
+            .unwrap()
+            .value;
+        assert_eq!(
+            second.ids[0], first.ids[0],
+            "both calls should return the same id"
+        );
+        assert_eq!(second.reused, [0]);
+        assert_eq!(
+            TASKS
+                .read()
+                .values()
+                .filter(|task| task.title.eq_ignore_ascii_case("wash dishes"))
+                .count(),
+            1
+        );
+        assert_eq!(TASKS.read().len(), 2);
+    }
+
+    #[test]
+    fn test_add_tasks_clamps_preference() {
+        let _guard = serial();
+        let task = PyTask {
+            preferred_users: Some(UserMap::from_iter([
+                (UserId(0), 0.5),
+                (UserId(1), -1.5),
+                (UserId(2), f32::INFINITY),
+            ])),
+            ..py_task("wash dishes")
+        };
+        let added = add_tasks(vec![py_task("dry dishes"), task]).unwrap();
+        let prefs = &TASKS.read()[&added.value[1]].preferred_users;
+        assert_eq!(prefs[&UserId(0)], Preference(0.5));
+        assert_eq!(prefs[&UserId(1)], Preference::MIN);
+        assert_eq!(prefs[&UserId(2)], Preference::INFINITY);
+        let [warning] = added.warnings.as_slice() else {
+            panic!("expected one warning, got {:?}", added.warnings);
+        };
+        let id = added.value[1].to_string();
+        assert_eq!((warning.index, warning.id.as_deref()), (1, Some(id.as_str())));
+        assert!(warning.reason.contains("-1.5"), "{}", warning.reason);
+    }
+
+    #[test]
+    fn test_shutdown_after() {
+        let _guard = serial();
+        shutdown_after(3600).unwrap();
+        assert!(cancel_shutdown(()).unwrap());
+        assert!(!cancel_shutdown(()).unwrap());
+
+        let e = shutdown_after(u64::MAX).unwrap_err();
+        assert_eq!(e.code, fault::BAD_REQUEST);
+        assert!(
+            !cancel_shutdown(()).unwrap(),
+            "a rejected timer should not be armed"
+        );
+
+        *SHUTDOWN_AT.lock() = Some(Instant::now() + Duration::from_millis(10));
+        assert!(!exit_requested());
+        let start = Instant::now();
+        while !exit_requested() {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "timer never fired"
+            );
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(*SHUTDOWN_AT.lock(), None);
+        EXIT_REQUESTED.store(false, Relaxed);
+    }
+
+    #[test]
+    fn test_instantiate_template() {
+        let _guard = serial();
+        let [template_id] = add_templates(vec![PySlotTemplate {
+            duration: TimeDelta::hours(8),
+            min_staff: Some(2),
+            max_staff: Some(4),
+            preferred_tasks: None,
+            name: Some("weekday".to_string()),
+        }])
+        .unwrap()[..] else {
+            panic!("expected one template")
+        };
+        assert_eq!(get_templates(()).unwrap()[&template_id].min_staff, Some(2));
+
+        let monday = instantiate_template((template_id, datetime!(4/14/2025 @ 9:00))).unwrap();
+        let tuesday = instantiate_template((template_id, datetime!(4/15/2025 @ 9:00))).unwrap();
+        let slots = SLOTS.read();
+        let (monday, tuesday) = (&slots[&monday], &slots[&tuesday]);
+        assert_eq!(
+            monday.interval,
+            time_interval!(4/14/2025 @ 9:00 - 4/14/2025 @ 17:00)
+        );
+        assert_eq!(
+            tuesday.interval,
+            time_interval!(4/15/2025 @ 9:00 - 4/15/2025 @ 17:00)
+        );
+        for slot in [monday, tuesday] {
+            assert_eq!(slot.min_staff, NonZeroUsize::new(2));
+            assert_eq!(slot.max_staff, NonZeroUsize::new(4));
+            assert_eq!(slot.name, "weekday");
+        }
+        drop(slots);
+
+        let err =
+            instantiate_template((SlotTemplateId(99), datetime!(4/14/2025 @ 9:00))).unwrap_err();
+        assert_eq!(err.code, fault::NOT_FOUND);
+        TEMPLATES.write().clear();
+    }
+
+    #[test]
+    fn test_read_only() {
+        let _guard = serial();
+        READ_ONLY.store(true, Relaxed);
+        let added = call_mutating(
+            "add_tasks",
+            vec![Value::Array(into_params(&vec![py_task("a")]).unwrap())],
+            |params| call_idempotent("add_tasks", add_tasks, params),
+        );
+        READ_ONLY.store(false, Relaxed);
+
+        assert_eq!(added.unwrap_err().code, fault::FORBIDDEN);
+        assert!(TASKS.read().is_empty(), "rejected call should not run");
+        assert!(get_tasks(TaskFilter::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_empty_inputs() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "bob" {
+                0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 | 1.0,
+            },
+        };
+        **SLOTS.write() = slots! {
+            0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00,
+        };
+        **TASKS.write() = crate::tasks! {
+            0: "a" {},
+        };
+        TaskId::store(1);
+        let version = VERSION.load(Relaxed);
+
+        assert!(
+            add_rules(UniqueKeys(UserMap::default()))
+                .unwrap()
+                .value
+                .is_empty()
+        );
+        assert!(add_slots(Vec::new()).unwrap().is_empty());
+        assert!(add_templates(Vec::new()).unwrap().is_empty());
+        assert!(add_tasks(Vec::new()).unwrap().value.is_empty());
+        assert!(add_tasks_unique(Vec::new()).unwrap().value.ids.is_empty());
+        assert!(add_users(Vec::new()).unwrap().is_empty());
+        assert_eq!(
+            TaskId::next(),
+            Some(TaskId(1)),
+            "the counter should not move"
+        );
+
+        assert!(mut_slots(SlotMap::default()).unwrap().is_empty());
+        assert!(mut_tasks(TaskMap::default()).unwrap().value.is_empty());
+        let result = mut_users(UserMap::default()).unwrap();
+        assert!(result.warnings.is_empty());
+        assert!(result.value.failed.is_empty() && result.value.created.is_empty());
+
+        assert!(pop_rules(UserMap::default()).unwrap().is_empty());
+        assert!(pop_slots(SlotSet::default()).unwrap().is_empty());
+        assert!(pop_tasks(TaskSet::default()).unwrap().is_empty());
+        assert!(pop_users(UserSet::default()).unwrap().is_empty());
+        assert_eq!(
+            VERSION.load(Relaxed),
+            version,
+            "no-op calls should not bump the version"
+        );
+
+        // an empty whitelist includes nothing, while no whitelist includes everything
+        let slots = |ids| SlotFilter {
+            ids,
+            ..Default::default()
+        };
+        assert!(
+            get_slots(slots(Some(SlotSet::default())))
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(get_slots(slots(None)).unwrap().len(), 1);
+        let tasks = |ids| TaskFilter {
+            ids,
+            ..Default::default()
+        };
+        assert!(
+            get_tasks(tasks(Some(TaskSet::default())))
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(get_tasks(tasks(None)).unwrap().len(), 1);
+        let users = |ids| UserFilter {
+            ids,
+            name_pat: None,
+        };
+        assert!(get_users(users(Some(Vec::new()))).unwrap().is_empty());
+        assert_eq!(get_users(users(None)).unwrap().len(), 1);
+        let rules = |ids| RuleFilter {
+            ids,
+            ..Default::default()
+        };
+        assert!(
+            get_all_rules(rules(Some(RuleSet::default())))
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(get_all_rules(rules(None)).unwrap().len(), 1);
+        assert!(get_rules(UserMap::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_echo_task_round_trip() {
+        let task = PyTask {
+            title: "wash dishes".to_string(),
+            desc: Some("by hand".to_string()),
+            deadline: Some(datetime!(4/12/2025 @ 17:00)),
+            soft_deadline: Some(datetime!(4/12/2025 @ 15:00)),
+            hard_deadline: Some(datetime!(4/12/2025 @ 17:00)),
+            deadline_offset: Some(TimeDelta::hours(2)),
+            awaiting: Some(TaskSet::from_iter([TaskId(1), TaskId(2)])),
+            preferred_users: Some(UserMap::from_iter([(UserId(3), f32::INFINITY)])),
+        };
+        let response = call_simple(echo::<PyTask>, into_params(&task).unwrap()).unwrap();
+        assert_eq!(from_params::<PyTask>(response).unwrap(), task);
+    }
+
+    #[test]
+    fn test_qualified_users() {
+        let _guard = serial();
+        **USERS.write() = users! {
+            0: "bob" {},
+            1: "sue" {},
+            2: "joe" {},
+            3: "ann" {},
+        };
+        grant_skill((vec![UserId(0)], SkillId(0), 0.5)).unwrap();
+        grant_skill((vec![UserId(1)], SkillId(0), 1.0)).unwrap();
+        grant_skill((vec![UserId(2)], SkillId(0), 0.2)).unwrap();
+        grant_skill((vec![UserId(3)], SkillId(1), 1.0)).unwrap();
+
+        assert_eq!(
+            qualified_users((SkillId(0), Some(0.4))).unwrap(),
+            [(UserId(1), 1.0), (UserId(0), 0.5)]
+        );
+        assert_eq!(
+            qualified_users((SkillId(0), None)).unwrap(),
+            [(UserId(1), 1.0), (UserId(0), 0.5), (UserId(2), 0.2)],
+            "ann does not have the skill"
+        );
+    }
+
+    #[test]
+    fn test_max_connections() {
+        let _guard = serial();
+        MAX_CONNECTIONS.store(2, Relaxed);
+        let first = admit().unwrap();
+        let second = admit().unwrap();
+        assert_eq!(admit().err().unwrap().code, fault::UNAVAILABLE);
+
+        drop(first);
+        let third = admit().expect("a finished call should free its place");
+        drop((second, third));
+        MAX_CONNECTIONS.store(DEFAULT_MAX_CONNECTIONS, Relaxed);
+        assert_eq!(IN_FLIGHT.load(Relaxed), 0);
+    }
+
+    #[test]
+    fn test_add_slots_granularity() {
+        let _guard = serial();
+        reset(()).unwrap();
+        let slot = |start, end| PySlot {
+            start,
+            end,
+            min_staff: None,
+            max_staff: None,
+            preferred_tasks: None,
+            name: None,
+        };
+        let to_add = || vec![slot(datetime!(4/5/2025 @ 9:7), datetime!(4/5/2025 @ 11:30))];
+
+        *SLOT_GRANULARITY.write() = Some(Granularity {
+            step: TimeDelta::minutes(15),
+            mode: GranularityMode::Reject,
+        });
+        let e = add_slots(to_add()).unwrap_err();
+        assert_eq!(e.code, fault::UNPROCESSABLE);
+        assert!(SLOTS.read().is_empty());
+
+        *SLOT_GRANULARITY.write() = Some(Granularity {
+            step: TimeDelta::minutes(15),
+            mode: GranularityMode::Round,
+        });
+        let ids = add_slots(to_add()).unwrap();
+        assert_eq!(
+            SLOTS.read()[&ids[0]].interval,
+            time_interval! { 4/5/2025 @ 9:00 - 4/5/2025 @ 11:30 }
+        );
+
+        let delta = |start| SlotDelta {
+            interval: Some(TimeInterval {
+                start,
+                end: datetime!(4/5/2025 @ 11:30),
+            }),
+            min_staff: None,
+            max_staff: None,
+            preferred_tasks: None,
+            name: None,
+        };
+        mut_slots(SlotMap::from_iter([(ids[0], delta(datetime!(4/5/2025 @ 9:8)))])).unwrap();
+        assert_eq!(
+            SLOTS.read()[&ids[0]].interval,
+            time_interval! { 4/5/2025 @ 9:15 - 4/5/2025 @ 11:30 },
+            "mutated intervals should be rounded too"
+        );
+
+        *SLOT_GRANULARITY.write() = Some(Granularity {
+            step: TimeDelta::minutes(15),
+            mode: GranularityMode::Reject,
+        });
+        let e = mut_slots(SlotMap::from_iter([(ids[0], delta(datetime!(4/5/2025 @ 9:7)))]))
+            .unwrap_err();
+        assert_eq!(e.code, fault::UNPROCESSABLE);
+        assert_eq!(
+            SLOTS.read()[&ids[0]].interval,
+            time_interval! { 4/5/2025 @ 9:15 - 4/5/2025 @ 11:30 },
+            "a rejected change should leave the slot alone"
+        );
+        *SLOT_GRANULARITY.write() = None;
+        reset(()).unwrap();
+    }
+
+    #[test]
+    fn test_changes_since_add_tasks() {
+        let _guard = serial();
+        let old = changes_since(0).unwrap().version;
+        add_tasks(vec![py_task("wash dishes")]).unwrap();
+        let changes = changes_since(old).unwrap();
+        assert!(
+            changes.changed,
+            "adding a task should be reported as a change"
+        );
+        assert!(changes.version > old, "version should advance");
+        assert!(
+            !changes_since(changes.version).unwrap().changed,
+            "nothing changed since the latest version"
+        );
+    }
 }
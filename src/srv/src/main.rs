@@ -2,10 +2,7 @@
 //!
 //! A management scheduling application (generator end; executed by backend)
 
-<<<<<<< HEAD
-=======
 #![feature(integer_atomics)]
->>>>>>> 04a1808e76feb61ebfb644cf6eff190bd1c24f5a
 #![deny(
     clippy::undocumented_unsafe_blocks,
     clippy::missing_safety_doc,
@@ -26,43 +23,26 @@
 )]
 #![cfg_attr(
     not(any(test, debug_assertions)),
-    forbid(clippy::todo, reason = "production code should not use `todo`")
+    deny(clippy::todo, reason = "production code should not use `todo`")
 )]
 
-<<<<<<< HEAD
-use crate::data::{Slot, Task, TaskId, TaskMap, User, UserId, UserMap};
-use chrono::{DateTime, Utc};
-=======
 use crate::{
     data::*,
-    integration::{EXIT_REQUESTED, SLOTS, TASKS, USERS},
+    integration::{SLOTS, TASKS, USERS},
 };
->>>>>>> 04a1808e76feb61ebfb644cf6eff190bd1c24f5a
 use clap::{
-    Parser,
+    ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum,
     builder::{Styles, styling::AnsiColor},
+    parser::ValueSource,
 };
-use miette::{IntoDiagnostic, LabeledSpan, NamedSource, Result, SourceOffset, miette};
-<<<<<<< HEAD
-use parking_lot::Mutex;
-use rustc_hash::{FxHashMap, FxHashSet};
-use serde::{Deserialize, Serialize, de::DeserializeOwned};
-=======
-use serde::{Serialize, de::DeserializeOwned};
->>>>>>> 04a1808e76feb61ebfb644cf6eff190bd1c24f5a
+use miette::{Context, IntoDiagnostic, LabeledSpan, NamedSource, Result, SourceOffset, miette};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, de::DeserializeOwned};
 use std::{
     fs::File,
-    io::BufReader,
+    io::{BufReader, Stdout, Write},
     net::{IpAddr, Ipv4Addr, SocketAddr},
     path::{Path, PathBuf},
-<<<<<<< HEAD
-    sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering::Relaxed},
-};
-use xml_rpc::{Fault, Server};
-
-pub mod algo;
-pub mod data;
-=======
     sync::atomic::Ordering::Relaxed,
 };
 use xml_rpc::Server;
@@ -70,7 +50,6 @@ use xml_rpc::Server;
 pub mod algo;
 pub mod data;
 pub mod integration;
->>>>>>> 04a1808e76feb61ebfb644cf6eff190bd1c24f5a
 
 const STYLE: Styles = Styles::styled()
     .header(AnsiColor::Green.on_default().bold())
@@ -78,132 +57,382 @@ const STYLE: Styles = Styles::styled()
     .literal(AnsiColor::BrightCyan.on_default().bold())
     .placeholder(AnsiColor::Cyan.on_default());
 
+/// Serialization format of a data file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// A JSON object of items keyed by ID
+    Json,
+    /// One item per row
+    Csv,
+}
+
+impl Format {
+    /// Guess the format from the file extension, falling back on [`Format::Json`].
+    pub fn infer(path: &Path) -> Self {
+        match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => Self::Csv,
+            _ => Self::Json,
+        }
+    }
+}
+
 /// Sporks scheduling software
 #[derive(Debug, Parser)]
 #[command(version, propagate_version = true, about, long_about = None, styles = STYLE, color = clap::ColorChoice::Always)]
 pub struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Provide path to user data file
-    #[arg(short, long, value_name = "PATH", default_value_os_t = PathBuf::from("./users.csv"))]
+    #[arg(short, long, global = true, value_name = "PATH", default_value_os_t = PathBuf::from("./users.csv"))]
     users: PathBuf,
 
     /// Provide path to timeslot data file
-    #[arg(short, long, value_name = "PATH", default_value_os_t = PathBuf::from("./slots.csv"))]
+    #[arg(short, long, global = true, value_name = "PATH", default_value_os_t = PathBuf::from("./slots.csv"))]
     slots: PathBuf,
 
     /// Provide path to task data file
-    #[arg(short, long, value_name = "PATH", default_value_os_t = PathBuf::from("./tasks.csv"))]
+    #[arg(short, long, global = true, value_name = "PATH", default_value_os_t = PathBuf::from("./tasks.csv"))]
     tasks: PathBuf,
 
     /// Provide path to output data file
-    #[arg(short, long, value_name = "PATH", default_value_os_t = PathBuf::from("./schedule.csv"))]
+    #[arg(short, long, global = true, value_name = "PATH", default_value_os_t = PathBuf::from("./schedule.csv"))]
     output: PathBuf,
+
+    /// Format of all data files [default: inferred from each file's extension]
+    #[arg(long, global = true, value_enum, value_name = "FORMAT")]
+    format: Option<Format>,
+
+    /// Do not print server lifecycle messages; errors are still printed to stderr
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// The most occurrences a repeating rule or slot copy may expand to
+    #[arg(long, global = true, value_name = "N", default_value_t = DEFAULT_MAX_OCCURRENCES)]
+    max_occurrences: usize,
+
+    /// Append a line of JSON to this file for every call that mutates data
+    #[arg(long, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    /// The address to serve requests on
+    #[arg(long, value_name = "ADDR", default_value_t = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8080))]
+    bind: SocketAddr,
+
+    /// Fail reads of busy data after this many milliseconds, rather than waiting indefinitely
+    #[arg(long, value_name = "MS")]
+    read_timeout: Option<u64>,
+
+    /// Reject every call that would change data or stop the server
+    #[arg(long)]
+    read_only: bool,
+
+    /// The most calls handled at once; calls beyond it are rejected as busy
+    #[arg(long, value_name = "N", default_value_t = integration::DEFAULT_MAX_CONNECTIONS)]
+    max_connections: usize,
+
+    /// Load settings from a JSON file; flags given on the command line take precedence
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Shuffle equally preferred candidates with this seed, rather than preferring lower user IDs
+    #[arg(long, global = true, value_name = "N")]
+    seed: Option<u64>,
+
+    /// Rescale each user's preferences relative to their own before comparing them with other users'
+    #[arg(long, global = true)]
+    normalize: bool,
+
+    /// The least time a user must have off between two slots they work
+    #[arg(long, global = true, value_name = "SECS", value_parser = parse_seconds)]
+    min_rest: Option<chrono::TimeDelta>,
+
+    /// See [`algo::Decay`]. Only settable through `--config`.
+    #[arg(skip)]
+    decay: Option<algo::Decay>,
+
+    /// See [`data::Granularity`]. Only settable through `--config`.
+    #[arg(skip)]
+    slot_granularity: Option<data::Granularity>,
+}
+
+/// The contents of a `--config` file. Each field corresponds to the [`Cli`] flag of the same name.
+///
+/// Any field may be left out, keeping the flag's value.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Settings {
+    users: Option<PathBuf>,
+    slots: Option<PathBuf>,
+    tasks: Option<PathBuf>,
+    output: Option<PathBuf>,
+    format: Option<Format>,
+    quiet: Option<bool>,
+    max_occurrences: Option<usize>,
+    log_file: Option<PathBuf>,
+    bind: Option<SocketAddr>,
+    read_timeout: Option<u64>,
+    read_only: Option<bool>,
+    max_connections: Option<usize>,
+    seed: Option<u64>,
+    normalize: Option<bool>,
+    #[serde(with = "data::seconds::option")]
+    min_rest: Option<chrono::TimeDelta>,
+    decay: Option<algo::Decay>,
+    slot_granularity: Option<data::Granularity>,
+}
+
+/// Parse a non-negative number of whole seconds, as `--config` files give durations.
+fn parse_seconds(s: &str) -> std::result::Result<chrono::TimeDelta, String> {
+    let secs = s.parse::<i64>().map_err(|e| e.to_string())?;
+    data::seconds::deserialize(serde::de::value::I64Deserializer::<serde::de::value::Error>::new(
+        secs,
+    ))
+    .map_err(|e| e.to_string())
+}
+
+/// Runs the server if no command is given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Subcommand)]
+enum Command {
+    /// Generate a schedule from the data files and write it to the output file, without starting the server
+    Generate,
+}
+
+impl Cli {
+    /// Build from parsed command line arguments, then fill in anything they leave unspecified from the `--config` file.
+    fn from_matches(matches: &ArgMatches) -> Result<Self> {
+        let mut cli = Self::from_arg_matches(matches).into_diagnostic()?;
+        if let Some(path) = cli.config.clone() {
+            let settings = std::fs::read_to_string(&path)
+                .into_diagnostic()
+                .and_then(|source| serde_json::from_str(&source).into_diagnostic())
+                .wrap_err_with(|| format!("could not load config file {}", path.display()))?;
+            cli.merge(settings, matches);
+        }
+        Ok(cli)
+    }
+
+    /// Take each value from `settings` that was not given on the command line.
+    fn merge(&mut self, settings: Settings, matches: &ArgMatches) {
+        let from_file = |id: &str| matches.value_source(id) != Some(ValueSource::CommandLine);
+        let Settings {
+            users,
+            slots,
+            tasks,
+            output,
+            format,
+            quiet,
+            max_occurrences,
+            log_file,
+            bind,
+            read_timeout,
+            read_only,
+            max_connections,
+            seed,
+            normalize,
+            min_rest,
+            decay,
+            slot_granularity,
+        } = settings;
+        macro_rules! merge {
+            ($($field:ident),* $(,)?) => {$(
+                if let Some(value) = $field
+                    && from_file(stringify!($field))
+                {
+                    self.$field = value.into();
+                }
+            )*};
+        }
+        merge!(
+            users,
+            slots,
+            tasks,
+            output,
+            format,
+            quiet,
+            max_occurrences,
+            log_file,
+            bind,
+            read_timeout,
+            read_only,
+            max_connections,
+            seed,
+            normalize,
+            min_rest
+        );
+        self.decay = decay;
+        self.slot_granularity = slot_granularity;
+    }
+
+    /// The scheduler settings shared by the server and the `generate` command, without any pins.
+    pub fn scheduler_config(&self) -> algo::SchedulerConfig {
+        algo::SchedulerConfig {
+            decay: self.decay,
+            normalize: self.normalize,
+            min_rest: self.min_rest,
+            seed: self.seed,
+            ..Default::default()
+        }
+    }
+
+    /// The format `path` should be read or written as.
+    pub fn format_of(&self, path: &Path) -> Format {
+        self.format.unwrap_or_else(|| Format::infer(path))
+    }
 }
 
 /// A handle that indicates it the server has started, then
 /// indicates that the server has closed when the application ends.
-struct RunningHandle(());
+///
+/// Prints nothing if `quiet`.
+struct RunningHandle<W: Write = Stdout>(Option<W>);
 
-impl Drop for RunningHandle {
+impl<W: Write> Drop for RunningHandle<W> {
     fn drop(&mut self) {
-        println!("srv: closed")
+        if let Some(out) = &mut self.0 {
+            _ = writeln!(out, "srv: closed");
+        }
     }
 }
 
 impl RunningHandle {
-    pub fn init() -> Self {
-        println!("srv: running");
-        Self(())
+    pub fn init(quiet: bool) -> Self {
+        Self::init_to(quiet, std::io::stdout())
+    }
+}
+
+impl<W: Write> RunningHandle<W> {
+    fn init_to(quiet: bool, mut out: W) -> Self {
+        if quiet {
+            return Self(None);
+        }
+        _ = writeln!(out, "srv: running");
+        Self(Some(out))
     }
 }
 
+/// Load a data file as `format`, keying each item by its [ID](Keyed::id).
+///
+/// Produces an empty map if the file does not exist.
+fn try_load<V: Keyed + DeserializeOwned>(
+    path: &Path,
+    format: Format,
+    name: &'static str,
+) -> Result<FxHashMap<V::Id, V>>
+where
+    V::Id: DeserializeOwned,
+{
+    match File::open(path) {
+        // successfully loaded
+        Ok(file) if format == Format::Csv => csv::Reader::from_reader(BufReader::new(file))
+            .into_deserialize::<V>()
+            .collect::<Result<Vec<_>, _>>()
+            .map(index_by_id)
+            .map_err(|e| miette!("could not parse {name} file {}: {e}", path.display())),
+
+        Ok(file) => serde_json::from_reader(BufReader::new(file)).map_err(|e| {
+            let source = std::fs::read_to_string(path).unwrap_or_default();
+            miette!(
+                labels = vec![LabeledSpan::new_primary_with_span(
+                    Some(e.to_string()),
+                    SourceOffset::from_location(&source, e.line(), e.column())
+                )],
+                "could not parse file"
+            )
+            .with_source_code(
+                NamedSource::new(path.display().to_string(), source).with_language("JSON"),
+            )
+        }),
+
+        // not found, generate one
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let default = FxHashMap::default();
+            // File::create(path)
+            //     .into_diagnostic()
+            //     .and_then(|file| serde_json::to_writer(file, &default).into_diagnostic())?;
+            // let source = match path.canonicalize() {
+            //     Ok(absolute) => absolute.display().to_string(),
+            //     Err(_) => path.display().to_string(),
+            // };
+            // let e = miette!(
+            //     severity = Severity::Warning,
+            //     labels = vec![LabeledSpan::new_primary_with_span(
+            //         Some(format!("{e}")),
+            //         0..source.len(),
+            //     )],
+            //     "could not load {name} data; generating a default"
+            // )
+            // .with_source_code(source);
+            // println!("{e:?}");
+            Ok(default)
+        }
+
+        // other error
+        Err(e) => {
+            let source = match path.canonicalize() {
+                Ok(absolute) => absolute.display().to_string(),
+                Err(_) => path.display().to_string(),
+            };
+            Err(miette!(
+                labels = vec![LabeledSpan::new_primary_with_span(
+                    Some(e.to_string()),
+                    0..source.len(),
+                )],
+                "could not load {name} data"
+            )
+            .with_source_code(source))
+        }
+    }
+}
+
+/// Load the slot, task, and user data files named by `cli`.
+fn load_all(cli: &Cli) -> Result<(SlotMap, TaskMap, UserMap)> {
+    let slots = try_load(&cli.slots, cli.format_of(&cli.slots), "slot")?;
+    let tasks = try_load(&cli.tasks, cli.format_of(&cli.tasks), "task")?;
+    let users = try_load(&cli.users, cli.format_of(&cli.users), "user")?;
+    Ok((slots, tasks, users))
+}
+
+/// Generate a schedule and write it to the output file named by `cli`.
+///
+/// CSV output is the same as [`integration::export_schedule_csv`].
+fn generate(cli: &Cli, slots: &SlotMap, tasks: &TaskMap, users: &UserMap) -> Result<()> {
+    let config = cli.scheduler_config();
+    let schedule = algo::Schedule::generate_with(slots, tasks, users, &config)
+        .into_diagnostic()
+        .wrap_err("could not generate schedule")?;
+    let buf = match cli.format_of(&cli.output) {
+        Format::Json => serde_json::to_vec_pretty(&schedule).into_diagnostic()?,
+        Format::Csv => integration::schedule_csv(&schedule, slots, tasks, users, &config.pins)
+            .into_diagnostic()?,
+    };
+    std::fs::write(&cli.output, buf)
+        .into_diagnostic()
+        .wrap_err_with(|| format!("could not write schedule to {}", cli.output.display()))
+}
+
 fn main() -> Result<()> {
-    let Cli {
-        users,
-        slots,
-        tasks,
-        output: _,
-    } = match Cli::try_parse() {
+    let matches = match Cli::command().try_get_matches() {
         Err(e) if e.kind() == clap::error::ErrorKind::DisplayHelp => {
             return e.print().into_diagnostic();
         }
-        cli => cli.into_diagnostic(),
+        matches => matches.into_diagnostic(),
     }?;
+    let cli = Cli::from_matches(&matches)?;
 
-    fn try_load<T: Serialize + DeserializeOwned + Default>(
-        path: &Path,
-        name: &'static str,
-    ) -> Result<T> {
-        match File::open(path) {
-            // successfully loaded
-            Ok(file) => serde_json::from_reader(BufReader::new(file)).map_err(|e| {
-                let source = std::fs::read_to_string(path).unwrap();
-                miette!(
-                    labels = vec![LabeledSpan::new_primary_with_span(
-                        Some(e.to_string()),
-                        SourceOffset::from_location(&source, e.line(), e.column())
-                    )],
-                    "could not parse file"
-                )
-                .with_source_code(
-                    NamedSource::new(path.display().to_string(), source).with_language("JSON"),
-                )
-            }),
-
-            // not found, generate one
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                let default = T::default();
-                // File::create(path)
-                //     .into_diagnostic()
-                //     .and_then(|file| serde_json::to_writer(file, &default).into_diagnostic())?;
-                // let source = match path.canonicalize() {
-                //     Ok(absolute) => absolute.display().to_string(),
-                //     Err(_) => path.display().to_string(),
-                // };
-                // let e = miette!(
-                //     severity = Severity::Warning,
-                //     labels = vec![LabeledSpan::new_primary_with_span(
-                //         Some(format!("{e}")),
-                //         0..source.len(),
-                //     )],
-                //     "could not load {name} data; generating a default"
-                // )
-                // .with_source_code(source);
-                // println!("{e:?}");
-                Ok(default)
-            }
-
-            // other error
-            Err(e) => {
-                let source = match path.canonicalize() {
-                    Ok(absolute) => absolute.display().to_string(),
-                    Err(_) => path.display().to_string(),
-                };
-                Err(miette!(
-                    labels = vec![LabeledSpan::new_primary_with_span(
-                        Some(e.to_string()),
-                        0..source.len(),
-                    )],
-                    "could not load {name} data"
-                )
-                .with_source_code(source))
-            }
-        }
-    }
-
-<<<<<<< HEAD
-    let mut users = try_load::<UserMap>(&users, "user")?;
-    let _slots = try_load::<Vec<Slot>>(&slots, "time slot")?;
-    let mut tasks = try_load::<TaskMap>(&tasks, "task")?;
+    MAX_OCCURRENCES.store(cli.max_occurrences, Relaxed);
+    let (slots, tasks, users) = load_all(&cli)?;
 
-    // let schedule =
-    //     Schedule::generate(&dbg!(slots), &dbg!(tasks), &dbg!(users)).into_diagnostic()?;
+    if let Some(Command::Generate) = cli.command {
+        return generate(&cli, &slots, &tasks, &users);
+    }
 
-    // serde_json::to_writer(File::create(output).into_diagnostic()?, &dbg!(schedule))
-    //     .into_diagnostic()?;
-=======
-    let slots = try_load::<SlotMap>(&slots, "slot")?;
-    let tasks = try_load::<TaskMap>(&tasks, "task")?;
-    let users = try_load::<UserMap>(&users, "user")?;
+    if let Some(path) = &cli.log_file {
+        integration::open_audit_log(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("could not open log file {}", path.display()))?;
+    }
 
     TaskId::store(tasks.keys().map(|k| k.0 + 1).max().unwrap_or(0));
     UserId::store(users.keys().map(|k| k.0 + 1).max().unwrap_or(0));
@@ -211,161 +440,201 @@ fn main() -> Result<()> {
     **SLOTS.write() = slots;
     **TASKS.write() = tasks;
     **USERS.write() = users;
->>>>>>> 04a1808e76feb61ebfb644cf6eff190bd1c24f5a
+    **integration::SCHEDULER_CONFIG.write() = cli.scheduler_config();
+    *integration::SLOT_GRANULARITY.write() = cli.slot_granularity;
+    *integration::READ_TIMEOUT.write() = cli.read_timeout.map(std::time::Duration::from_millis);
+    integration::READ_ONLY.store(cli.read_only, Relaxed);
+    integration::MAX_CONNECTIONS.store(cli.max_connections, Relaxed);
 
-    let socket = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
     let mut server = Server::new();
 
-<<<<<<< HEAD
-    static EXIT_REQUESTED: AtomicBool = const { AtomicBool::new(false) };
-    static NEXT_USER_ID: AtomicU32 = const { AtomicU32::new(0) };
-    static NEXT_TASK_ID: AtomicU64 = const { AtomicU64::new(0) };
-    static TASKS_TO_ADD: Mutex<Vec<Task>> = const { Mutex::new(Vec::new()) };
-    static USERS_TO_ADD: Mutex<Vec<User>> = const { Mutex::new(Vec::new()) };
-
-    NEXT_USER_ID.store(users.keys().map(|k| k.0).max().unwrap_or(0), Relaxed);
-    NEXT_TASK_ID.store(tasks.keys().map(|k| k.0).max().unwrap_or(0), Relaxed);
-
-    // quit
-    {
-        server.register_simple("quit", |()| {
-            EXIT_REQUESTED.store(true, Relaxed);
-            Ok(())
-        });
-    }
-
-    // add_users
-    {
-        /// Python requirements for constructing a [`User`]
-        #[derive(Debug, Serialize, Deserialize)]
-        pub struct PyUser {
-            name: String,
-        }
-
-        impl From<(UserId, PyUser)> for User {
-            #[inline]
-            fn from((id, user): (UserId, PyUser)) -> Self {
-                let PyUser { name, .. } = user;
-                User {
-                    id,
-                    name,
-                    availability: Vec::new(),
-                    user_prefs: FxHashMap::default(),
-                    skills: FxHashMap::default(),
-                }
-            }
-        }
+    integration::register(&mut server);
 
-        #[derive(Debug, Serialize, Deserialize)]
-        struct AddUsersParams {
-            to_add: Vec<PyUser>,
+    let bound_server = server
+        .bind(&cli.bind)
+        .map_err(|e| miette!("could not bind to {}: {e}", cli.bind))?;
+    let _marker = RunningHandle::init(cli.quiet);
+    loop {
+        bound_server.poll();
+        if integration::exit_requested() {
+            break Ok(());
         }
+    }
+}
 
-        server.register_simple(
-            "add_users",
-            |AddUsersParams { to_add }: AddUsersParams| -> Result<Vec<UserId>, Fault> {
-                println!("srv: recieved users: {to_add:?}");
-                let additional = to_add.len().try_into().unwrap();
-                let start = NEXT_USER_ID.fetch_add(additional, Relaxed);
-                let ids = (start..start + additional).map(UserId);
-                USERS_TO_ADD
-                    .lock()
-                    .extend(ids.clone().zip(to_add).map(User::from));
-                Ok(ids.collect())
-            },
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_command() {
+        let dir = std::env::temp_dir().join("sporks-test-generate-command");
+        std::fs::create_dir_all(&dir).unwrap();
+        let (slots, tasks, users, output) = (
+            dir.join("slots.json"),
+            dir.join("tasks.json"),
+            dir.join("users.json"),
+            dir.join("schedule.json"),
+        );
+        _ = std::fs::remove_file(&output);
+        std::fs::write(
+            &slots,
+            serde_json::to_string(&slots! {
+                0: 4/12/2025 @ 9:00 - 4/12/2025 @ 17:00 [1] | "a",
+            })
+            .unwrap(),
+        )
+        .unwrap();
+        std::fs::write(
+            &tasks,
+            serde_json::to_string(&<TaskMap>::default()).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(
+            &users,
+            serde_json::to_string(&users! {
+                0: "bob" {
+                    0: 4/12/2025 - 4/13/2025 | 1.0,
+                },
+            })
+            .unwrap(),
+        )
+        .unwrap();
+
+        let cli = Cli::try_parse_from([
+            "srv".as_ref(),
+            "generate".as_ref(),
+            "-u".as_ref(),
+            users.as_os_str(),
+            "-t".as_ref(),
+            tasks.as_os_str(),
+            "-s".as_ref(),
+            slots.as_os_str(),
+            "-o".as_ref(),
+            output.as_os_str(),
+        ])
+        .unwrap();
+        assert_eq!(cli.command, Some(Command::Generate));
+
+        let (slots, tasks, users) = load_all(&cli).unwrap();
+        generate(&cli, &slots, &tasks, &users).unwrap();
+        let schedule = serde_json::from_str::<algo::Schedule>(
+            &std::fs::read_to_string(&output).expect("output file should be produced"),
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            schedule.0[&SlotId(0)],
+            UserSet::from_iter([UserId(0)]),
+            "bob should be scheduled"
         );
     }
 
-    // add_tasks
-    {
-        /// Python requirements for constructing a [`Task`]
-        #[derive(Debug, Serialize, Deserialize)]
-        pub struct PyTask {
-            title: String,
-            desc: Option<String>,
-            deadline: Option<DateTime<Utc>>,
-            awaiting: Option<Vec<TaskId>>,
-        }
-
-        impl From<(TaskId, PyTask)> for Task {
-            #[inline]
-            fn from((id, task): (TaskId, PyTask)) -> Self {
-                let PyTask {
-                    title, deadline, ..
-                } = task;
-                Task {
-                    id,
-                    title,
-                    desc: task.desc.unwrap_or_default(),
-                    skills: FxHashMap::default(),
-                    deadline,
-                    deps: task.awaiting.map(FxHashSet::from_iter).unwrap_or_default(),
-                }
-            }
-        }
-
-        #[derive(Debug, Serialize, Deserialize)]
-        struct AddTasksParams {
-            to_add: Vec<PyTask>,
-        }
+    #[test]
+    fn test_quiet_suppresses_lifecycle() {
+        let cli = Cli::try_parse_from(["srv", "-q"]).unwrap();
+        assert!(cli.quiet);
+        let mut out = Vec::new();
+        drop(RunningHandle::init_to(cli.quiet, &mut out));
+        assert!(
+            out.is_empty(),
+            "quiet server should not print running/closed"
+        );
 
-        server.register_simple(
-            "add_tasks",
-            |AddTasksParams { to_add }: AddTasksParams| -> Result<Vec<TaskId>, Fault> {
-                println!("srv: recieved tasks: {to_add:?}");
-                let additional = to_add.len().try_into().unwrap();
-                let start = NEXT_TASK_ID.fetch_add(additional, Relaxed);
-                let ids = (start..start + additional).map(TaskId);
-                TASKS_TO_ADD
-                    .lock()
-                    .extend(ids.clone().zip(to_add).map(Task::from));
-                Ok(ids.collect())
-            },
+        let cli = Cli::try_parse_from(["srv"]).unwrap();
+        let mut out = Vec::new();
+        drop(RunningHandle::init_to(cli.quiet, &mut out));
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "srv: running\nsrv: closed\n"
         );
     }
-=======
-    integration::register(&mut server);
->>>>>>> 04a1808e76feb61ebfb644cf6eff190bd1c24f5a
 
-    let bound_server = server.bind(&socket).unwrap();
-    let _marker = RunningHandle::init();
-    loop {
-        bound_server.poll();
-<<<<<<< HEAD
-
-        {
-            let mut tasks_to_add = TASKS_TO_ADD.lock();
-            if !tasks_to_add.is_empty() {
-                println!("srv: adding tasks: {tasks_to_add:?}");
-                tasks.extend(
-                    std::mem::take(&mut *tasks_to_add)
-                        .into_iter()
-                        .map(|task| (task.id, task)),
-                );
-            }
-        }
-
-        {
-            let mut users_to_add = USERS_TO_ADD.lock();
-            if !users_to_add.is_empty() {
-                println!("srv: adding users: {users_to_add:?}");
-                users.extend(
-                    std::mem::take(&mut *users_to_add)
-                        .into_iter()
-                        .map(|user| (user.id, user)),
-                );
-            }
-        }
-
-        if EXIT_REQUESTED.load(Relaxed) {
-            break;
-        }
+    #[test]
+    fn test_format_overrides_extension() {
+        let path = std::env::temp_dir().join("sporks-test-format-override-slots.csv");
+        let slots = slots! {
+            0: 4/12/2025 - 4/13/2025 [1] | "a",
+        };
+        std::fs::write(&path, serde_json::to_string(&slots).unwrap()).unwrap();
+
+        let cli = Cli::try_parse_from([
+            "srv".as_ref(),
+            "--format".as_ref(),
+            "json".as_ref(),
+            "--slots".as_ref(),
+            path.as_os_str(),
+        ])
+        .unwrap();
+        assert_eq!(Format::infer(&cli.slots), Format::Csv);
+        assert_eq!(cli.format_of(&cli.slots), Format::Json);
+
+        let loaded = try_load::<Slot>(&cli.slots, cli.format_of(&cli.slots), "slot");
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            loaded
+                .unwrap()
+                .into_values()
+                .map(|slot| slot.name)
+                .collect::<Vec<_>>(),
+            ["a"],
+            "a .csv path should be parsed as JSON when --format json is given"
+        );
     }
-    Ok(())
-=======
-        if EXIT_REQUESTED.load(Relaxed) {
-            break Ok(());
-        }
+
+    #[test]
+    fn test_config_file() {
+        let path = std::env::temp_dir().join("sporks-test-config.json");
+        std::fs::write(
+            &path,
+            serde_json::json!({
+                "slots": "shifts.json",
+                "format": "csv",
+                "max_occurrences": 5,
+                "bind": "0.0.0.0:9000",
+                "decay": { "half_life": 86400 },
+                "seed": 42,
+                "normalize": true,
+                "min_rest": 3600,
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let matches = Cli::command()
+            .try_get_matches_from([
+                "srv".as_ref(),
+                "--config".as_ref(),
+                path.as_os_str(),
+                "--max-occurrences".as_ref(),
+                "7".as_ref(),
+                "--min-rest".as_ref(),
+                "7200".as_ref(),
+            ])
+            .unwrap();
+        let cli = Cli::from_matches(&matches);
+        std::fs::remove_file(&path).unwrap();
+        let cli = cli.unwrap();
+
+        assert_eq!(cli.slots, PathBuf::from("shifts.json"));
+        assert_eq!(cli.format, Some(Format::Csv));
+        assert_eq!(cli.bind, "0.0.0.0:9000".parse().unwrap());
+        let config = cli.scheduler_config();
+        assert_eq!(
+            config.decay.map(|decay| decay.half_life),
+            Some(chrono::TimeDelta::days(1))
+        );
+        assert_eq!(config.seed, Some(42));
+        assert!(config.normalize);
+        assert_eq!(
+            config.min_rest,
+            Some(chrono::TimeDelta::hours(2)),
+            "the command line should win"
+        );
+        assert_eq!(cli.max_occurrences, 7, "the command line should win");
+        assert_eq!(
+            cli.users,
+            PathBuf::from("./users.csv"),
+            "unset fields should keep their defaults"
+        );
     }
->>>>>>> 04a1808e76feb61ebfb644cf6eff190bd1c24f5a
 }